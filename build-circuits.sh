@@ -73,15 +73,6 @@ for CIRCUIT_CONFIG in "${CIRCUITS[@]}"; do
     echo -e "${GREEN}  ✓ ${CIRCUIT_NAME} circuit built${NC}"
 done
 
-# Step 5: Convert withdraw circuit verifying key to Rust for Solana
-echo -e "${YELLOW}Converting withdraw verifying key to Rust...${NC}"
-if [ -f "scripts/parse_vk_to_rust.js" ]; then
-    node scripts/parse_vk_to_rust.js build/withdraw/groth16_vkey.json src/
-    echo -e "${GREEN}✓ Verifying key converted to src/verifying_key.rs${NC}"
-else
-    echo -e "${RED}parse_vk_to_rust.js not found in scripts/. Please ensure it's available.${NC}"
-fi
-
 # Copy final keys to trusted-setup
 echo -e "${YELLOW}Copying final keys to trusted-setup...${NC}"
 for CIRCUIT_CONFIG in "${CIRCUITS[@]}"; do
@@ -90,6 +81,11 @@ for CIRCUIT_CONFIG in "${CIRCUITS[@]}"; do
     cp "build/${CIRCUIT_NAME}/groth16_vkey.json" "trusted-setup/final-keys/${CIRCUIT_NAME}_vkey.json"
 done
 
+# Step 5: Convert the verifying keys to Rust for Solana
+echo -e "${YELLOW}Converting verifying keys to Rust...${NC}"
+cargo run --quiet --bin generate-verifying-keys --features client
+echo -e "${GREEN}✓ Verifying keys converted to src/crypto/circuit_keys.rs${NC}"
+
 echo -e "${GREEN}All circuits built successfully!${NC}"
 echo ""
 echo "Build artifacts:"
@@ -97,4 +93,4 @@ echo "  - R1CS files: build/*/[circuit].r1cs"
 echo "  - WASM files: build/*/[circuit]_js/"
 echo "  - Proving keys: build/*/groth16_pkey.zkey"
 echo "  - Verification keys: build/*/groth16_vkey.json"
-echo "  - Rust verifying key: src/verifying_key.rs"
\ No newline at end of file
+echo "  - Rust verifying keys: src/crypto/circuit_keys.rs"
\ No newline at end of file
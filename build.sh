@@ -9,7 +9,7 @@ NC='\033[0m' # No Color
 echo -e "${GREEN}Building Solana Privacy Pools...${NC}"
 
 # Step 1: Build circuits if needed
-if [ ! -f "src/verifying_key.rs" ]; then
+if [ ! -f "trusted-setup/final-keys/withdraw_vkey.json" ]; then
     echo -e "${YELLOW}Verifying key not found. Building circuits first...${NC}"
     ./build-circuits.sh
 fi
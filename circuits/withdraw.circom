@@ -111,4 +111,5 @@ template Withdraw(maxTreeDepth) {
   signal contextSquared <== context * context;
 }
 
-component main = Withdraw(20);
+// Outputs come first, then these inputs: the 8 public signals the program verifies
+component main {public [withdrawnValue, stateRoot, stateTreeDepth, ASPRoot, ASPTreeDepth, context]} = Withdraw(20);
@@ -0,0 +1,29 @@
+#!/usr/bin/env node
+
+// Prints the roots pinned by test_lean_imt_update_and_remove_vectors in src/crypto/merkle_tree.rs
+// Leaves 1..7, then update(4, 42), remove(6) and remove(0) on the same tree
+
+const { buildPoseidon } = require('circomlibjs');
+const { LeanIMT } = require('@zk-kit/lean-imt');
+
+async function main() {
+    const poseidon = await buildPoseidon();
+    const hash = (a, b) => poseidon.F.toObject(poseidon([a, b]));
+
+    const tree = new LeanIMT(hash, [1n, 2n, 3n, 4n, 5n, 6n, 7n]);
+    console.log('initial:    ', tree.root.toString());
+
+    tree.update(4, 42n);
+    console.log('update(4):  ', tree.root.toString());
+
+    tree.remove(6);
+    console.log('remove(6):  ', tree.root.toString());
+
+    tree.remove(0);
+    console.log('remove(0):  ', tree.root.toString());
+}
+
+main().catch((error) => {
+    console.error(error);
+    process.exit(1);
+});
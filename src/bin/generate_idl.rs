@@ -0,0 +1,25 @@
+//! Regenerates `idl/privacy_pools.json` from the instruction and account definitions
+//!
+//! Usage: cargo run --bin generate-idl --features client [-- <output file>]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use solana_privacy_pools::client::idl::{generate_idl_json, IDL_PATH};
+
+fn main() {
+    let output = PathBuf::from(std::env::args().nth(1).unwrap_or_else(|| IDL_PATH.to_string()));
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Err(err) = fs::create_dir_all(parent) {
+            eprintln!("error: cannot create {}: {}", parent.display(), err);
+            process::exit(1);
+        }
+    }
+    if let Err(err) = fs::write(&output, generate_idl_json()) {
+        eprintln!("error: cannot write {}: {}", output.display(), err);
+        process::exit(1);
+    }
+    println!("wrote {}", output.display());
+}
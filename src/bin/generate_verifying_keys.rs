@@ -0,0 +1,51 @@
+//! Regenerates `src/crypto/circuit_keys.rs` from `trusted-setup/final-keys/*_vkey.json`
+//!
+//! Usage: cargo run --bin generate-verifying-keys --features client [-- <keys dir> <output file>]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use solana_privacy_pools::client::vkey::{generate_circuit_keys_source, VerifyingKeyBytes};
+
+const DEFAULT_KEYS_DIR: &str = "trusted-setup/final-keys";
+const DEFAULT_OUTPUT: &str = "src/crypto/circuit_keys.rs";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let keys_dir = PathBuf::from(args.next().unwrap_or_else(|| DEFAULT_KEYS_DIR.to_string()));
+    let output = PathBuf::from(args.next().unwrap_or_else(|| DEFAULT_OUTPUT.to_string()));
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&keys_dir)
+        .unwrap_or_else(|err| fail(&format!("cannot read {}: {}", keys_dir.display(), err)))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.to_string_lossy().ends_with("_vkey.json"))
+        .collect();
+    // Sorted so the output doesn't depend on directory order
+    paths.sort();
+
+    let mut keys = Vec::new();
+    for path in paths {
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        let circuit = file_name.trim_end_matches("_vkey.json").to_string();
+        let json = fs::read_to_string(&path)
+            .unwrap_or_else(|err| fail(&format!("cannot read {}: {}", path.display(), err)));
+        let key = VerifyingKeyBytes::from_snarkjs_json(&json)
+            .unwrap_or_else(|err| fail(&format!("{}: {}", path.display(), err)));
+        println!("{}: {} public inputs", circuit, key.nr_pubinputs);
+        keys.push((circuit, key));
+    }
+
+    if keys.is_empty() {
+        fail(&format!("no *_vkey.json files in {}", keys_dir.display()));
+    }
+
+    fs::write(&output, generate_circuit_keys_source(&keys))
+        .unwrap_or_else(|err| fail(&format!("cannot write {}: {}", output.display(), err)));
+    println!("wrote {}", output.display());
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
@@ -0,0 +1,353 @@
+//! `privacy-pools` command-line client
+//!
+//! Everything runs offline: transactions are printed as unsigned base64 for any signer,
+//! and pool state is read from account data dumped by the caller.
+//!
+//! Usage: cargo run --bin privacy-pools --features client -- <command> [--option value]...
+//! Run without arguments for the list of commands.
+
+use std::collections::HashMap;
+use std::fs;
+use std::process;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_program::hash::Hash;
+use solana_program::pubkey::Pubkey;
+
+use solana_privacy_pools::client::accounts::{PoolAccount, ProgramAccount};
+use solana_privacy_pools::client::backup::NoteBackup;
+use solana_privacy_pools::client::hex;
+use solana_privacy_pools::client::instructions;
+use solana_privacy_pools::client::keys::MasterKeys;
+use solana_privacy_pools::client::snarkjs;
+use solana_privacy_pools::client::transaction::unsigned_transaction_base64;
+use solana_privacy_pools::client::witness::WithdrawWitnessInputs;
+use solana_privacy_pools::constants::ROOT_HISTORY_SIZE;
+use solana_privacy_pools::crypto::merkle_tree::LeanIMT;
+use solana_privacy_pools::crypto::poseidon;
+use solana_privacy_pools::instructions::WithdrawalData;
+use solana_privacy_pools::state::lean_imt::MAX_TREE_DEPTH;
+
+const USAGE: &str = "\
+usage: privacy-pools <command> [--option value]...
+
+Transactions are printed as unsigned base64; 32-byte values are hex.
+
+commands:
+  init             --program --pool --authority --entrypoint --mint --blockhash
+                   [--max-depth] [--root-history-capacity] [--root-expiry-slots] [--payer]
+  deposit          --program --pool --depositor-account --depositor --value --scope --index
+                   (--seed-file | --wallet-signature) --blockhash [--queue] [--payer]
+  note             --scope --index --value --label --leaf-index --signature
+                   (--seed-file | --wallet-signature)
+  withdraw-inputs  --note --snapshot --amount --processooor (--seed-file | --wallet-signature)
+                   [--data] [--change-index]
+  withdraw         --program --pool --nullifier-account --processooor --proof --public
+                   --blockhash [--data] [--payer]
+  ragequit         --program --pool --depositor-account --ragequitter --nullifier-account
+                   --proof --public --blockhash [--payer]
+  decode           --account [--base64]     (pool, nullifier or depositor account)
+  roots            --account [--base64]
+
+A snapshot is a JSON file {\"state_leaves\": [hex...], \"asp_leaves\": [hex...]} in insertion order.";
+
+struct Args {
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument {}", arg))?;
+            // `--base64` is the only switch, everything else takes a value
+            let value = if name == "base64" {
+                String::new()
+            } else {
+                iter.next().ok_or_else(|| format!("--{} needs a value", name))?.clone()
+            };
+            options.insert(name.to_string(), value);
+        }
+        Ok(Self { options })
+    }
+
+    fn get(&self, name: &str) -> Result<&str, String> {
+        self.options
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing --{}", name))
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn parse_value<T: FromStr>(&self, name: &str) -> Result<T, String> {
+        self.get(name)?.parse().map_err(|_| format!("invalid --{}", name))
+    }
+
+    fn parse_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        if self.has(name) {
+            self.parse_value(name)
+        } else {
+            Ok(default)
+        }
+    }
+
+    fn pubkey(&self, name: &str) -> Result<Pubkey, String> {
+        self.parse_value(name)
+    }
+
+    fn payer_or(&self, default: &Pubkey) -> Result<Pubkey, String> {
+        self.parse_or("payer", *default)
+    }
+
+    fn blockhash(&self) -> Result<Hash, String> {
+        self.parse_value("blockhash")
+    }
+
+    fn bytes32(&self, name: &str) -> Result<[u8; 32], String> {
+        parse_bytes32(self.get(name)?).map_err(|err| format!("--{}: {}", name, err))
+    }
+
+    fn master_keys(&self) -> Result<MasterKeys, String> {
+        if self.has("wallet-signature") {
+            let signature = bs58::decode(self.get("wallet-signature")?)
+                .into_vec()
+                .ok()
+                .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+                .ok_or("--wallet-signature must be a base58 ed25519 signature")?;
+            return Ok(MasterKeys::from_signature(&signature));
+        }
+
+        let path = self.get("seed-file")?;
+        let seed = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+        let seed = from_hex(seed.trim()).map_err(|err| format!("{}: {}", path, err))?;
+        MasterKeys::from_seed(&seed).map_err(|err| err.to_string())
+    }
+
+    fn account_data(&self) -> Result<Vec<u8>, String> {
+        let path = self.get("account")?;
+        let data = fs::read(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+        if !self.has("base64") {
+            return Ok(data);
+        }
+
+        use base64::{engine::general_purpose::STANDARD, Engine};
+        let text = String::from_utf8(data).map_err(|_| format!("{} is not base64", path))?;
+        STANDARD
+            .decode(text.trim())
+            .map_err(|_| format!("{} is not base64", path))
+    }
+
+    fn proof_files(&self) -> Result<(String, String), String> {
+        let read = |name: &str| -> Result<String, String> {
+            let path = self.get(name)?;
+            fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))
+        };
+        Ok((read("proof")?, read("public")?))
+    }
+}
+
+#[derive(Deserialize)]
+struct TreeSnapshot {
+    state_leaves: Vec<String>,
+    asp_leaves: Vec<String>,
+}
+
+impl TreeSnapshot {
+    fn tree(leaves: &[String]) -> Result<LeanIMT, String> {
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        for leaf in leaves {
+            tree.insert(parse_bytes32(leaf)?)?;
+        }
+        Ok(tree)
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        println!("{}", USAGE);
+        return;
+    };
+
+    let result = Args::parse(rest).and_then(|args| match command.as_str() {
+        "init" => init(&args),
+        "deposit" => deposit(&args),
+        "note" => note(&args),
+        "withdraw-inputs" => withdraw_inputs(&args),
+        "withdraw" => withdraw(&args),
+        "ragequit" => ragequit(&args),
+        "decode" => decode(&args),
+        "roots" => roots(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("unknown command {}\n\n{}", other, USAGE)),
+    });
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn init(args: &Args) -> Result<(), String> {
+    let authority = args.pubkey("authority")?;
+    let instruction = instructions::initialize_pool(
+        &args.pubkey("program")?,
+        &args.pubkey("pool")?,
+        &authority,
+        &args.pubkey("entrypoint")?,
+        args.parse_or("max-depth", MAX_TREE_DEPTH as u8)?,
+        &args.pubkey("mint")?,
+        args.parse_or("root-history-capacity", ROOT_HISTORY_SIZE as u32)?,
+        args.parse_or("root-expiry-slots", 0u64)?,
+    )?;
+
+    println!("{}", unsigned_transaction_base64(&[instruction], &args.payer_or(&authority)?, &args.blockhash()?));
+    Ok(())
+}
+
+fn deposit(args: &Args) -> Result<(), String> {
+    let keys = args.master_keys()?;
+    let scope = args.bytes32("scope")?;
+    let index: u64 = args.parse_value("index")?;
+    let secrets = keys.deposit_secrets(&scope, index);
+
+    let depositor = args.pubkey("depositor")?;
+    let queue = if args.has("queue") { Some(args.pubkey("queue")?) } else { None };
+    let instruction = instructions::deposit(
+        &args.pubkey("program")?,
+        &args.pubkey("pool")?,
+        &args.pubkey("depositor-account")?,
+        &depositor,
+        args.parse_value("value")?,
+        secrets.precommitment(),
+        queue.as_ref(),
+    )?;
+
+    print_json(&json!({
+        "deposit_index": index,
+        "precommitment": hex::encode(&secrets.precommitment()),
+        "transaction": unsigned_transaction_base64(&[instruction], &args.payer_or(&depositor)?, &args.blockhash()?),
+    }));
+    Ok(())
+}
+
+/// Export a confirmed deposit as a note string, once its label and leaf index are known
+fn note(args: &Args) -> Result<(), String> {
+    let keys = args.master_keys()?;
+    let scope = args.bytes32("scope")?;
+    let note = keys
+        .deposit_secrets(&scope, args.parse_value("index")?)
+        .into_note(args.parse_value("value")?, args.bytes32("label")?);
+    let backup = NoteBackup::new(scope, note, args.parse_value("leaf-index")?, args.get("signature")?.to_string());
+
+    print_json(&json!({
+        "commitment": hex::encode(&note.commitment()),
+        "note": backup.to_note_string().map_err(|err| err.to_string())?,
+    }));
+    Ok(())
+}
+
+/// Circuit inputs for a withdrawal, to be proven with snarkjs or the native prover
+fn withdraw_inputs(args: &Args) -> Result<(), String> {
+    let backup = NoteBackup::from_note_string(args.get("note")?).map_err(|err| err.to_string())?;
+    let path = args.get("snapshot")?;
+    let snapshot: TreeSnapshot = serde_json::from_str(
+        &fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?,
+    )
+    .map_err(|err| format!("{}: {}", path, err))?;
+
+    let withdrawal = WithdrawalData {
+        processooor: args.pubkey("processooor")?.to_bytes(),
+        data: from_hex(args.options.get("data").map(String::as_str).unwrap_or(""))?,
+    };
+    let change = args
+        .master_keys()?
+        .withdrawal_secrets(&backup.note.label, args.parse_or("change-index", 0u64)?);
+
+    let inputs = WithdrawWitnessInputs::builder(backup.note)
+        .withdrawn_value(args.parse_value("amount")?)
+        .context(poseidon::compute_context(&withdrawal, &backup.scope))
+        .new_note_secrets(change.nullifier, change.secret)
+        .state_tree(&TreeSnapshot::tree(&snapshot.state_leaves)?)?
+        .asp_tree(&TreeSnapshot::tree(&snapshot.asp_leaves)?)?
+        .build()?;
+
+    println!("{}", inputs.to_json_string());
+    Ok(())
+}
+
+fn withdraw(args: &Args) -> Result<(), String> {
+    let (proof_json, public_json) = args.proof_files()?;
+    let proof = snarkjs::withdraw_proof_from_snarkjs(&proof_json, &public_json)?;
+    let processooor = args.pubkey("processooor")?;
+    let withdrawal = WithdrawalData {
+        processooor: processooor.to_bytes(),
+        data: from_hex(args.options.get("data").map(String::as_str).unwrap_or(""))?,
+    };
+
+    let instruction = instructions::withdraw(
+        &args.pubkey("program")?,
+        &args.pubkey("pool")?,
+        &args.pubkey("nullifier-account")?,
+        withdrawal,
+        proof,
+    )?;
+    println!("{}", unsigned_transaction_base64(&[instruction], &args.payer_or(&processooor)?, &args.blockhash()?));
+    Ok(())
+}
+
+fn ragequit(args: &Args) -> Result<(), String> {
+    let (proof_json, public_json) = args.proof_files()?;
+    let proof = snarkjs::ragequit_proof_from_snarkjs(&proof_json, &public_json)?;
+    let ragequitter = args.pubkey("ragequitter")?;
+
+    let instruction = instructions::ragequit(
+        &args.pubkey("program")?,
+        &args.pubkey("pool")?,
+        &args.pubkey("depositor-account")?,
+        &ragequitter,
+        &args.pubkey("nullifier-account")?,
+        proof,
+    )?;
+    println!("{}", unsigned_transaction_base64(&[instruction], &args.payer_or(&ragequitter)?, &args.blockhash()?));
+    Ok(())
+}
+
+fn decode(args: &Args) -> Result<(), String> {
+    let account = ProgramAccount::decode(&args.account_data()?).map_err(|err| err.to_string())?;
+    print_json(&serde_json::to_value(account).expect("accounts serialize"));
+    Ok(())
+}
+
+fn roots(args: &Args) -> Result<(), String> {
+    let pool = PoolAccount::decode(&args.account_data()?).map_err(|err| err.to_string())?;
+    print_json(&json!({
+        "state_root": hex::encode(&pool.state_tree.root),
+        "asp_root": hex::encode(&pool.asp_tree.root),
+        "root_history": pool.root_history.roots,
+    }));
+    Ok(())
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).expect("JSON values serialize"));
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value).ok_or_else(|| format!("invalid hex {}", value))
+}
+
+fn parse_bytes32(value: &str) -> Result<[u8; 32], String> {
+    hex::decode32(value).ok_or_else(|| format!("expected 32 bytes of hex, got {}", value))
+}
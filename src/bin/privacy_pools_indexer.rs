@@ -0,0 +1,150 @@
+//! `privacy-pools-indexer`: index one pool into SQLite and serve proofs over HTTP
+//!
+//! Usage: cargo run --bin privacy-pools-indexer --features sqlite -- --program <id> --pool <key>
+//!        --db <path> (--dump <file> | --rpc <url>) [--scope <hex>] [--listen <addr>]
+//!        [--poll-seconds <n>] [--once]
+//!
+//! `--dump` reads `getTransaction` results, one per line; `--rpc` polls a local validator.
+//! `--once` indexes what is available, prints the stats and exits instead of serving.
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::process;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use pinocchio::pubkey::Pubkey;
+
+use solana_privacy_pools::client::indexer::http;
+use solana_privacy_pools::client::indexer::source::{FileSource, RecordSource, RpcSource};
+use solana_privacy_pools::client::indexer::sqlite::SqliteStore;
+use solana_privacy_pools::client::indexer::{IndexError, Indexer};
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+const DEFAULT_POLL_SECONDS: u64 = 5;
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn parse_options(args: &[String]) -> HashMap<String, String> {
+    let mut options = HashMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            fail(format!("unexpected argument {}", arg));
+        };
+        let value = if name == "once" {
+            String::new()
+        } else {
+            iter.next().cloned().unwrap_or_else(|| fail(format!("--{} needs a value", name)))
+        };
+        options.insert(name.to_string(), value);
+    }
+    options
+}
+
+fn required<'a>(options: &'a HashMap<String, String>, name: &str) -> &'a str {
+    options
+        .get(name)
+        .map(String::as_str)
+        .unwrap_or_else(|| fail(format!("missing --{}", name)))
+}
+
+fn pubkey(options: &HashMap<String, String>, name: &str) -> Pubkey {
+    bs58::decode(required(options, name))
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or_else(|| fail(format!("--{} must be a base58 public key", name)))
+}
+
+fn scope(options: &HashMap<String, String>) -> Option<[u8; 32]> {
+    let hex = options.get("scope")?;
+    let bytes: Option<Vec<u8>> = (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect();
+    Some(
+        bytes
+            .and_then(|bytes| bytes.try_into().ok())
+            .unwrap_or_else(|| fail("--scope must be 32 bytes of hex")),
+    )
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args[0] == "--help" {
+        println!("usage: privacy-pools-indexer --program <id> --pool <key> --db <path> (--dump <file> | --rpc <url>)");
+        println!("       [--scope <hex>] [--listen <addr>] [--poll-seconds <n>] [--once]");
+        return;
+    }
+
+    let options = parse_options(&args);
+    let program_id = pubkey(&options, "program");
+    let pool = pubkey(&options, "pool");
+
+    let mut source: Box<dyn RecordSource + Send> = match (options.get("dump"), options.get("rpc")) {
+        (Some(path), None) => Box::new(FileSource::new(path)),
+        (None, Some(url)) => Box::new(RpcSource::new(url.as_str())),
+        _ => fail("pass exactly one of --dump and --rpc"),
+    };
+
+    let db = required(&options, "db");
+    let mut store = SqliteStore::open(db).unwrap_or_else(|err| fail(format!("cannot open {}: {}", db, err)));
+    let mut indexer = Indexer::open(program_id, pool, scope(&options), &store).unwrap_or_else(|err| fail(err));
+
+    let indexed = indexer.sync(source.as_mut(), &mut store).unwrap_or_else(|err| fail(err));
+    eprintln!("indexed {} new transactions", indexed);
+
+    if options.contains_key("once") {
+        let stats = http::route(&indexer, "GET", "/stats");
+        println!("{}", serde_json::to_string_pretty(&stats.body).expect("JSON values serialize"));
+        return;
+    }
+
+    let listen = options.get("listen").map(String::as_str).unwrap_or(DEFAULT_LISTEN);
+    let listener = TcpListener::bind(listen).unwrap_or_else(|err| fail(format!("cannot listen on {}: {}", listen, err)));
+    let poll = Duration::from_secs(
+        options
+            .get("poll-seconds")
+            .map(|seconds| seconds.parse().unwrap_or_else(|_| fail("invalid --poll-seconds")))
+            .unwrap_or(DEFAULT_POLL_SECONDS),
+    );
+
+    let indexer = Arc::new(RwLock::new(indexer));
+    let syncing = Arc::clone(&indexer);
+    thread::spawn(move || loop {
+        thread::sleep(poll);
+        if let Err(err) = poll_once(&syncing, source.as_mut(), &mut store, &pool) {
+            // The in-memory index may be ahead of the database now; restart to reload it
+            fail(err);
+        }
+    });
+
+    eprintln!("serving on http://{}", listen);
+    if let Err(err) = http::serve(&listener, &indexer) {
+        fail(err);
+    }
+}
+
+/// Fetch without holding the lock, so HTTP requests aren't blocked on the source
+///
+/// A poisoned lock is fatal here too, as in `http::serve`: the index may be half updated.
+fn poll_once(
+    indexer: &RwLock<Indexer>,
+    source: &mut dyn RecordSource,
+    store: &mut SqliteStore,
+    pool: &Pubkey,
+) -> Result<(), IndexError> {
+    let until = indexer.read().unwrap_or_else(|_| fail("indexer lock poisoned")).last_signature().map(str::to_string);
+    let records = source.records(pool, until.as_deref())?;
+
+    let indexed = indexer.write().unwrap_or_else(|_| fail("indexer lock poisoned")).index_records(&records, store)?;
+    if indexed > 0 {
+        eprintln!("indexed {} new transactions", indexed);
+    }
+    Ok(())
+}
@@ -0,0 +1,467 @@
+//! Typed decoding of program accounts
+//!
+//! Copies account data into owned structs instead of casting it in place, so RPC responses
+//! and file dumps can be inspected without `unsafe`. The accounts carry no discriminator;
+//! they are told apart by size and validated field by field. Every type serializes to JSON
+//! with 32-byte values as hex and keys as base58.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::frontier::LeanIMTFrontier;
+use crate::crypto::merkle_tree::LeanIMT;
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+use crate::state::{
+    DepositorStateZC, LeanIMTStateZC, NullifierStateZC, PoolStateLeanIMT, RootHistory, RootHistoryEntry,
+    RootHistoryHeader,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountError {
+    /// Data size doesn't match the account type
+    InvalidLength { expected: usize, actual: usize },
+    NotInitialized,
+    /// A flag or counter holds a value the program never writes
+    InvalidField { field: &'static str },
+    /// The root history after the pool state is inconsistent with its header
+    InvalidRootHistory,
+    /// The size matches none of the program's accounts
+    UnknownAccount { len: usize },
+}
+
+impl fmt::Display for AccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes of account data, got {}", expected, actual)
+            }
+            AccountError::NotInitialized => write!(f, "account is not initialized"),
+            AccountError::InvalidField { field } => write!(f, "invalid value for {}", field),
+            AccountError::InvalidRootHistory => write!(f, "corrupt root history"),
+            AccountError::UnknownAccount { len } => write!(f, "no account type is {} bytes long", len),
+        }
+    }
+}
+
+impl std::error::Error for AccountError {}
+
+/// A `LeanIMTStateZC` embedded in a pool or shard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeAccount {
+    pub size: u64,
+    pub depth: u32,
+    #[serde(with = "hex32")]
+    pub root: [u8; 32],
+    /// `side_nodes[0..=depth]`, the frontier needed to keep appending
+    #[serde(with = "hex32_vec")]
+    pub side_nodes: Vec<[u8; 32]>,
+    /// Leaves the program keeps for duplicate checks (the first 1024 only)
+    #[serde(with = "hex32_vec")]
+    pub leaves: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootEntry {
+    #[serde(with = "hex32")]
+    pub root: [u8; 32],
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootHistoryAccount {
+    pub capacity: u32,
+    pub expiry_slots: u64,
+    /// Total number of roots ever pushed
+    pub count: u64,
+    /// Roots still in the buffer, newest first
+    pub roots: Vec<RootEntry>,
+}
+
+/// `PoolStateLeanIMT` followed by its root history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolAccount {
+    #[serde(with = "base58")]
+    pub authority: [u8; 32],
+    #[serde(with = "base58")]
+    pub asset_mint: [u8; 32],
+    #[serde(with = "base58")]
+    pub entrypoint: [u8; 32],
+    #[serde(with = "base58")]
+    pub withdrawal_verifier: [u8; 32],
+    #[serde(with = "hex32")]
+    pub scope: [u8; 32],
+    pub nonce: u64,
+    pub is_dead: bool,
+    pub shard_count: u16,
+    /// Deposit queue attached to the pool, if any
+    #[serde(with = "base58_option")]
+    pub deposit_queue: Option<[u8; 32]>,
+    pub state_tree: TreeAccount,
+    pub asp_tree: TreeAccount,
+    pub root_history: RootHistoryAccount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierAccount {
+    pub is_spent: bool,
+    #[serde(with = "hex32")]
+    pub nullifier_hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositorAccount {
+    #[serde(with = "base58")]
+    pub depositor: [u8; 32],
+    #[serde(with = "hex32")]
+    pub label: [u8; 32],
+}
+
+/// Any account owned by the program, identified by its size
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgramAccount {
+    Pool(Box<PoolAccount>),
+    Nullifier(NullifierAccount),
+    Depositor(DepositorAccount),
+}
+
+impl ProgramAccount {
+    pub fn decode(data: &[u8]) -> Result<Self, AccountError> {
+        match data.len() {
+            NullifierStateZC::LEN => NullifierAccount::decode(data).map(ProgramAccount::Nullifier),
+            DepositorStateZC::LEN => DepositorAccount::decode(data).map(ProgramAccount::Depositor),
+            len if len >= PoolStateLeanIMT::space(1) => PoolAccount::decode(data).map(|pool| ProgramAccount::Pool(Box::new(pool))),
+            len => Err(AccountError::UnknownAccount { len }),
+        }
+    }
+}
+
+impl TreeAccount {
+    fn decode(tree: &LeanIMTStateZC, name: &'static str) -> Result<Self, AccountError> {
+        let (size, depth, leaf_count) = (tree.size, tree.depth, tree.leaf_count);
+        if depth as usize > MAX_TREE_DEPTH || size > 1u64 << depth {
+            return Err(AccountError::InvalidField { field: name });
+        }
+        if leaf_count != size.min(1024) {
+            return Err(AccountError::InvalidField { field: name });
+        }
+
+        let side_nodes = tree.side_nodes;
+        let leaves = tree.leaf_indices;
+        Ok(Self {
+            size,
+            depth,
+            root: tree.root(),
+            side_nodes: side_nodes[..=depth as usize].to_vec(),
+            leaves: leaves[..leaf_count as usize].to_vec(),
+        })
+    }
+
+    /// The frontier to seed an off-chain tree that keeps appending from this one
+    pub fn frontier(&self) -> Result<LeanIMTFrontier, &'static str> {
+        LeanIMTFrontier::from_parts(self.size, self.side_nodes.clone())
+    }
+
+    /// Whether `tree` has exactly the size and frontier the program stored
+    pub fn matches(&self, tree: &LeanIMT) -> bool {
+        self.frontier().is_ok_and(|frontier| frontier == tree.frontier())
+    }
+}
+
+impl PoolAccount {
+    pub fn decode(data: &[u8]) -> Result<Self, AccountError> {
+        if data.len() < PoolStateLeanIMT::space(1) {
+            return Err(AccountError::InvalidLength {
+                expected: PoolStateLeanIMT::space(1),
+                actual: data.len(),
+            });
+        }
+
+        let state = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const PoolStateLeanIMT) };
+        if state.is_initialized == 0 {
+            return Err(AccountError::NotInitialized);
+        }
+        if state.is_initialized != 1 {
+            return Err(AccountError::InvalidField { field: "is_initialized" });
+        }
+
+        Ok(Self {
+            authority: state.authority,
+            asset_mint: state.asset_mint,
+            entrypoint: state.entrypoint,
+            withdrawal_verifier: state.withdrawal_verifier,
+            scope: state.scope,
+            nonce: state.nonce,
+            is_dead: flag(state.is_dead, "is_dead")?,
+            shard_count: state.shard_count,
+            deposit_queue: state.has_deposit_queue().then_some(state.deposit_queue),
+            state_tree: TreeAccount::decode(&state.state_tree, "state_tree")?,
+            asp_tree: TreeAccount::decode(&state.asp_tree, "asp_tree")?,
+            root_history: RootHistoryAccount::decode(&data[PoolStateLeanIMT::LEN..])?,
+        })
+    }
+}
+
+impl RootHistoryAccount {
+    fn decode(data: &[u8]) -> Result<Self, AccountError> {
+        let mut bytes = data.to_vec();
+        let history = RootHistory::from_bytes_mut(&mut bytes).map_err(|_| AccountError::InvalidRootHistory)?;
+        let RootHistoryHeader { capacity, expiry_slots, current_index, count, .. } = *history.header;
+
+        let capacity_u64 = capacity as u64;
+        if current_index >= capacity_u64 || current_index != count % capacity_u64 {
+            return Err(AccountError::InvalidRootHistory);
+        }
+
+        let roots = (1..=count.min(capacity_u64))
+            .map(|back| {
+                let RootHistoryEntry { root, slot } = history.entries[((current_index + capacity_u64 - back) % capacity_u64) as usize];
+                RootEntry { root, slot }
+            })
+            .collect();
+
+        Ok(Self { capacity, expiry_slots, count, roots })
+    }
+}
+
+impl NullifierAccount {
+    pub fn decode(data: &[u8]) -> Result<Self, AccountError> {
+        let state: NullifierStateZC = read_exact(data)?;
+        Ok(Self {
+            is_spent: flag(state.is_spent, "is_spent")?,
+            nullifier_hash: state.nullifier_hash,
+        })
+    }
+}
+
+impl DepositorAccount {
+    pub fn decode(data: &[u8]) -> Result<Self, AccountError> {
+        let state: DepositorStateZC = read_exact(data)?;
+        if state.depositor == [0u8; 32] {
+            return Err(AccountError::NotInitialized);
+        }
+
+        Ok(Self {
+            depositor: state.depositor,
+            label: state.label,
+        })
+    }
+}
+
+/// Copy a fixed-size account out of `data`
+///
+/// Only used for the `repr(C, packed)` account structs, whose fields are all plain bytes and
+/// integers, so any bit pattern is a valid value.
+fn read_exact<T: Copy>(data: &[u8]) -> Result<T, AccountError> {
+    let expected = std::mem::size_of::<T>();
+    if data.len() != expected {
+        return Err(AccountError::InvalidLength { expected, actual: data.len() });
+    }
+    Ok(unsafe { std::ptr::read_unaligned(data.as_ptr() as *const T) })
+}
+
+fn flag(value: u8, field: &'static str) -> Result<bool, AccountError> {
+    match value {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(AccountError::InvalidField { field }),
+    }
+}
+
+mod hex32 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        from_hex(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    pub fn to_hex(bytes: &[u8; 32]) -> String {
+        crate::client::hex::encode(bytes)
+    }
+
+    pub fn from_hex(hex: &str) -> Result<[u8; 32], String> {
+        crate::client::hex::decode32(hex).ok_or_else(|| format!("expected 32 bytes of hex, got {}", hex))
+    }
+}
+
+mod hex32_vec {
+    use serde::{de::Error, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values {
+            seq.serialize_element(&super::hex32::to_hex(value))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|hex| super::hex32::from_hex(hex).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+mod base58 {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&bs58::encode(key).into_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let text = String::deserialize(deserializer)?;
+        bs58::decode(&text)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| D::Error::custom(format!("invalid public key {}", text)))
+    }
+}
+
+mod base58_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &Option<[u8; 32]>, serializer: S) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => super::base58::serialize(key, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error> {
+        #[derive(Deserialize)]
+        struct Key(#[serde(with = "super::base58")] [u8; 32]);
+
+        Ok(Option::<Key>::deserialize(deserializer)?.map(|Key(key)| key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio::pubkey::Pubkey;
+
+    fn pool_account_data() -> Vec<u8> {
+        let mut data = vec![0u8; PoolStateLeanIMT::space(4)];
+        let (state_bytes, history_bytes) = data.split_at_mut(PoolStateLeanIMT::LEN);
+        let pool = unsafe { &mut *(state_bytes.as_mut_ptr() as *mut PoolStateLeanIMT) };
+        pool.initialize(Pubkey::from([1u8; 32]), Pubkey::from([2u8; 32]), Pubkey::from([3u8; 32]), Pubkey::from([4u8; 32]), [5u8; 32]);
+
+        let mut roots = RootHistory::initialize(history_bytes, 4, 10).unwrap();
+        for i in 0..6u8 {
+            pool.insert_state_commitment([10 + i; 32], &mut roots, 100 + i as u64).unwrap();
+        }
+        pool.insert_asp_label([9u8; 32]).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_decode_pool_account() {
+        let data = pool_account_data();
+        let pool = PoolAccount::decode(&data).unwrap();
+
+        assert_eq!(pool.authority, [1u8; 32]);
+        assert_eq!(pool.withdrawal_verifier, [4u8; 32]);
+        assert_eq!(pool.scope, [5u8; 32]);
+        assert!(!pool.is_dead);
+        assert_eq!(pool.deposit_queue, None);
+        assert_eq!((pool.state_tree.size, pool.state_tree.depth), (6, 3));
+        assert_eq!(pool.state_tree.side_nodes.len(), 4);
+        assert_eq!(pool.state_tree.leaves[5], [15u8; 32]);
+        assert_eq!(pool.asp_tree.root, [9u8; 32]);
+
+        // Capacity 4 after 6 pushes: the newest 4 roots, newest first
+        let history = &pool.root_history;
+        assert_eq!((history.capacity, history.expiry_slots, history.count), (4, 10, 6));
+        assert_eq!(history.roots.len(), 4);
+        assert_eq!(history.roots[0], RootEntry { root: pool.state_tree.root, slot: 105 });
+        assert_eq!(history.roots[3].slot, 102);
+
+        assert_eq!(ProgramAccount::decode(&data).unwrap(), ProgramAccount::Pool(Box::new(pool.clone())));
+
+        let json = serde_json::to_value(ProgramAccount::Pool(Box::new(pool.clone()))).unwrap();
+        assert_eq!(json["type"], "pool");
+        assert_eq!(json["authority"], bs58::encode([1u8; 32]).into_string());
+        assert_eq!(json["scope"], "05".repeat(32));
+        let parsed: ProgramAccount = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, ProgramAccount::Pool(Box::new(pool)));
+    }
+
+    #[test]
+    fn test_tree_account_frontier() {
+        let pool = PoolAccount::decode(&pool_account_data()).unwrap();
+
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        for i in 0..6u8 {
+            tree.insert([10 + i; 32]).unwrap();
+        }
+        assert!(pool.state_tree.matches(&tree));
+        assert!(!pool.asp_tree.matches(&tree));
+
+        // Seeded from the account, the frontier follows the full tree
+        let mut frontier = pool.state_tree.frontier().unwrap();
+        frontier.insert([20u8; 32]).unwrap();
+        tree.insert([20u8; 32]).unwrap();
+        assert_eq!(frontier.root(), tree.root());
+        assert!(!pool.state_tree.matches(&tree));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_data() {
+        let mut data = pool_account_data();
+        data[0] = 0;
+        assert_eq!(PoolAccount::decode(&data), Err(AccountError::NotInitialized));
+
+        let mut data = pool_account_data();
+        data.push(0);
+        assert_eq!(PoolAccount::decode(&data), Err(AccountError::InvalidRootHistory));
+
+        let mut data = pool_account_data();
+        let is_dead = std::mem::offset_of!(PoolStateLeanIMT, is_dead);
+        data[is_dead] = 2;
+        assert_eq!(PoolAccount::decode(&data), Err(AccountError::InvalidField { field: "is_dead" }));
+
+        let mut data = pool_account_data();
+        let state_size = std::mem::offset_of!(PoolStateLeanIMT, state_tree);
+        data[state_size] = 200;
+        assert_eq!(PoolAccount::decode(&data), Err(AccountError::InvalidField { field: "state_tree" }));
+
+        assert_eq!(
+            ProgramAccount::decode(&[0u8; 40]),
+            Err(AccountError::UnknownAccount { len: 40 })
+        );
+    }
+
+    #[test]
+    fn test_decode_nullifier_and_depositor() {
+        let mut nullifier = vec![1u8];
+        nullifier.extend_from_slice(&[7u8; 32]);
+        assert_eq!(
+            ProgramAccount::decode(&nullifier).unwrap(),
+            ProgramAccount::Nullifier(NullifierAccount { is_spent: true, nullifier_hash: [7u8; 32] })
+        );
+        nullifier[0] = 3;
+        assert_eq!(
+            NullifierAccount::decode(&nullifier),
+            Err(AccountError::InvalidField { field: "is_spent" })
+        );
+        assert!(matches!(
+            NullifierAccount::decode(&nullifier[1..]),
+            Err(AccountError::InvalidLength { expected: 33, actual: 32 })
+        ));
+
+        let mut depositor = [8u8; 32].to_vec();
+        depositor.extend_from_slice(&[9u8; 32]);
+        let decoded = DepositorAccount::decode(&depositor).unwrap();
+        assert_eq!(decoded.label, [9u8; 32]);
+        assert_eq!(ProgramAccount::decode(&depositor).unwrap(), ProgramAccount::Depositor(decoded));
+        assert_eq!(DepositorAccount::decode(&[0u8; 64]), Err(AccountError::NotInitialized));
+    }
+}
@@ -0,0 +1,443 @@
+//! Reference Association Set Provider
+//!
+//! An ASP vouches for a subset of deposits: its tree holds the labels it approved, and a
+//! withdrawal proves its label is in that tree. `AspService` replays the pool's deposits,
+//! runs every label past a list of `DepositPolicy`s, and inserts the approved ones into its
+//! own `LeanIMT`. Each review that approves something produces an `AspRootUpdate`.
+//!
+//! The program has no instruction to publish an ASP root: every pool deposit inserts its
+//! label into the on-chain ASP tree by itself (queued deposits in `ProcessQueue`), and
+//! `Withdraw` only accepts a proof whose `asp_root` is that tree's current root. An
+//! `AspRootUpdate` is therefore what an operator publishes off-chain, e.g. for relayers to
+//! screen the labels of the withdrawals they submit; it is not a Solana instruction.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use pinocchio::pubkey::Pubkey;
+
+use crate::client::scanner::{PoolTransaction, TransactionSource};
+use crate::crypto::merkle_tree::{CircuitMerkleProof, LeanIMT};
+use crate::crypto::poseidon;
+use crate::instructions::PrivacyPoolInstruction;
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+use crate::BorshDeserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AspError {
+    Source(String),
+    /// Instruction data the program would have rejected
+    InvalidTransaction { signature: String },
+    /// The ASP tree rejected an insert
+    Tree(&'static str),
+}
+
+impl fmt::Display for AspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AspError::Source(err) => write!(f, "transaction source failed: {}", err),
+            AspError::InvalidTransaction { signature } => write!(f, "transaction {} is not a valid pool instruction", signature),
+            AspError::Tree(reason) => write!(f, "ASP tree: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AspError {}
+
+/// A pool deposit, as the program recorded it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositEvent {
+    pub signature: String,
+    pub slot: u64,
+    pub depositor: Pubkey,
+    pub value: u64,
+    pub label: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Approve,
+    Reject,
+    /// Not decided yet; the deposit is reviewed again later
+    Defer,
+}
+
+/// One rule a deposit has to pass
+pub trait DepositPolicy {
+    fn review(&self, deposit: &DepositEvent, current_slot: u64) -> Decision;
+}
+
+/// Only approve deposits from these depositors
+#[derive(Debug, Clone, Default)]
+pub struct AllowList(pub HashSet<Pubkey>);
+
+impl DepositPolicy for AllowList {
+    fn review(&self, deposit: &DepositEvent, _current_slot: u64) -> Decision {
+        if self.0.contains(&deposit.depositor) {
+            Decision::Approve
+        } else {
+            Decision::Reject
+        }
+    }
+}
+
+/// Reject deposits from these depositors
+#[derive(Debug, Clone, Default)]
+pub struct DenyList(pub HashSet<Pubkey>);
+
+impl DepositPolicy for DenyList {
+    fn review(&self, deposit: &DepositEvent, _current_slot: u64) -> Decision {
+        if self.0.contains(&deposit.depositor) {
+            Decision::Reject
+        } else {
+            Decision::Approve
+        }
+    }
+}
+
+/// Reject deposits outside `min..=max`
+#[derive(Debug, Clone, Copy)]
+pub struct AmountLimit {
+    pub min: u64,
+    pub max: u64,
+}
+
+impl DepositPolicy for AmountLimit {
+    fn review(&self, deposit: &DepositEvent, _current_slot: u64) -> Decision {
+        if (self.min..=self.max).contains(&deposit.value) {
+            Decision::Approve
+        } else {
+            Decision::Reject
+        }
+    }
+}
+
+/// Defer deposits until `slots` have passed since they landed
+#[derive(Debug, Clone, Copy)]
+pub struct WaitingPeriod {
+    pub slots: u64,
+}
+
+impl DepositPolicy for WaitingPeriod {
+    fn review(&self, deposit: &DepositEvent, current_slot: u64) -> Decision {
+        if current_slot >= deposit.slot.saturating_add(self.slots) {
+            Decision::Approve
+        } else {
+            Decision::Defer
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelStatus {
+    Pending,
+    Approved { leaf_index: u64 },
+    Rejected,
+}
+
+/// A new ASP root, for the operator to publish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AspRootUpdate {
+    pub root: [u8; 32],
+    pub size: u64,
+    pub depth: u8,
+    /// Slot the review ran at
+    pub slot: u64,
+    /// Labels inserted since the previous update, in leaf order
+    pub labels: Vec<[u8; 32]>,
+}
+
+pub struct AspService {
+    scope: [u8; 32],
+    policies: Vec<Box<dyn DepositPolicy + Send + Sync>>,
+    tree: LeanIMT,
+    pending: Vec<DepositEvent>,
+    rejected: HashSet<[u8; 32]>,
+    deposits: HashMap<[u8; 32], DepositEvent>,
+    pool_nonce: u64,
+    shard_nonces: HashMap<u16, u64>,
+    /// Pool instructions consumed so far, so `sync` only looks at new ones
+    processed: usize,
+    updates: Vec<AspRootUpdate>,
+}
+
+impl AspService {
+    /// With no policies every deposit is approved
+    pub fn new(scope: [u8; 32]) -> Self {
+        Self {
+            scope,
+            policies: Vec::new(),
+            tree: LeanIMT::new(MAX_TREE_DEPTH as u8),
+            pending: Vec::new(),
+            rejected: HashSet::new(),
+            deposits: HashMap::new(),
+            pool_nonce: 0,
+            shard_nonces: HashMap::new(),
+            processed: 0,
+            updates: Vec::new(),
+        }
+    }
+
+    /// Add a policy; a deposit is approved once every policy approves it, and rejected as
+    /// soon as one rejects it
+    pub fn with_policy(mut self, policy: impl DepositPolicy + Send + Sync + 'static) -> Self {
+        self.policies.push(Box::new(policy));
+        self
+    }
+
+    /// Consume the pool instructions `source` added since the last call
+    pub fn sync<S>(&mut self, source: &S, pool: &Pubkey) -> Result<usize, AspError>
+    where
+        S: TransactionSource,
+        S::Error: fmt::Display,
+    {
+        let transactions = source.pool_transactions(pool).map_err(|err| AspError::Source(err.to_string()))?;
+        let mut deposits = 0;
+        for transaction in transactions.iter().skip(self.processed) {
+            if self.ingest(transaction)?.is_some() {
+                deposits += 1;
+            }
+        }
+        Ok(deposits)
+    }
+
+    /// Consume one pool instruction, queueing its deposit (if any) for review
+    ///
+    /// Labels follow the pool and shard nonces, so every pool instruction has to be passed in order.
+    pub fn ingest(&mut self, transaction: &PoolTransaction) -> Result<Option<&DepositEvent>, AspError> {
+        let instruction = PrivacyPoolInstruction::try_from_slice(&transaction.instruction_data).map_err(|_| {
+            AspError::InvalidTransaction {
+                signature: transaction.signature.clone(),
+            }
+        })?;
+        self.processed += 1;
+
+        let (depositor, value, precommitment_hash, label) = match instruction {
+            PrivacyPoolInstruction::Deposit {
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                self.pool_nonce += 1;
+                (depositor, value, precommitment_hash, poseidon::compute_label(&self.scope, self.pool_nonce))
+            }
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index,
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                let nonce = self.shard_nonces.entry(shard_index).or_insert(0);
+                *nonce += 1;
+                let label = poseidon::compute_shard_label(&self.scope, shard_index, *nonce);
+                (depositor, value, precommitment_hash, label)
+            }
+            _ => return Ok(None),
+        };
+
+        let deposit = DepositEvent {
+            signature: transaction.signature.clone(),
+            slot: transaction.slot,
+            depositor,
+            value,
+            label,
+            commitment: poseidon::compute_commitment(value, &label, &precommitment_hash),
+        };
+        self.deposits.insert(label, deposit.clone());
+        self.pending.push(deposit);
+        Ok(self.pending.last())
+    }
+
+    /// Run the policies over every pending deposit at `current_slot`
+    ///
+    /// Approved labels are inserted in deposit order. Returns the new root if any were.
+    pub fn review(&mut self, current_slot: u64) -> Result<Option<AspRootUpdate>, AspError> {
+        let mut labels = Vec::new();
+        let mut still_pending = Vec::new();
+        for deposit in std::mem::take(&mut self.pending) {
+            match self.decide(&deposit, current_slot) {
+                Decision::Approve => labels.push(deposit.label),
+                Decision::Reject => {
+                    self.rejected.insert(deposit.label);
+                }
+                Decision::Defer => still_pending.push(deposit),
+            }
+        }
+        self.pending = still_pending;
+
+        if labels.is_empty() {
+            return Ok(None);
+        }
+        for label in &labels {
+            self.tree.insert(*label).map_err(AspError::Tree)?;
+        }
+
+        let update = AspRootUpdate {
+            root: self.tree.root(),
+            size: self.tree.size(),
+            depth: self.tree.depth(),
+            slot: current_slot,
+            labels,
+        };
+        self.updates.push(update.clone());
+        Ok(Some(update))
+    }
+
+    fn decide(&self, deposit: &DepositEvent, current_slot: u64) -> Decision {
+        let mut decision = Decision::Approve;
+        for policy in &self.policies {
+            match policy.review(deposit, current_slot) {
+                Decision::Reject => return Decision::Reject,
+                Decision::Defer => decision = Decision::Defer,
+                Decision::Approve => {}
+            }
+        }
+        decision
+    }
+
+    pub fn scope(&self) -> [u8; 32] {
+        self.scope
+    }
+
+    pub fn tree(&self) -> &LeanIMT {
+        &self.tree
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.tree.root()
+    }
+
+    /// Deposits waiting on a `Defer`, oldest first
+    pub fn pending(&self) -> &[DepositEvent] {
+        &self.pending
+    }
+
+    /// Every root update so far, oldest first
+    pub fn updates(&self) -> &[AspRootUpdate] {
+        &self.updates
+    }
+
+    pub fn deposit(&self, label: &[u8; 32]) -> Option<&DepositEvent> {
+        self.deposits.get(label)
+    }
+
+    pub fn status(&self, label: &[u8; 32]) -> Option<LabelStatus> {
+        if !self.deposits.contains_key(label) {
+            return None;
+        }
+        Some(match self.tree.index_of(label) {
+            Some(leaf_index) => LabelStatus::Approved { leaf_index },
+            None if self.rejected.contains(label) => LabelStatus::Rejected,
+            None => LabelStatus::Pending,
+        })
+    }
+
+    /// Circuit proof for an approved label against the current ASP root
+    pub fn proof(&self, label: &[u8; 32]) -> Option<CircuitMerkleProof> {
+        let leaf_index = self.tree.index_of(label)?;
+        self.tree.generate_circuit_proof(leaf_index, MAX_TREE_DEPTH).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::scanner::InMemoryTransactionSource;
+    use crate::BorshSerialize;
+
+    const POOL: Pubkey = [1u8; 32];
+    const SCOPE: [u8; 32] = [2u8; 32];
+
+    fn deposit(source: &mut InMemoryTransactionSource, slot: u64, depositor: u8, value: u64) {
+        let instruction = PrivacyPoolInstruction::Deposit {
+            depositor: [depositor; 32],
+            value,
+            precommitment_hash: [slot as u8; 32],
+        };
+        source.push(
+            POOL,
+            PoolTransaction {
+                signature: format!("tx{}", slot),
+                slot,
+                instruction_data: instruction.try_to_vec().unwrap(),
+                account_count: 3,
+            },
+        );
+    }
+
+    fn label(nonce: u64) -> [u8; 32] {
+        poseidon::compute_label(&SCOPE, nonce)
+    }
+
+    #[test]
+    fn test_policies_filter_labels() {
+        let mut source = InMemoryTransactionSource::new();
+        deposit(&mut source, 10, 7, 1_000);
+        deposit(&mut source, 11, 8, 1_000);
+        deposit(&mut source, 12, 7, 5);
+        deposit(&mut source, 13, 7, 2_000);
+
+        let mut service = AspService::new(SCOPE)
+            .with_policy(DenyList([[8u8; 32]].into_iter().collect()))
+            .with_policy(AmountLimit { min: 100, max: 10_000 });
+        assert_eq!(service.sync(&source, &POOL).unwrap(), 4);
+
+        let update = service.review(20).unwrap().unwrap();
+        assert_eq!(update.labels, vec![label(1), label(4)]);
+        assert_eq!(update.size, 2);
+
+        let mut expected = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        expected.insert_many(&[label(1), label(4)]).unwrap();
+        assert_eq!(update.root, expected.root());
+
+        assert_eq!(service.status(&label(2)), Some(LabelStatus::Rejected));
+        assert_eq!(service.status(&label(3)), Some(LabelStatus::Rejected));
+        assert_eq!(service.status(&label(4)), Some(LabelStatus::Approved { leaf_index: 1 }));
+        assert_eq!(service.status(&[0u8; 32]), None);
+        assert_eq!(service.deposit(&label(2)).unwrap().depositor, [8u8; 32]);
+
+        // Nothing new to approve
+        assert_eq!(service.review(30).unwrap(), None);
+    }
+
+    #[test]
+    fn test_waiting_period_defers() {
+        let mut source = InMemoryTransactionSource::new();
+        deposit(&mut source, 100, 7, 1_000);
+        let mut service = AspService::new(SCOPE)
+            .with_policy(WaitingPeriod { slots: 50 })
+            .with_policy(AllowList([[7u8; 32]].into_iter().collect()));
+        service.sync(&source, &POOL).unwrap();
+
+        assert_eq!(service.review(149).unwrap(), None);
+        assert_eq!(service.status(&label(1)), Some(LabelStatus::Pending));
+
+        // Only the new deposit is consumed on the next sync
+        deposit(&mut source, 120, 9, 1_000);
+        assert_eq!(service.sync(&source, &POOL).unwrap(), 1);
+
+        let update = service.review(150).unwrap().unwrap();
+        assert_eq!(update.labels, vec![label(1)]);
+        assert_eq!(service.status(&label(2)), Some(LabelStatus::Rejected));
+        assert!(service.pending().is_empty());
+        assert_eq!(service.updates().len(), 1);
+    }
+
+    #[test]
+    fn test_label_proofs() {
+        let mut source = InMemoryTransactionSource::new();
+        for slot in 1..=5 {
+            deposit(&mut source, slot, 7, 1_000);
+        }
+        let mut service = AspService::new(SCOPE);
+        service.sync(&source, &POOL).unwrap();
+        service.review(5).unwrap();
+
+        let proof = service.proof(&label(3)).unwrap();
+        assert_eq!(proof.leaf_index, 2);
+        assert_eq!(proof.root, service.root());
+        assert_eq!(proof.compute_root(), service.root());
+        assert!(service.proof(&label(6)).is_none());
+    }
+}
@@ -0,0 +1,407 @@
+//! Portable note backups
+//!
+//! A single note is exported as a copyable string, `ppnote:` followed by base58 of the
+//! versioned binary record and a 4-byte SHA-256 checksum. Any number of notes can be
+//! exported to a password-encrypted JSON file. Both formats carry the commitment, which
+//! is recomputed on import so a corrupted or mistyped note is rejected instead of
+//! producing proofs that can never verify.
+//!
+//! Files are encrypted with ChaCha20-Poly1305 under a key PBKDF2-HMAC-SHA256 derives from the
+//! password. The header (version, KDF, iterations and salt) is the associated data, so no
+//! header field can be swapped without failing authentication.
+
+use std::fmt;
+
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::Hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::client::hex;
+use crate::client::note::Note;
+use crate::client::scanner::ScannedNote;
+use crate::crypto::{field, poseidon};
+
+pub const NOTE_BACKUP_VERSION: u8 = 1;
+pub const NOTE_STRING_PREFIX: &str = "ppnote:";
+pub const BACKUP_FILE_VERSION: u8 = 1;
+pub const BACKUP_KDF: &str = "pbkdf2-hmac-sha256";
+/// PBKDF2 rounds for new backup files
+pub const DEFAULT_KDF_ITERATIONS: u32 = 600_000;
+/// Files asking for more rounds are rejected, so a crafted file can't stall the importer
+pub const MAX_KDF_ITERATIONS: u32 = 10 * DEFAULT_KDF_ITERATIONS;
+
+const CHECKSUM_LEN: usize = 4;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackupError {
+    UnsupportedVersion(u8),
+    /// Not a note string, bad base58, or a truncated record
+    InvalidEncoding,
+    /// The note string was mistyped or truncated
+    ChecksumMismatch,
+    /// Nullifier or secret is not a canonical field element
+    InvalidNote,
+    /// The stored commitment doesn't match the note's fields
+    CommitmentMismatch,
+    /// The transaction signature doesn't fit the record's one-byte length
+    SignatureTooLong,
+    /// The backup file isn't valid JSON, uses an unknown KDF or too many KDF rounds
+    InvalidFile,
+    /// Wrong password, or the file was modified
+    AuthenticationFailed,
+    /// The OS random number generator failed
+    Random,
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::UnsupportedVersion(version) => write!(f, "unsupported backup version {}", version),
+            BackupError::InvalidEncoding => write!(f, "not a valid note string"),
+            BackupError::ChecksumMismatch => write!(f, "note string checksum mismatch"),
+            BackupError::InvalidNote => write!(f, "note secrets are not field elements"),
+            BackupError::CommitmentMismatch => write!(f, "note does not match its commitment"),
+            BackupError::SignatureTooLong => write!(f, "transaction signature is longer than {} bytes", u8::MAX),
+            BackupError::InvalidFile => write!(f, "not a valid backup file"),
+            BackupError::AuthenticationFailed => write!(f, "wrong password or corrupted backup"),
+            BackupError::Random => write!(f, "failed to generate random bytes"),
+        }
+    }
+}
+
+impl std::error::Error for BackupError {}
+
+/// Everything needed to spend a note on another device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteBackup {
+    pub scope: [u8; 32],
+    pub note: Note,
+    pub leaf_index: u64,
+    /// Transaction that created the commitment
+    pub signature: String,
+}
+
+impl NoteBackup {
+    pub fn new(scope: [u8; 32], note: Note, leaf_index: u64, signature: String) -> Self {
+        Self {
+            scope,
+            note,
+            leaf_index,
+            signature,
+        }
+    }
+
+    pub fn from_scanned(scope: [u8; 32], scanned: &ScannedNote) -> Self {
+        Self::new(scope, scanned.note, scanned.leaf_index, scanned.signature.clone())
+    }
+
+    /// `version | scope | value | label | nullifier | secret | leaf_index | commitment | signature_len u8 | signature`
+    ///
+    /// Solana signatures are 88 base58 characters at most; anything over 255 bytes is an error
+    /// rather than being cut short.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BackupError> {
+        let signature = self.signature.as_bytes();
+        if signature.len() > u8::MAX as usize {
+            return Err(BackupError::SignatureTooLong);
+        }
+
+        let mut bytes = Vec::with_capacity(1 + 32 + 8 + 32 * 3 + 8 + 32 + 1 + signature.len());
+        bytes.push(NOTE_BACKUP_VERSION);
+        bytes.extend_from_slice(&self.scope);
+        bytes.extend_from_slice(&self.note.value.to_le_bytes());
+        bytes.extend_from_slice(&self.note.label);
+        bytes.extend_from_slice(&self.note.nullifier);
+        bytes.extend_from_slice(&self.note.secret);
+        bytes.extend_from_slice(&self.leaf_index.to_le_bytes());
+        bytes.extend_from_slice(&self.note.commitment());
+        bytes.push(signature.len() as u8);
+        bytes.extend_from_slice(signature);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BackupError> {
+        let mut reader = Reader(bytes);
+        let version = reader.u8()?;
+        if version != NOTE_BACKUP_VERSION {
+            return Err(BackupError::UnsupportedVersion(version));
+        }
+
+        let scope = reader.bytes32()?;
+        let value = reader.u64()?;
+        let label = reader.bytes32()?;
+        let nullifier = reader.bytes32()?;
+        let secret = reader.bytes32()?;
+        let leaf_index = reader.u64()?;
+        let commitment = reader.bytes32()?;
+        let signature_len = reader.u8()? as usize;
+        let signature = String::from_utf8(reader.take(signature_len)?.to_vec()).map_err(|_| BackupError::InvalidEncoding)?;
+        if !reader.0.is_empty() {
+            return Err(BackupError::InvalidEncoding);
+        }
+
+        if !field::is_in_field(&nullifier) || !field::is_in_field(&secret) {
+            return Err(BackupError::InvalidNote);
+        }
+        let precommitment = poseidon::compute_precommitment(&nullifier, &secret);
+        if poseidon::compute_commitment(value, &label, &precommitment) != commitment {
+            return Err(BackupError::CommitmentMismatch);
+        }
+
+        Ok(Self::new(scope, Note::new(value, label, nullifier, secret), leaf_index, signature))
+    }
+
+    pub fn to_note_string(&self) -> Result<String, BackupError> {
+        let mut payload = self.to_bytes()?;
+        let checksum = checksum(&payload);
+        payload.extend_from_slice(&checksum);
+        Ok(format!("{}{}", NOTE_STRING_PREFIX, bs58::encode(payload).into_string()))
+    }
+
+    /// Surrounding whitespace is ignored, since these get pasted around
+    pub fn from_note_string(note: &str) -> Result<Self, BackupError> {
+        let encoded = note
+            .trim()
+            .strip_prefix(NOTE_STRING_PREFIX)
+            .ok_or(BackupError::InvalidEncoding)?;
+        let payload = bs58::decode(encoded).into_vec().map_err(|_| BackupError::InvalidEncoding)?;
+        if payload.len() < CHECKSUM_LEN {
+            return Err(BackupError::InvalidEncoding);
+        }
+
+        let (record, stored) = payload.split_at(payload.len() - CHECKSUM_LEN);
+        if checksum(record) != stored {
+            return Err(BackupError::ChecksumMismatch);
+        }
+        Self::from_bytes(record)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    version: u8,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    /// Ciphertext followed by the Poly1305 tag
+    ciphertext: String,
+}
+
+/// Encrypt notes into a backup file with `DEFAULT_KDF_ITERATIONS`
+pub fn encrypt_backup(notes: &[NoteBackup], password: &str) -> Result<String, BackupError> {
+    encrypt_backup_with_iterations(notes, password, DEFAULT_KDF_ITERATIONS)
+}
+
+pub fn encrypt_backup_with_iterations(notes: &[NoteBackup], password: &str, iterations: u32) -> Result<String, BackupError> {
+    if iterations == 0 || iterations > MAX_KDF_ITERATIONS {
+        return Err(BackupError::InvalidFile);
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut salt).map_err(|_| BackupError::Random)?;
+    getrandom::getrandom(&mut nonce).map_err(|_| BackupError::Random)?;
+
+    let note_strings = notes.iter().map(NoteBackup::to_note_string).collect::<Result<Vec<_>, _>>()?;
+    let plaintext = serde_json::to_vec(&note_strings).map_err(|_| BackupError::InvalidFile)?;
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt, iterations));
+    let aad = header(iterations, &salt);
+    let payload = Payload {
+        msg: &plaintext,
+        aad: &aad,
+    };
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), payload)
+        .map_err(|_| BackupError::InvalidFile)?;
+
+    let file = BackupFile {
+        version: BACKUP_FILE_VERSION,
+        kdf: BACKUP_KDF.to_string(),
+        iterations,
+        salt: hex::encode(&salt),
+        nonce: hex::encode(&nonce),
+        ciphertext: hex::encode(&ciphertext),
+    };
+    serde_json::to_string_pretty(&file).map_err(|_| BackupError::InvalidFile)
+}
+
+/// Decrypt a backup file, validating every note it contains
+pub fn decrypt_backup(file: &str, password: &str) -> Result<Vec<NoteBackup>, BackupError> {
+    let file: BackupFile = serde_json::from_str(file).map_err(|_| BackupError::InvalidFile)?;
+    if file.version != BACKUP_FILE_VERSION {
+        return Err(BackupError::UnsupportedVersion(file.version));
+    }
+    if file.kdf != BACKUP_KDF || file.iterations == 0 || file.iterations > MAX_KDF_ITERATIONS {
+        return Err(BackupError::InvalidFile);
+    }
+
+    let salt = from_hex(&file.salt)?;
+    let nonce: [u8; NONCE_LEN] = from_hex(&file.nonce)?
+        .try_into()
+        .map_err(|_| BackupError::InvalidFile)?;
+    let ciphertext = from_hex(&file.ciphertext)?;
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt, file.iterations));
+    let aad = header(file.iterations, &salt);
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: &aad,
+    };
+    let plaintext = cipher
+        .decrypt(&Nonce::from(nonce), payload)
+        .map_err(|_| BackupError::AuthenticationFailed)?;
+
+    let note_strings: Vec<String> = serde_json::from_slice(&plaintext).map_err(|_| BackupError::InvalidFile)?;
+    note_strings.iter().map(|note| NoteBackup::from_note_string(note)).collect()
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = Sha256::digest(bytes);
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    checksum.copy_from_slice(&digest[..CHECKSUM_LEN]);
+    checksum
+}
+
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> Key {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// Associated data: everything in the header that affects decryption
+fn header(iterations: u32, salt: &[u8]) -> Vec<u8> {
+    let mut header = vec![BACKUP_FILE_VERSION];
+    header.extend_from_slice(BACKUP_KDF.as_bytes());
+    header.extend_from_slice(&iterations.to_le_bytes());
+    header.extend_from_slice(salt);
+    header
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, BackupError> {
+    hex::decode(value).ok_or(BackupError::InvalidFile)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BackupError> {
+        if self.0.len() < len {
+            return Err(BackupError::InvalidEncoding);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, BackupError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, BackupError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes32(&mut self) -> Result<[u8; 32], BackupError> {
+        Ok(self.take(32)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::keys::MasterKeys;
+
+    const SCOPE: [u8; 32] = [4u8; 32];
+
+    fn backup(index: u64) -> NoteBackup {
+        let keys = MasterKeys::from_seed(b"backup test wallet seed").unwrap();
+        let label = poseidon::compute_label(&SCOPE, index + 1);
+        let note = keys.deposit_secrets(&SCOPE, index).into_note(1_000 + index, label);
+        NoteBackup::new(SCOPE, note, index, format!("signature{}", index))
+    }
+
+    #[test]
+    fn test_note_string_round_trip() {
+        let original = backup(0);
+        let encoded = original.to_note_string().unwrap();
+        assert!(encoded.starts_with(NOTE_STRING_PREFIX));
+        assert_eq!(NoteBackup::from_note_string(&format!("  {}\n", encoded)).unwrap(), original);
+
+        // A single mistyped character is caught by the checksum
+        let mut typo: Vec<char> = encoded.chars().collect();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == '2' { '3' } else { '2' };
+        let typo: String = typo.into_iter().collect();
+        assert!(matches!(
+            NoteBackup::from_note_string(&typo),
+            Err(BackupError::ChecksumMismatch) | Err(BackupError::InvalidEncoding)
+        ));
+        assert_eq!(
+            NoteBackup::from_note_string("note:abc"),
+            Err(BackupError::InvalidEncoding)
+        );
+
+        // A record with a valid checksum but the wrong value fails the commitment check
+        let mut bytes = original.to_bytes().unwrap();
+        bytes[33] ^= 1;
+        assert_eq!(NoteBackup::from_bytes(&bytes), Err(BackupError::CommitmentMismatch));
+
+        let mut bytes = original.to_bytes().unwrap();
+        bytes[0] = 9;
+        assert_eq!(NoteBackup::from_bytes(&bytes), Err(BackupError::UnsupportedVersion(9)));
+
+        // Signatures aren't silently truncated
+        let mut long = original.clone();
+        long.signature = "x".repeat(256);
+        assert_eq!(long.to_bytes(), Err(BackupError::SignatureTooLong));
+        long.signature.pop();
+        assert_eq!(NoteBackup::from_note_string(&long.to_note_string().unwrap()).unwrap(), long);
+    }
+
+    #[test]
+    fn test_encrypted_file_round_trip() {
+        let notes = vec![backup(0), backup(1), backup(2)];
+        let file = encrypt_backup_with_iterations(&notes, "correct horse", 1_000).unwrap();
+        assert_eq!(decrypt_backup(&file, "correct horse").unwrap(), notes);
+
+        // Fresh salt and nonce each time
+        assert_ne!(file, encrypt_backup_with_iterations(&notes, "correct horse", 1_000).unwrap());
+
+        assert_eq!(decrypt_backup(&file, "wrong horse"), Err(BackupError::AuthenticationFailed));
+
+        let mut tampered: serde_json::Value = serde_json::from_str(&file).unwrap();
+        tampered["iterations"] = serde_json::Value::from(999);
+        assert_eq!(
+            decrypt_backup(&tampered.to_string(), "correct horse"),
+            Err(BackupError::AuthenticationFailed)
+        );
+
+        let mut tampered: serde_json::Value = serde_json::from_str(&file).unwrap();
+        let ciphertext = tampered["ciphertext"].as_str().unwrap().to_string();
+        let flipped = if ciphertext.starts_with('0') { "1" } else { "0" };
+        tampered["ciphertext"] = serde_json::Value::from(format!("{}{}", flipped, &ciphertext[1..]));
+        assert_eq!(
+            decrypt_backup(&tampered.to_string(), "correct horse"),
+            Err(BackupError::AuthenticationFailed)
+        );
+
+        let mut expensive: serde_json::Value = serde_json::from_str(&file).unwrap();
+        expensive["iterations"] = serde_json::Value::from(MAX_KDF_ITERATIONS + 1);
+        assert_eq!(decrypt_backup(&expensive.to_string(), "correct horse"), Err(BackupError::InvalidFile));
+        assert_eq!(
+            encrypt_backup_with_iterations(&notes, "correct horse", MAX_KDF_ITERATIONS + 1),
+            Err(BackupError::InvalidFile)
+        );
+
+        let mut non_ascii: serde_json::Value = serde_json::from_str(&file).unwrap();
+        non_ascii["salt"] = serde_json::Value::from("é".repeat(16));
+        assert_eq!(decrypt_backup(&non_ascii.to_string(), "correct horse"), Err(BackupError::InvalidFile));
+
+        assert_eq!(decrypt_backup("{}", "correct horse"), Err(BackupError::InvalidFile));
+    }
+}
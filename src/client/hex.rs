@@ -0,0 +1,56 @@
+//! Hex encoding shared by the JSON formats, the HTTP API, backups and the CLI
+//!
+//! Decoding works on bytes rather than slicing the string, so non-ASCII input is rejected
+//! instead of panicking on a char boundary.
+
+/// Lowercase hex without a prefix
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex with an optional `0x` prefix
+pub fn decode(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| Some(nibble(pair[0])? << 4 | nibble(pair[1])?))
+        .collect()
+}
+
+/// Decode exactly 32 bytes of hex
+pub fn decode32(hex: &str) -> Option<[u8; 32]> {
+    decode(hex)?.try_into().ok()
+}
+
+fn nibble(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0xab, 0xff];
+        assert_eq!(encode(&bytes), "007fabff");
+        assert_eq!(decode("007fabff"), Some(bytes.to_vec()));
+        assert_eq!(decode("0x007FABFF"), Some(bytes.to_vec()));
+        assert_eq!(decode(""), Some(vec![]));
+        assert_eq!(decode32(&"ab".repeat(32)), Some([0xab; 32]));
+        assert_eq!(decode32(&"ab".repeat(31)), None);
+    }
+
+    #[test]
+    fn test_hex_rejects_invalid_input() {
+        assert_eq!(decode("abc"), None);
+        assert_eq!(decode("zz"), None);
+        assert_eq!(decode("+1"), None);
+        // Multi-byte characters used to split a char and panic
+        assert_eq!(decode("éé"), None);
+        assert_eq!(decode("aé"), None);
+        assert_eq!(decode32(&format!("{}é", "a".repeat(62))), None);
+    }
+}
@@ -0,0 +1,617 @@
+//! Shank-style IDL describing the program's instructions and account layouts
+//!
+//! Field offsets and account sizes are taken from the Rust structs themselves, so a
+//! layout change shows up as a diff in `idl/privacy_pools.json` once it's regenerated
+//! with `cargo run --bin generate-idl --features client`. Account structs are
+//! `#[repr(C, packed)]` zero-copy layouts, not Borsh, hence the explicit offsets.
+
+use std::mem::{offset_of, size_of};
+
+use serde_json::{json, Value};
+
+use crate::state::deposit_queue::{DepositQueueZC, QueuedDeposit, DEPOSIT_QUEUE_CAPACITY};
+use crate::state::lean_imt::{LeanIMTStateZC, PoolStateLeanIMT, MAX_TREE_DEPTH};
+use crate::state::root_history::{RootHistoryEntry, RootHistoryHeader};
+use crate::state::shard::StateShardZC;
+use crate::state::zero_copy::{DepositorStateZC, NullifierStateZC};
+
+/// Where the checked-in IDL lives, relative to the repository root
+pub const IDL_PATH: &str = "idl/privacy_pools.json";
+
+/// `{ name, type, offset }` for each listed field of a packed struct
+macro_rules! fields {
+    ($ty:ty { $($field:ident: $kind:expr),* $(,)? }) => {
+        vec![$(json!({
+            "name": stringify!($field),
+            "type": $kind,
+            "offset": offset_of!($ty, $field),
+        })),*]
+    };
+}
+
+fn array(element: Value, len: usize) -> Value {
+    json!({ "array": [element, len] })
+}
+
+fn hash() -> Value {
+    array(json!("u8"), 32)
+}
+
+fn defined(name: &str) -> Value {
+    json!({ "defined": name })
+}
+
+fn layout(name: &str, docs: &[&str], size: usize, fields: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "docs": docs,
+        "size": size,
+        "type": { "kind": "struct", "fields": fields },
+    })
+}
+
+fn arg(name: &str, kind: Value) -> Value {
+    json!({ "name": name, "type": kind })
+}
+
+fn account(name: &str, is_mut: bool, is_signer: bool, docs: &str) -> Value {
+    json!({ "name": name, "isMut": is_mut, "isSigner": is_signer, "docs": [docs] })
+}
+
+fn instruction(name: &str, discriminant: u8, docs: &str, accounts: Vec<Value>, args: Vec<Value>) -> Value {
+    json!({
+        "name": name,
+        "docs": [docs],
+        "accounts": accounts,
+        "args": args,
+        "discriminant": { "type": "u8", "value": discriminant },
+    })
+}
+
+fn instructions() -> Vec<Value> {
+    let withdraw_args = || {
+        vec![
+            arg("withdrawal_data", defined("WithdrawalData")),
+            arg("proof_data", defined("WithdrawProofData")),
+        ]
+    };
+
+    vec![
+        instruction(
+            "InitializePool",
+            0,
+            "Initialize a pool account; root_history_capacity and root_expiry_slots may be omitted together",
+            vec![
+                account("pool", true, false, "Pool account, PoolStateLeanIMT followed by a RootHistory region"),
+                account("authority", false, true, "Pool authority"),
+            ],
+            vec![
+                arg("entrypoint_authority", json!("publicKey")),
+                arg("max_tree_depth", json!("u8")),
+                arg("asset_mint", json!("publicKey")),
+                arg("root_history_capacity", json!("u32")),
+                arg("root_expiry_slots", json!("u64")),
+            ],
+        ),
+        instruction(
+            "Deposit",
+            1,
+            "Insert a commitment into the state tree, or append it to the deposit queue once the pool has one",
+            vec![
+                account("pool", true, false, "Pool account"),
+                account("depositor_account", true, false, "Receives the depositor and label"),
+                account("depositor", false, true, "Must equal the depositor argument"),
+                json!({
+                    "name": "queue",
+                    "isMut": true,
+                    "isSigner": false,
+                    "isOptional": true,
+                    "docs": ["Deposit queue attached to the pool; required once InitializeQueue ran, rejected before"],
+                }),
+            ],
+            vec![
+                arg("depositor", json!("publicKey")),
+                arg("value", json!("u64")),
+                arg("precommitment_hash", hash()),
+            ],
+        ),
+        instruction(
+            "Withdraw",
+            2,
+            "Withdraw with a proof against a known state root and the pool's current ASP root",
+            vec![
+                account("pool", true, false, "Pool account; records the new state root"),
+                account("processooor", false, true, "Must equal withdrawal_data.processooor"),
+                account("nullifier", true, false, "Marked spent"),
+            ],
+            withdraw_args(),
+        ),
+        instruction(
+            "Ragequit",
+            3,
+            "Exit by the original depositor without ASP approval",
+            vec![
+                account("pool", false, false, "Pool account"),
+                account("depositor_account", false, false, "Written by the original deposit"),
+                account("ragequitter", false, true, "Original depositor"),
+                account("nullifier", true, false, "Marked spent"),
+            ],
+            vec![arg("proof_data", defined("RagequitProofData"))],
+        ),
+        instruction(
+            "WindDown",
+            4,
+            "Stop accepting deposits",
+            vec![
+                account("pool", true, false, "Pool account"),
+                account("entrypoint_authority", false, true, "Pool entrypoint"),
+            ],
+            vec![],
+        ),
+        instruction(
+            "InitializeShard",
+            5,
+            "Create the next state tree shard of a pool",
+            vec![
+                account("pool", true, false, "Pool account"),
+                account("shard", true, false, "StateShardZC followed by a RootHistory region"),
+                account("authority", false, true, "Pool authority"),
+            ],
+            vec![arg("shard_index", json!("u16"))],
+        ),
+        instruction(
+            "ShardDeposit",
+            6,
+            "Deposit into a shard instead of the pool's state tree",
+            vec![
+                account("pool", true, false, "Pool account, receives the label in its ASP tree"),
+                account("shard", true, false, "Shard account"),
+                account("depositor_account", true, false, "Receives the depositor and label"),
+                account("depositor", false, true, "Must equal the depositor argument"),
+            ],
+            vec![
+                arg("shard_index", json!("u16")),
+                arg("depositor", json!("publicKey")),
+                arg("value", json!("u64")),
+                arg("precommitment_hash", hash()),
+            ],
+        ),
+        instruction(
+            "ShardWithdraw",
+            7,
+            "Withdraw with a proof against one of a shard's roots",
+            vec![
+                account("pool", false, false, "Pool account"),
+                account("shard", true, false, "Shard account"),
+                account("processooor", false, true, "Must equal withdrawal_data.processooor"),
+                account("nullifier", true, false, "Marked spent"),
+            ],
+            [vec![arg("shard_index", json!("u16"))], withdraw_args()].concat(),
+        ),
+        instruction(
+            "InitializeQueue",
+            8,
+            "Attach a deposit queue to a pool",
+            vec![
+                account("pool", true, false, "Pool account; records the queue key"),
+                account("queue", true, false, "DepositQueueZC account"),
+                account("authority", false, true, "Pool authority"),
+            ],
+            vec![],
+        ),
+        instruction(
+            "ProcessQueue",
+            9,
+            "Insert up to max_batch queued deposits; permissionless",
+            vec![
+                account("pool", true, false, "Pool account"),
+                account("queue", true, false, "Deposit queue recorded in the pool"),
+            ],
+            vec![arg("max_batch", json!("u16"))],
+        ),
+    ]
+}
+
+fn accounts() -> Vec<Value> {
+    vec![
+        layout(
+            "PoolStateLeanIMT",
+            &["Pool account header; followed by a RootHistoryHeader and root_history capacity RootHistoryEntry items"],
+            PoolStateLeanIMT::LEN,
+            fields!(PoolStateLeanIMT {
+                is_initialized: json!("u8"),
+                _padding1: array(json!("u8"), 7),
+                authority: json!("publicKey"),
+                asset_mint: json!("publicKey"),
+                entrypoint: json!("publicKey"),
+                withdrawal_verifier: json!("publicKey"),
+                scope: hash(),
+                nonce: json!("u64"),
+                is_dead: json!("u8"),
+                shard_count: json!("u16"),
+                _padding2: array(json!("u8"), 5),
+                deposit_queue: json!("publicKey"),
+                state_tree: defined("LeanIMTStateZC"),
+                asp_tree: defined("LeanIMTStateZC"),
+            }),
+        ),
+        layout(
+            "StateShardZC",
+            &["Shard account header; followed by a RootHistory region like the pool"],
+            StateShardZC::LEN,
+            fields!(StateShardZC {
+                is_initialized: json!("u8"),
+                _padding1: array(json!("u8"), 5),
+                shard_index: json!("u16"),
+                pool: json!("publicKey"),
+                nonce: json!("u64"),
+                state_tree: defined("LeanIMTStateZC"),
+            }),
+        ),
+        layout(
+            "DepositQueueZC",
+            &["Ring buffer of deposits waiting for ProcessQueue"],
+            DepositQueueZC::LEN,
+            fields!(DepositQueueZC {
+                is_initialized: json!("u8"),
+                _padding1: array(json!("u8"), 7),
+                pool: json!("publicKey"),
+                head: json!("u64"),
+                tail: json!("u64"),
+                entries: array(defined("QueuedDeposit"), DEPOSIT_QUEUE_CAPACITY),
+            }),
+        ),
+        layout(
+            "NullifierStateZC",
+            &["Spent marker for a nullifier hash"],
+            NullifierStateZC::LEN,
+            fields!(NullifierStateZC {
+                is_spent: json!("u8"),
+                nullifier_hash: hash(),
+            }),
+        ),
+        layout(
+            "DepositorStateZC",
+            &["Original depositor of a label, checked by Ragequit"],
+            DepositorStateZC::LEN,
+            fields!(DepositorStateZC {
+                depositor: json!("publicKey"),
+                label: hash(),
+            }),
+        ),
+    ]
+}
+
+fn types() -> Vec<Value> {
+    vec![
+        layout(
+            "LeanIMTStateZC",
+            &["Lean IMT frontier: side node per level plus the inserted leaves"],
+            LeanIMTStateZC::LEN,
+            fields!(LeanIMTStateZC {
+                size: json!("u64"),
+                depth: json!("u32"),
+                _padding: json!("u32"),
+                side_nodes: array(hash(), MAX_TREE_DEPTH + 1),
+                leaf_indices: array(hash(), 1024),
+                leaf_count: json!("u64"),
+            }),
+        ),
+        layout(
+            "RootHistoryHeader",
+            &["Start of the root history region at the tail of pool and shard accounts"],
+            RootHistoryHeader::LEN,
+            fields!(RootHistoryHeader {
+                capacity: json!("u32"),
+                _padding: json!("u32"),
+                expiry_slots: json!("u64"),
+                current_index: json!("u64"),
+                count: json!("u64"),
+            }),
+        ),
+        layout(
+            "RootHistoryEntry",
+            &["A root and the slot it was created in"],
+            RootHistoryEntry::LEN,
+            fields!(RootHistoryEntry {
+                root: hash(),
+                slot: json!("u64"),
+            }),
+        ),
+        layout(
+            "QueuedDeposit",
+            &["A deposit waiting in the queue"],
+            size_of::<QueuedDeposit>(),
+            fields!(QueuedDeposit {
+                commitment: hash(),
+                label: hash(),
+            }),
+        ),
+        json!({
+            "name": "WithdrawalData",
+            "docs": ["data is prefixed with its length as a u32"],
+            "type": { "kind": "struct", "fields": [
+                arg("processooor", json!("publicKey")),
+                arg("data", json!("bytes")),
+            ]},
+        }),
+        json!({
+            "name": "WithdrawProofData",
+            "docs": ["Groth16 proof in groth16-solana encoding; public_signals is prefixed with its count as a u32"],
+            "type": { "kind": "struct", "fields": [
+                arg("proof_a", array(json!("u8"), 64)),
+                arg("proof_b", array(json!("u8"), 128)),
+                arg("proof_c", array(json!("u8"), 64)),
+                arg("public_signals", json!({ "vec": hash() })),
+            ]},
+        }),
+        json!({
+            "name": "RagequitProofData",
+            "docs": ["Same encoding as WithdrawProofData"],
+            "type": { "kind": "struct", "fields": [
+                arg("proof_a", array(json!("u8"), 64)),
+                arg("proof_b", array(json!("u8"), 128)),
+                arg("proof_c", array(json!("u8"), 64)),
+                arg("public_signals", json!({ "vec": hash() })),
+            ]},
+        }),
+    ]
+}
+
+pub fn generate_idl() -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": "privacy_pools",
+        "instructions": instructions(),
+        "accounts": accounts(),
+        "types": types(),
+        "metadata": { "origin": "shank", "encoding": "little-endian" },
+    })
+}
+
+/// The IDL as it's written to `IDL_PATH`
+pub fn generate_idl_json() -> String {
+    let mut json = serde_json::to_string_pretty(&generate_idl()).expect("IDL serializes");
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::instructions as builders;
+    use crate::instructions::{PrivacyPoolInstruction, RagequitProofData, WithdrawProofData, WithdrawalData};
+    use crate::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+
+    fn find<'a>(idl: &'a Value, section: &str, name: &str) -> &'a Value {
+        idl[section]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["name"] == name)
+            .unwrap_or_else(|| panic!("{} missing from {}", name, section))
+    }
+
+    /// Encoded size of a fixed-size type, or of `bytes` / `vec` with the given element count
+    fn type_size(idl: &Value, kind: &Value, elements: usize) -> usize {
+        match kind {
+            Value::String(name) => match name.as_str() {
+                "u8" => 1,
+                "u16" => 2,
+                "u32" => 4,
+                "u64" => 8,
+                "publicKey" => 32,
+                "bytes" => 4 + elements,
+                other => panic!("unknown type {}", other),
+            },
+            _ if kind.get("array").is_some() => {
+                let array = &kind["array"];
+                type_size(idl, &array[0], 0) * array[1].as_u64().unwrap() as usize
+            }
+            _ if kind.get("vec").is_some() => 4 + elements * type_size(idl, &kind["vec"], 0),
+            _ => {
+                let name = kind["defined"].as_str().unwrap();
+                let definition = idl["types"].as_array().unwrap().iter().find(|t| t["name"] == name).unwrap();
+                definition["size"]
+                    .as_u64()
+                    .map(|size| size as usize)
+                    .unwrap_or_else(|| {
+                        definition["type"]["fields"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|field| type_size(idl, &field["type"], elements))
+                            .sum()
+                    })
+            }
+        }
+    }
+
+    #[test]
+    fn test_checked_in_idl_is_up_to_date() {
+        assert_eq!(
+            include_str!("../../idl/privacy_pools.json"),
+            generate_idl_json(),
+            "idl/privacy_pools.json is stale, regenerate it with `cargo run --bin generate-idl --features client`"
+        );
+    }
+
+    #[test]
+    fn test_layouts_cover_every_byte() {
+        let idl = generate_idl();
+        let layouts = idl["accounts"].as_array().unwrap().iter().chain(
+            idl["types"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|t| t.get("size").is_some()),
+        );
+
+        for layout in layouts {
+            // Contiguous fields ending at the struct size means no field was added, removed or resized
+            let mut end = 0;
+            for field in layout["type"]["fields"].as_array().unwrap() {
+                assert_eq!(field["offset"].as_u64().unwrap() as usize, end, "{}.{}", layout["name"], field["name"]);
+                end += type_size(&idl, &field["type"], 0);
+            }
+            assert_eq!(layout["size"].as_u64().unwrap() as usize, end, "{}", layout["name"]);
+        }
+    }
+
+    #[test]
+    fn test_instructions_match_serializer() {
+        let idl = generate_idl();
+        let key = [1u8; 32];
+        let proof = || WithdrawProofData {
+            proof_a: [0u8; 64],
+            proof_b: [0u8; 128],
+            proof_c: [0u8; 64],
+            public_signals: vec![[0u8; 32]; 3],
+        };
+        let withdrawal = || WithdrawalData {
+            processooor: key,
+            data: vec![0u8; 5],
+        };
+        let samples = [
+            (
+                "InitializePool",
+                PrivacyPoolInstruction::InitializePool {
+                    entrypoint_authority: key,
+                    max_tree_depth: 20,
+                    asset_mint: key,
+                    root_history_capacity: 64,
+                    root_expiry_slots: 0,
+                },
+            ),
+            (
+                "Deposit",
+                PrivacyPoolInstruction::Deposit {
+                    depositor: key,
+                    value: 1,
+                    precommitment_hash: key,
+                },
+            ),
+            (
+                "Withdraw",
+                PrivacyPoolInstruction::Withdraw {
+                    withdrawal_data: withdrawal(),
+                    proof_data: proof(),
+                },
+            ),
+            (
+                "Ragequit",
+                PrivacyPoolInstruction::Ragequit {
+                    proof_data: RagequitProofData {
+                        proof_a: [0u8; 64],
+                        proof_b: [0u8; 128],
+                        proof_c: [0u8; 64],
+                        public_signals: vec![[0u8; 32]; 3],
+                    },
+                },
+            ),
+            ("WindDown", PrivacyPoolInstruction::WindDown),
+            ("InitializeShard", PrivacyPoolInstruction::InitializeShard { shard_index: 1 }),
+            (
+                "ShardDeposit",
+                PrivacyPoolInstruction::ShardDeposit {
+                    shard_index: 1,
+                    depositor: key,
+                    value: 1,
+                    precommitment_hash: key,
+                },
+            ),
+            (
+                "ShardWithdraw",
+                PrivacyPoolInstruction::ShardWithdraw {
+                    shard_index: 1,
+                    withdrawal_data: withdrawal(),
+                    proof_data: proof(),
+                },
+            ),
+            ("InitializeQueue", PrivacyPoolInstruction::InitializeQueue),
+            ("ProcessQueue", PrivacyPoolInstruction::ProcessQueue { max_batch: 1 }),
+        ];
+        assert_eq!(samples.len(), idl["instructions"].as_array().unwrap().len());
+
+        for (name, sample) in samples {
+            let ix = find(&idl, "instructions", name);
+            let data = sample.try_to_vec().unwrap();
+            assert_eq!(data[0] as u64, ix["discriminant"]["value"].as_u64().unwrap(), "{}", name);
+
+            // Samples carry 5 bytes of withdrawal data and 3 public signals
+            let args_size: usize = ix["args"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|arg| match arg["type"]["defined"].as_str() {
+                    Some("WithdrawalData") => type_size(&idl, &arg["type"], 5),
+                    _ => type_size(&idl, &arg["type"], 3),
+                })
+                .sum();
+            assert_eq!(data.len(), 1 + args_size, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_instruction_accounts_match_builders() {
+        let idl = generate_idl();
+        let key = Pubkey::new_from_array([1u8; 32]);
+        let proof = WithdrawProofData {
+            proof_a: [0u8; 64],
+            proof_b: [0u8; 128],
+            proof_c: [0u8; 64],
+            public_signals: vec![],
+        };
+        let built = [
+            ("InitializePool", builders::initialize_pool(&key, &key, &key, &key, 20, &key, 64, 0)),
+            ("Deposit", builders::deposit(&key, &key, &key, &key, 1, [0u8; 32], Some(&key))),
+            (
+                "Withdraw",
+                builders::withdraw(
+                    &key,
+                    &key,
+                    &key,
+                    WithdrawalData {
+                        processooor: key.to_bytes(),
+                        data: vec![],
+                    },
+                    proof,
+                ),
+            ),
+            (
+                "Ragequit",
+                builders::ragequit(
+                    &key,
+                    &key,
+                    &key,
+                    &key,
+                    &key,
+                    RagequitProofData {
+                        proof_a: [0u8; 64],
+                        proof_b: [0u8; 128],
+                        proof_c: [0u8; 64],
+                        public_signals: vec![],
+                    },
+                ),
+            ),
+            ("WindDown", builders::wind_down(&key, &key, &key)),
+        ];
+
+        for (name, instruction) in built {
+            let accounts: Vec<(bool, bool)> = find(&idl, "instructions", name)["accounts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|account| (account["isSigner"].as_bool().unwrap(), account["isMut"].as_bool().unwrap()))
+                .collect();
+            let metas: Vec<(bool, bool)> = instruction
+                .unwrap()
+                .accounts
+                .iter()
+                .map(|meta| (meta.is_signer, meta.is_writable))
+                .collect();
+            assert_eq!(accounts, metas, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Read-only HTTP API over an `Indexer`, plus the plain-HTTP client `RpcSource` uses
+//!
+//! Routes (all `GET`, JSON responses, hashes as hex):
+//!
+//! - `/stats`: counters, tree sizes and roots
+//! - `/roots` and `/shards/{index}/roots`: every root of a state tree, oldest first
+//! - `/proofs/state/{commitment}`: Merkle proof for a commitment, in whichever tree holds it
+//! - `/proofs/asp/{label}`: Merkle proof for a label in the ASP tree
+//! - `/nullifiers/{hash}`: whether a nullifier hash is spent, and by which transaction
+//!
+//! Requests are served one at a time; the indexer is only locked while a response is built, and
+//! a client that stalls is cut off after `IO_TIMEOUT`. A poisoned lock means a sync panicked part
+//! way through an update, so `serve` stops rather than answering from a half-applied index.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::client::hex;
+use crate::client::indexer::Indexer;
+use crate::client::scanner::StateTree;
+use crate::crypto::merkle_tree::{CircuitMerkleProof, LeanIMT};
+
+/// Longest request head accepted, the API has no request bodies
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+/// How long a connection may take to send its request or accept the response
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+/// Answer a request without any I/O
+pub fn route(indexer: &Indexer, method: &str, path: &str) -> Response {
+    if method != "GET" {
+        return Response::error(405, "only GET is supported");
+    }
+
+    let segments: Vec<&str> = path.split('?').next().unwrap_or_default().trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["stats"] => Response::ok(stats(indexer)),
+        ["roots"] => roots(indexer, StateTree::Pool),
+        ["shards", index, "roots"] => match index.parse() {
+            Ok(index) => roots(indexer, StateTree::Shard(index)),
+            Err(_) => Response::error(400, "invalid shard index"),
+        },
+        ["proofs", "state", commitment] => with_hash(commitment, |commitment| match indexer.state_proof(&commitment) {
+            Some((tree, proof)) => Response::ok(proof_json(&proof, tree_json(tree), "state")),
+            None => Response::error(404, "unknown commitment"),
+        }),
+        ["proofs", "asp", label] => with_hash(label, |label| match indexer.asp_proof(&label) {
+            Some(proof) => Response::ok(proof_json(&proof, json!("asp"), "ASP")),
+            None => Response::error(404, "unknown label"),
+        }),
+        ["nullifiers", hash] => with_hash(hash, |hash| {
+            let spent_by = indexer.nullifier(&hash);
+            Response::ok(json!({ "spent": spent_by.is_some(), "signature": spent_by }))
+        }),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn with_hash(value: &str, respond: impl FnOnce([u8; 32]) -> Response) -> Response {
+    match hex::decode32(value) {
+        Some(hash) => respond(hash),
+        None => Response::error(400, "expected 32 bytes of hex"),
+    }
+}
+
+fn stats(indexer: &Indexer) -> Value {
+    let shards: Vec<Value> = indexer
+        .shards()
+        .into_iter()
+        .map(|index| {
+            let mut tree = tree_summary(indexer.state_tree(StateTree::Shard(index)));
+            tree["shard_index"] = json!(index);
+            tree
+        })
+        .collect();
+
+    json!({
+        "pool": bs58::encode(indexer.pool()).into_string(),
+        "scope": indexer.scope().map(|scope| hex::encode(&scope)),
+        "last_signature": indexer.last_signature(),
+        "counters": indexer.stats(),
+        "spent_nullifiers": indexer.spent_nullifiers(),
+        "state_tree": tree_summary(indexer.state_tree(StateTree::Pool)),
+        "asp_tree": tree_summary(Some(indexer.asp_tree())),
+        "shards": shards,
+    })
+}
+
+fn tree_summary(tree: Option<&LeanIMT>) -> Value {
+    match tree {
+        Some(tree) => json!({ "size": tree.size(), "depth": tree.depth(), "root": hex::encode(&tree.root()) }),
+        None => json!({ "size": 0, "depth": 0, "root": hex::encode(&[0u8; 32]) }),
+    }
+}
+
+fn roots(indexer: &Indexer, tree: StateTree) -> Response {
+    if indexer.state_tree(tree).is_none() {
+        return Response::error(404, "unknown tree");
+    }
+    Response::ok(json!({ "tree": tree_json(tree), "roots": indexer.roots(tree) }))
+}
+
+fn tree_json(tree: StateTree) -> Value {
+    match tree {
+        StateTree::Pool => json!("pool"),
+        StateTree::Shard(index) => json!({ "shard": index }),
+    }
+}
+
+/// The proof in hex, plus the circuit inputs it provides (`prefix` as in `to_circuit_inputs`)
+fn proof_json(proof: &CircuitMerkleProof, tree: Value, prefix: &str) -> Value {
+    json!({
+        "tree": tree,
+        "leaf": hex::encode(&proof.leaf),
+        "leaf_index": proof.leaf_index,
+        "root": hex::encode(&proof.root),
+        "depth": proof.depth,
+        "siblings": proof.siblings.iter().map(|sibling| hex::encode(sibling)).collect::<Vec<_>>(),
+        "circuit_inputs": proof.to_circuit_inputs(prefix),
+    })
+}
+
+
+/// Serve `route` on `listener` until accepting fails or the indexer lock is poisoned
+pub fn serve(listener: &TcpListener, indexer: &RwLock<Indexer>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        // A misbehaving client only loses its own connection
+        let _ = handle_connection(stream?, indexer);
+        if indexer.is_poisoned() {
+            return Err(io::Error::other("indexer lock poisoned"));
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, indexer: &RwLock<Indexer>) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skip the headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => match indexer.read() {
+            Ok(indexer) => route(&indexer, method, path),
+            Err(_) => Response::error(503, "index unavailable"),
+        },
+        _ => Response::error(400, "malformed request"),
+    };
+    write_response(stream, &response)
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// POST a JSON body to an `http://` URL and return the response body
+pub(crate) fn post_json(url: &str, body: &str) -> io::Result<String> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    let rest = url.strip_prefix("http://").ok_or_else(|| invalid("only http:// URLs are supported"))?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+
+    let mut stream = TcpStream::connect(authority)?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8(response).map_err(|_| invalid("response is not UTF-8"))?;
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(|| invalid("malformed HTTP response"))?;
+
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        return Err(io::Error::other(format!("HTTP status {}", status)));
+    }
+    if head.lines().any(|line| line.eq_ignore_ascii_case("transfer-encoding: chunked")) {
+        return decode_chunked(body).ok_or_else(|| invalid("malformed chunked body"));
+    }
+    Ok(body.to_string())
+}
+
+fn decode_chunked(mut body: &str) -> Option<String> {
+    let mut decoded = String::new();
+    loop {
+        let (size, rest) = body.split_once("\r\n")?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.push_str(rest.get(..size)?);
+        body = rest.get(size..)?.strip_prefix("\r\n")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_errors() {
+        let indexer = Indexer::new([1u8; 32], [2u8; 32], None);
+        assert_eq!(route(&indexer, "POST", "/stats").status, 405);
+        assert_eq!(route(&indexer, "GET", "/nope").status, 404);
+        assert_eq!(route(&indexer, "GET", "/proofs/state/xyz").status, 400);
+        assert_eq!(route(&indexer, "GET", &format!("/proofs/state/{}", "ab".repeat(32))).status, 404);
+        assert_eq!(route(&indexer, "GET", "/roots").status, 404);
+        assert_eq!(route(&indexer, "GET", "/shards/x/roots").status, 400);
+
+        let nullifier = route(&indexer, "GET", &format!("/nullifiers/{}", "00".repeat(32)));
+        assert_eq!(nullifier.body, json!({ "spent": false, "signature": null }));
+        assert_eq!(route(&indexer, "GET", "/stats?verbose=1").body["state_tree"]["size"], 0);
+    }
+
+    #[test]
+    fn test_decode_chunked() {
+        assert_eq!(decode_chunked("5\r\nhello\r\n6;x=y\r\n world\r\n0\r\n\r\n").as_deref(), Some("hello world"));
+        assert_eq!(decode_chunked("5\r\nhel"), None);
+    }
+
+    #[test]
+    fn test_serve_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let indexer = RwLock::new(Indexer::new([1u8; 32], [2u8; 32], Some([3u8; 32])));
+            serve(&listener, &indexer)
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET /stats HTTP/1.1\r\nHost: test\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["scope"], "03".repeat(32));
+    }
+}
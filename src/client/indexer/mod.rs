@@ -0,0 +1,477 @@
+//! Local indexer for a single pool
+//!
+//! Replays the pool's instructions the way `WalletScanner` does, but for every deposit rather
+//! than one wallet's: the state trees (pool and shards) and the ASP tree are rebuilt, spent
+//! nullifier hashes and every root are recorded, and each indexed transaction is persisted
+//! through an `IndexStore`. `ProcessQueue` inserts are checked against the `queue_insert`
+//! events the program logs, which catches a replay drifting from the chain.
+//!
+//! `http` serves the result; the `privacy-pools-indexer` binary wires everything together.
+
+pub mod http;
+pub mod source;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use pinocchio::pubkey::Pubkey;
+use serde::Serialize;
+use solana_program::keccak;
+
+use crate::client::accounts::RootEntry;
+use crate::client::scanner::{PoolTransaction, StateTree};
+use crate::crypto::merkle_tree::{CircuitMerkleProof, LeanIMT};
+use crate::crypto::poseidon;
+use crate::instructions::{PrivacyPoolInstruction, WithdrawProofData};
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+use crate::BorshDeserialize;
+
+use source::{QueueInsertEvent, RecordSource, SourceError, TransactionRecord};
+use store::IndexStore;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexError {
+    Source(SourceError),
+    Store(String),
+    /// Instruction data the program would have rejected
+    InvalidTransaction { signature: String },
+    /// A deposit was replayed before the pool's `InitializePool`, and no scope was given
+    UnknownScope { signature: String },
+    /// A tree rejected an insert
+    Tree { signature: String, reason: &'static str },
+    /// The program logged different queue inserts than the replay produced
+    QueueEventMismatch { signature: String },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Source(err) => write!(f, "transaction source failed: {}", err),
+            IndexError::Store(err) => write!(f, "index store failed: {}", err),
+            IndexError::InvalidTransaction { signature } => write!(f, "transaction {} is not a valid pool instruction", signature),
+            IndexError::UnknownScope { signature } => {
+                write!(f, "transaction {} needs the pool scope, which is only known after InitializePool", signature)
+            }
+            IndexError::Tree { signature, reason } => write!(f, "transaction {}: {}", signature, reason),
+            IndexError::QueueEventMismatch { signature } => {
+                write!(f, "transaction {} logged queue inserts that don't match the replay", signature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+impl From<SourceError> for IndexError {
+    fn from(err: SourceError) -> Self {
+        IndexError::Source(err)
+    }
+}
+
+/// Something an indexed instruction changed, in the order it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexChange {
+    Leaf { tree: StateTree, leaf_index: u64, commitment: [u8; 32] },
+    AspLeaf { leaf_index: u64, label: [u8; 32] },
+    Root { tree: StateTree, root: [u8; 32], slot: u64 },
+    Nullifier { nullifier_hash: [u8; 32], signature: String },
+}
+
+/// A transaction's pool instructions and the changes they made
+pub type Ingested = (Vec<PoolTransaction>, Vec<IndexChange>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+    /// Pool instructions indexed
+    pub instructions: u64,
+    pub deposits: u64,
+    /// Queued deposits not yet inserted by `ProcessQueue`
+    pub pending_deposits: u64,
+    pub withdrawals: u64,
+    pub ragequits: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub total_ragequit: u64,
+    pub is_dead: bool,
+    pub last_slot: u64,
+}
+
+pub struct Indexer {
+    program_id: Pubkey,
+    pool: Pubkey,
+    scope: Option<[u8; 32]>,
+    trees: HashMap<StateTree, LeanIMT>,
+    asp_tree: LeanIMT,
+    /// Where each commitment was inserted
+    leaves: HashMap<[u8; 32], (StateTree, u64)>,
+    roots: HashMap<StateTree, Vec<RootEntry>>,
+    /// Spent nullifier hash to the transaction that spent it
+    nullifiers: HashMap<[u8; 32], String>,
+    pool_nonce: u64,
+    shard_nonces: HashMap<u16, u64>,
+    /// Queued deposits: commitment and label
+    queue: VecDeque<([u8; 32], [u8; 32])>,
+    indexed: HashSet<String>,
+    last_signature: Option<String>,
+    stats: PoolStats,
+}
+
+impl Indexer {
+    /// `scope` may be omitted when the history starts with the pool's `InitializePool`
+    pub fn new(program_id: Pubkey, pool: Pubkey, scope: Option<[u8; 32]>) -> Self {
+        Self {
+            program_id,
+            pool,
+            scope,
+            trees: HashMap::new(),
+            asp_tree: LeanIMT::new(MAX_TREE_DEPTH as u8),
+            leaves: HashMap::new(),
+            roots: HashMap::new(),
+            nullifiers: HashMap::new(),
+            pool_nonce: 0,
+            shard_nonces: HashMap::new(),
+            queue: VecDeque::new(),
+            indexed: HashSet::new(),
+            last_signature: None,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// Rebuild the index from the instructions persisted in `store`
+    pub fn open<S: IndexStore>(
+        program_id: Pubkey,
+        pool: Pubkey,
+        scope: Option<[u8; 32]>,
+        store: &S,
+    ) -> Result<Self, IndexError> {
+        let mut indexer = Self::new(program_id, pool, scope);
+        let transactions = store.transactions().map_err(|err| IndexError::Store(err.to_string()))?;
+        for transaction in &transactions {
+            indexer.apply(transaction)?;
+            indexer.mark_indexed(&transaction.signature);
+        }
+        Ok(indexer)
+    }
+
+    /// Index new transactions from `source`, persisting each one to `store`
+    ///
+    /// Returns the number of transactions indexed. On a store error the in-memory index is
+    /// ahead of the store; reopen from the store before continuing.
+    pub fn sync<R: RecordSource + ?Sized, S: IndexStore>(&mut self, source: &mut R, store: &mut S) -> Result<usize, IndexError> {
+        let records = source.records(&self.pool, self.last_signature.as_deref())?;
+        self.index_records(&records, store)
+    }
+
+    /// `sync` for records fetched separately, e.g. without holding a lock on the indexer
+    pub fn index_records<S: IndexStore>(&mut self, records: &[TransactionRecord], store: &mut S) -> Result<usize, IndexError> {
+        let mut indexed = 0;
+        for record in records {
+            let Some((instructions, changes)) = self.ingest(record)? else {
+                continue;
+            };
+            store
+                .append(&instructions, &changes)
+                .map_err(|err| IndexError::Store(err.to_string()))?;
+            indexed += 1;
+        }
+        Ok(indexed)
+    }
+
+    /// Apply one confirmed transaction, returning its pool instructions and what they changed
+    ///
+    /// `None` if the transaction failed, was already indexed or doesn't touch the pool.
+    pub fn ingest(&mut self, record: &TransactionRecord) -> Result<Option<Ingested>, IndexError> {
+        let signature = record.signature();
+        if !record.succeeded() || self.indexed.contains(signature) {
+            return Ok(None);
+        }
+
+        let instructions = record.pool_instructions(&self.program_id, &self.pool)?;
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+
+        let mut changes = Vec::new();
+        let mut queue_inserts = Vec::new();
+        for instruction in &instructions {
+            let applied = self.apply(instruction)?;
+            // `ProcessQueue` is the only instruction logging its inserts
+            if matches!(
+                PrivacyPoolInstruction::try_from_slice(&instruction.instruction_data),
+                Ok(PrivacyPoolInstruction::ProcessQueue { .. })
+            ) {
+                queue_inserts.extend(applied.iter().filter_map(|change| match change {
+                    IndexChange::Leaf { leaf_index, commitment, .. } => Some(QueueInsertEvent {
+                        commitment: *commitment,
+                        leaf_index: *leaf_index,
+                    }),
+                    _ => None,
+                }));
+            }
+            changes.extend(applied);
+        }
+
+        // Truncated or missing logs can't be checked
+        let logs = record.meta.as_ref().and_then(|meta| meta.log_messages.as_ref());
+        if logs.is_some_and(|logs| !logs.iter().any(|line| line == "Log truncated"))
+            && queue_inserts != record.queue_inserts(&self.program_id)
+        {
+            return Err(IndexError::QueueEventMismatch {
+                signature: signature.to_string(),
+            });
+        }
+
+        self.mark_indexed(signature);
+        Ok(Some((instructions, changes)))
+    }
+
+    fn mark_indexed(&mut self, signature: &str) {
+        if self.indexed.insert(signature.to_string()) {
+            self.last_signature = Some(signature.to_string());
+        }
+    }
+
+    /// Apply a single pool instruction
+    pub fn apply(&mut self, transaction: &PoolTransaction) -> Result<Vec<IndexChange>, IndexError> {
+        let signature = transaction.signature.as_str();
+        let instruction = PrivacyPoolInstruction::try_from_slice(&transaction.instruction_data).map_err(|_| {
+            IndexError::InvalidTransaction {
+                signature: signature.to_string(),
+            }
+        })?;
+
+        let mut changes = Vec::new();
+        match instruction {
+            PrivacyPoolInstruction::InitializePool { asset_mint, .. } => {
+                self.scope.get_or_insert_with(|| pool_scope(&asset_mint));
+            }
+            PrivacyPoolInstruction::Deposit {
+                value,
+                precommitment_hash,
+                ..
+            } => {
+                let scope = self.require_scope(signature)?;
+                self.pool_nonce += 1;
+                let label = poseidon::compute_label(&scope, self.pool_nonce);
+                let commitment = poseidon::compute_commitment(value, &label, &precommitment_hash);
+                self.stats.deposits += 1;
+                self.stats.total_deposited = self.stats.total_deposited.saturating_add(value);
+
+                // Same rule as `WalletScanner`: a fourth account is the deposit queue
+                if transaction.account_count > 3 {
+                    self.queue.push_back((commitment, label));
+                } else {
+                    self.insert_batch(StateTree::Pool, &[(commitment, Some(label))], transaction, &mut changes)?;
+                }
+            }
+            PrivacyPoolInstruction::ProcessQueue { max_batch } => {
+                let batch = self.queue.len().min(max_batch as usize);
+                if batch > 0 {
+                    let entries: Vec<_> = self
+                        .queue
+                        .drain(..batch)
+                        .map(|(commitment, label)| (commitment, Some(label)))
+                        .collect();
+                    self.insert_batch(StateTree::Pool, &entries, transaction, &mut changes)?;
+                }
+            }
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index,
+                value,
+                precommitment_hash,
+                ..
+            } => {
+                let scope = self.require_scope(signature)?;
+                let nonce = self.shard_nonces.entry(shard_index).or_insert(0);
+                *nonce += 1;
+                let label = poseidon::compute_shard_label(&scope, shard_index, *nonce);
+                let commitment = poseidon::compute_commitment(value, &label, &precommitment_hash);
+                self.stats.deposits += 1;
+                self.stats.total_deposited = self.stats.total_deposited.saturating_add(value);
+
+                self.insert_batch(StateTree::Shard(shard_index), &[(commitment, Some(label))], transaction, &mut changes)?;
+            }
+            PrivacyPoolInstruction::Withdraw { proof_data, .. } => {
+                self.withdraw(StateTree::Pool, &proof_data, transaction, &mut changes)?;
+            }
+            PrivacyPoolInstruction::ShardWithdraw {
+                shard_index,
+                proof_data,
+                ..
+            } => {
+                self.withdraw(StateTree::Shard(shard_index), &proof_data, transaction, &mut changes)?;
+            }
+            PrivacyPoolInstruction::Ragequit { proof_data } => {
+                if proof_data.public_signals.len() < 4 {
+                    return Err(IndexError::InvalidTransaction {
+                        signature: signature.to_string(),
+                    });
+                }
+                self.stats.ragequits += 1;
+                self.stats.total_ragequit = self.stats.total_ragequit.saturating_add(proof_data.value());
+                self.spend(proof_data.nullifier_hash(), signature, &mut changes);
+            }
+            PrivacyPoolInstruction::WindDown => self.stats.is_dead = true,
+            PrivacyPoolInstruction::InitializeShard { .. } | PrivacyPoolInstruction::InitializeQueue => {}
+        }
+
+        self.stats.instructions += 1;
+        self.stats.pending_deposits = self.queue.len() as u64;
+        self.stats.last_slot = self.stats.last_slot.max(transaction.slot);
+        Ok(changes)
+    }
+
+    fn require_scope(&self, signature: &str) -> Result<[u8; 32], IndexError> {
+        self.scope.ok_or_else(|| IndexError::UnknownScope {
+            signature: signature.to_string(),
+        })
+    }
+
+    /// Insert commitments (and their labels into the ASP tree), then record the single new root
+    fn insert_batch(
+        &mut self,
+        tree: StateTree,
+        entries: &[([u8; 32], Option<[u8; 32]>)],
+        transaction: &PoolTransaction,
+        changes: &mut Vec<IndexChange>,
+    ) -> Result<(), IndexError> {
+        let tree_error = |reason| IndexError::Tree {
+            signature: transaction.signature.clone(),
+            reason,
+        };
+
+        let state_tree = self.trees.entry(tree).or_insert_with(|| LeanIMT::new(MAX_TREE_DEPTH as u8));
+        for (commitment, label) in entries {
+            let leaf_index = state_tree.insert(*commitment).map_err(tree_error)?;
+            self.leaves.entry(*commitment).or_insert((tree, leaf_index));
+            changes.push(IndexChange::Leaf {
+                tree,
+                leaf_index,
+                commitment: *commitment,
+            });
+
+            if let Some(label) = label {
+                let leaf_index = self.asp_tree.insert(*label).map_err(tree_error)?;
+                changes.push(IndexChange::AspLeaf { leaf_index, label: *label });
+            }
+        }
+
+        let root = RootEntry {
+            root: state_tree.root(),
+            slot: transaction.slot,
+        };
+        self.roots.entry(tree).or_default().push(root);
+        changes.push(IndexChange::Root {
+            tree,
+            root: root.root,
+            slot: root.slot,
+        });
+        Ok(())
+    }
+
+    fn withdraw(
+        &mut self,
+        tree: StateTree,
+        proof_data: &WithdrawProofData,
+        transaction: &PoolTransaction,
+        changes: &mut Vec<IndexChange>,
+    ) -> Result<(), IndexError> {
+        if proof_data.public_signals.len() < 8 {
+            return Err(IndexError::InvalidTransaction {
+                signature: transaction.signature.clone(),
+            });
+        }
+
+        self.stats.withdrawals += 1;
+        self.stats.total_withdrawn = self.stats.total_withdrawn.saturating_add(proof_data.withdrawn_value());
+        self.spend(proof_data.existing_nullifier_hash(), &transaction.signature, changes);
+        self.insert_batch(tree, &[(proof_data.new_commitment_hash(), None)], transaction, changes)
+    }
+
+    fn spend(&mut self, nullifier_hash: [u8; 32], signature: &str, changes: &mut Vec<IndexChange>) {
+        self.nullifiers.insert(nullifier_hash, signature.to_string());
+        changes.push(IndexChange::Nullifier {
+            nullifier_hash,
+            signature: signature.to_string(),
+        });
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn pool(&self) -> &Pubkey {
+        &self.pool
+    }
+
+    pub fn scope(&self) -> Option<[u8; 32]> {
+        self.scope
+    }
+
+    pub fn stats(&self) -> &PoolStats {
+        &self.stats
+    }
+
+    /// Signature of the most recently indexed transaction
+    pub fn last_signature(&self) -> Option<&str> {
+        self.last_signature.as_deref()
+    }
+
+    /// The pool's state tree or one of its shards, once it has a leaf
+    pub fn state_tree(&self, tree: StateTree) -> Option<&LeanIMT> {
+        self.trees.get(&tree)
+    }
+
+    pub fn asp_tree(&self) -> &LeanIMT {
+        &self.asp_tree
+    }
+
+    /// Every root of a state tree, oldest first
+    pub fn roots(&self, tree: StateTree) -> &[RootEntry] {
+        self.roots.get(&tree).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Shards with at least one leaf, in index order
+    pub fn shards(&self) -> Vec<u16> {
+        let mut shards: Vec<u16> = self
+            .trees
+            .keys()
+            .filter_map(|tree| match tree {
+                StateTree::Shard(index) => Some(*index),
+                StateTree::Pool => None,
+            })
+            .collect();
+        shards.sort_unstable();
+        shards
+    }
+
+    /// Transaction that spent `nullifier_hash`, if any
+    pub fn nullifier(&self, nullifier_hash: &[u8; 32]) -> Option<&str> {
+        self.nullifiers.get(nullifier_hash).map(String::as_str)
+    }
+
+    pub fn spent_nullifiers(&self) -> usize {
+        self.nullifiers.len()
+    }
+
+    /// Circuit proof for a commitment against the current root of its tree
+    pub fn state_proof(&self, commitment: &[u8; 32]) -> Option<(StateTree, CircuitMerkleProof)> {
+        let (tree, leaf_index) = *self.leaves.get(commitment)?;
+        let proof = self.trees[&tree].generate_circuit_proof(leaf_index, MAX_TREE_DEPTH).ok()?;
+        Some((tree, proof))
+    }
+
+    /// Circuit proof for a label against the current ASP root
+    pub fn asp_proof(&self, label: &[u8; 32]) -> Option<CircuitMerkleProof> {
+        let leaf_index = self.asp_tree.index_of(label)?;
+        self.asp_tree.generate_circuit_proof(leaf_index, MAX_TREE_DEPTH).ok()
+    }
+}
+
+/// Scope `InitializePool` derives from the asset mint
+fn pool_scope(asset_mint: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[b"PrivacyPool", asset_mint.as_ref()]).to_bytes()
+}
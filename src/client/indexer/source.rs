@@ -0,0 +1,447 @@
+//! Where the indexer reads transactions from
+//!
+//! Records use the JSON shape of the RPC `getTransaction` method (`"encoding": "json"`), so a
+//! dump file is simply one `getTransaction` result per line, oldest first, and the same
+//! parser serves a local validator.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use pinocchio::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::client::indexer::http;
+use crate::client::scanner::PoolTransaction;
+use crate::instructions::queue::QUEUE_INSERT_EVENT;
+
+/// A confirmed transaction, as returned by `getTransaction`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionRecord {
+    pub slot: u64,
+    pub transaction: RecordTransaction,
+    #[serde(default)]
+    pub meta: Option<RecordMeta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordTransaction {
+    pub signatures: Vec<String>,
+    pub message: RecordMessage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordMessage {
+    pub account_keys: Vec<String>,
+    pub instructions: Vec<RecordInstruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    /// Base58 instruction data
+    pub data: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordMeta {
+    /// `null` for successful transactions
+    #[serde(default)]
+    pub err: Option<Value>,
+    #[serde(default)]
+    pub log_messages: Option<Vec<String>>,
+    /// Keys loaded from lookup tables by v0 transactions, indexed after `account_keys`
+    #[serde(default)]
+    pub loaded_addresses: Option<LoadedAddresses>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>,
+}
+
+/// A `queue_insert` event logged by `ProcessQueue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueInsertEvent {
+    pub commitment: [u8; 32],
+    pub leaf_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    Io(String),
+    /// A record that isn't a `getTransaction` result
+    InvalidRecord { line: usize, reason: String },
+    /// The record references keys or data that don't decode
+    MalformedTransaction { signature: String },
+    Rpc(String),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Io(err) => write!(f, "{}", err),
+            SourceError::InvalidRecord { line, reason } => write!(f, "line {}: {}", line, reason),
+            SourceError::MalformedTransaction { signature } => write!(f, "transaction {} is malformed", signature),
+            SourceError::Rpc(err) => write!(f, "RPC request failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl TransactionRecord {
+    pub fn signature(&self) -> &str {
+        self.transaction.signatures.first().map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.meta.as_ref().is_none_or(|meta| meta.err.is_none())
+    }
+
+    fn account_key(&self, index: u8) -> Result<Pubkey, SourceError> {
+        let loaded = self.meta.as_ref().and_then(|meta| meta.loaded_addresses.as_ref());
+        let key = self
+            .transaction
+            .message
+            .account_keys
+            .iter()
+            .chain(loaded.into_iter().flat_map(|loaded| loaded.writable.iter().chain(&loaded.readonly)))
+            .nth(index as usize);
+
+        key.and_then(|key| decode_pubkey(key))
+            .ok_or_else(|| SourceError::MalformedTransaction {
+                signature: self.signature().to_string(),
+            })
+    }
+
+    /// Top-level instructions of `program_id` whose first account is `pool`, in order
+    ///
+    /// Instructions reaching the program through CPI are not visible in this format.
+    pub fn pool_instructions(&self, program_id: &Pubkey, pool: &Pubkey) -> Result<Vec<PoolTransaction>, SourceError> {
+        let mut instructions = Vec::new();
+        for instruction in &self.transaction.message.instructions {
+            if self.account_key(instruction.program_id_index)? != *program_id {
+                continue;
+            }
+            match instruction.accounts.first() {
+                Some(index) if self.account_key(*index)? == *pool => {}
+                _ => continue,
+            }
+
+            let instruction_data = bs58::decode(&instruction.data).into_vec().map_err(|_| {
+                SourceError::MalformedTransaction {
+                    signature: self.signature().to_string(),
+                }
+            })?;
+            instructions.push(PoolTransaction {
+                signature: self.signature().to_string(),
+                slot: self.slot,
+                instruction_data,
+                account_count: instruction.accounts.len(),
+            });
+        }
+        Ok(instructions)
+    }
+
+    /// `queue_insert` events `program_id` logged in this transaction, in order
+    pub fn queue_inserts(&self, program_id: &Pubkey) -> Vec<QueueInsertEvent> {
+        let program_id = bs58::encode(program_id).into_string();
+        let logs = self.meta.as_ref().and_then(|meta| meta.log_messages.as_deref()).unwrap_or_default();
+
+        // `Program data:` lines belong to the innermost program currently invoked
+        let mut invoked: Vec<&str> = Vec::new();
+        let mut events = Vec::new();
+        for line in logs {
+            if let Some(data) = line.strip_prefix("Program data: ") {
+                if invoked.last() == Some(&program_id.as_str()) {
+                    events.extend(parse_queue_insert(data));
+                }
+            } else if let Some(rest) = line.strip_prefix("Program ") {
+                let mut words = rest.split(' ');
+                match (words.next(), words.next()) {
+                    (Some(id), Some("invoke")) => invoked.push(id),
+                    (Some(_), Some("success" | "failed:")) => {
+                        invoked.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        events
+    }
+}
+
+/// `sol_log_data(&[QUEUE_INSERT_EVENT, commitment, leaf_index_le])`, each field base64
+fn parse_queue_insert(data: &str) -> Option<QueueInsertEvent> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let fields: Vec<Vec<u8>> = data.split(' ').map(|field| STANDARD.decode(field).ok()).collect::<Option<_>>()?;
+    match fields.as_slice() {
+        [name, commitment, leaf_index] if name.as_slice() == QUEUE_INSERT_EVENT => Some(QueueInsertEvent {
+            commitment: commitment.as_slice().try_into().ok()?,
+            leaf_index: u64::from_le_bytes(leaf_index.as_slice().try_into().ok()?),
+        }),
+        _ => None,
+    }
+}
+
+fn decode_pubkey(key: &str) -> Option<Pubkey> {
+    bs58::decode(key).into_vec().ok()?.try_into().ok()
+}
+
+/// Where the indexer gets a pool's transactions from
+pub trait RecordSource {
+    /// Transactions touching `pool`, oldest first, skipping those up to and including `until`
+    /// when the source can
+    ///
+    /// Returning already indexed transactions is fine, the indexer skips them.
+    fn records(&mut self, pool: &Pubkey, until: Option<&str>) -> Result<Vec<TransactionRecord>, SourceError>;
+}
+
+/// One `getTransaction` result per line, oldest first
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn parse(contents: &str) -> Result<Vec<TransactionRecord>, SourceError> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                serde_json::from_str(line).map_err(|err| SourceError::InvalidRecord {
+                    line: number + 1,
+                    reason: err.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl RecordSource for FileSource {
+    fn records(&mut self, _pool: &Pubkey, _until: Option<&str>) -> Result<Vec<TransactionRecord>, SourceError> {
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|err| SourceError::Io(format!("cannot read {}: {}", self.path.display(), err)))?;
+        Self::parse(&contents)
+    }
+}
+
+/// JSON-RPC over plain HTTP, meant for a local validator (`solana-test-validator`)
+pub struct RpcSource {
+    url: String,
+    next_id: u64,
+}
+
+/// `getSignaturesForAddress` page size, the RPC maximum
+const SIGNATURE_PAGE: usize = 1000;
+
+impl RpcSource {
+    /// `url` must be `http://host:port`; TLS isn't supported
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), next_id: 1 }
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Result<Value, SourceError> {
+        let request = json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params });
+        self.next_id += 1;
+
+        let body = http::post_json(&self.url, &request.to_string()).map_err(|err| SourceError::Rpc(err.to_string()))?;
+        let mut response: Value =
+            serde_json::from_str(&body).map_err(|err| SourceError::Rpc(format!("{}: {}", method, err)))?;
+        if let Some(error) = response.get("error") {
+            return Err(SourceError::Rpc(format!("{}: {}", method, error)));
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Successful signatures for `pool` newer than `until`, oldest first
+    fn signatures(&mut self, pool: &Pubkey, until: Option<&str>) -> Result<Vec<String>, SourceError> {
+        let address = bs58::encode(pool).into_string();
+        let mut signatures = Vec::new();
+        let mut before: Option<String> = None;
+        loop {
+            let mut config = json!({ "limit": SIGNATURE_PAGE, "commitment": "confirmed" });
+            if let Some(before) = &before {
+                config["before"] = json!(before);
+            }
+            if let Some(until) = until {
+                config["until"] = json!(until);
+            }
+
+            let page = self.call("getSignaturesForAddress", json!([address, config]))?;
+            let page = page.as_array().ok_or_else(|| SourceError::Rpc("getSignaturesForAddress: expected an array".into()))?;
+            for entry in page {
+                let signature = entry["signature"]
+                    .as_str()
+                    .ok_or_else(|| SourceError::Rpc("getSignaturesForAddress: missing signature".into()))?;
+                if entry["err"].is_null() {
+                    signatures.push(signature.to_string());
+                }
+                before = Some(signature.to_string());
+            }
+            if page.len() < SIGNATURE_PAGE {
+                break;
+            }
+        }
+
+        // Newest first from the node
+        signatures.reverse();
+        Ok(signatures)
+    }
+}
+
+impl RecordSource for RpcSource {
+    fn records(&mut self, pool: &Pubkey, until: Option<&str>) -> Result<Vec<TransactionRecord>, SourceError> {
+        let config = json!({ "encoding": "json", "commitment": "confirmed", "maxSupportedTransactionVersion": 0 });
+        self.signatures(pool, until)?
+            .into_iter()
+            .map(|signature| {
+                let record = self.call("getTransaction", json!([signature, config]))?;
+                serde_json::from_value(record).map_err(|err| SourceError::Rpc(format!("getTransaction {}: {}", signature, err)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    const PROGRAM: Pubkey = [7u8; 32];
+    const POOL: Pubkey = [8u8; 32];
+
+    fn record(signature: &str, logs: Vec<String>) -> TransactionRecord {
+        let key = |key: &Pubkey| bs58::encode(key).into_string();
+        TransactionRecord {
+            slot: 5,
+            transaction: RecordTransaction {
+                signatures: vec![signature.to_string()],
+                message: RecordMessage {
+                    account_keys: vec![key(&[1u8; 32]), key(&POOL), key(&PROGRAM)],
+                    instructions: vec![
+                        RecordInstruction { program_id_index: 2, accounts: vec![0], data: "2".into() },
+                        RecordInstruction { program_id_index: 2, accounts: vec![1, 3], data: bs58::encode([9u8, 4, 0]).into_string() },
+                    ],
+                },
+            },
+            meta: Some(RecordMeta {
+                err: None,
+                log_messages: Some(logs),
+                loaded_addresses: Some(LoadedAddresses { writable: vec![key(&[3u8; 32])], readonly: vec![] }),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_pool_instructions_and_queue_events() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let program = bs58::encode(PROGRAM).into_string();
+        let event = |commitment: u8, leaf_index: u64| {
+            format!(
+                "Program data: {} {} {}",
+                STANDARD.encode(QUEUE_INSERT_EVENT),
+                STANDARD.encode([commitment; 32]),
+                STANDARD.encode(leaf_index.to_le_bytes())
+            )
+        };
+        let logs = vec![
+            format!("Program {} invoke [1]", program),
+            event(1, 4),
+            "Program Other111 invoke [2]".to_string(),
+            // Logged by the inner program, not ours
+            event(2, 5),
+            "Program Other111 success".to_string(),
+            event(3, 5),
+            format!("Program {} success", program),
+        ];
+        let record = record("sig", logs);
+
+        let instructions = record.pool_instructions(&PROGRAM, &POOL).unwrap();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].instruction_data, vec![9, 4, 0]);
+        assert_eq!(instructions[0].account_count, 2);
+
+        assert_eq!(
+            record.queue_inserts(&PROGRAM),
+            vec![
+                QueueInsertEvent { commitment: [1u8; 32], leaf_index: 4 },
+                QueueInsertEvent { commitment: [3u8; 32], leaf_index: 5 },
+            ]
+        );
+
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(FileSource::parse(&format!("{}\n\n{}\n", line, line)).unwrap().len(), 2);
+        assert!(matches!(FileSource::parse("{}"), Err(SourceError::InvalidRecord { line: 1, .. })));
+    }
+
+    /// Answers each JSON-RPC request with `respond(method)` until `requests` are served
+    fn fake_rpc(requests: usize, respond: fn(&str) -> Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(length) = line.to_ascii_lowercase().strip_prefix("content-length: ") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": respond(request["method"].as_str().unwrap()) }).to_string();
+                // Chunked, like some RPC proxies answer
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                    response.len(),
+                    response
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_rpc_source_fetches_oldest_first() {
+        let url = fake_rpc(4, |method| match method {
+            "getSignaturesForAddress" => json!([
+                { "signature": "newest", "err": null },
+                { "signature": "failed", "err": { "InstructionError": [0, "Custom"] } },
+                { "signature": "oldest", "err": null },
+            ]),
+            _ => serde_json::to_value(record("fetched", Vec::new())).unwrap(),
+        });
+
+        let records = RpcSource::new(url).records(&POOL, None).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].signature(), "fetched");
+
+        assert!(RpcSource::new("https://localhost").records(&POOL, None).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! SQLite-backed `IndexStore`
+//!
+//! Tables mirror `IndexChange`; `tree` is `-1` for the pool's state tree and the shard index
+//! otherwise. Hashes are stored as 32-byte blobs.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::client::indexer::store::IndexStore;
+use crate::client::indexer::IndexChange;
+use crate::client::scanner::{PoolTransaction, StateTree};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS instructions (
+    id INTEGER PRIMARY KEY,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    account_count INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS instructions_signature ON instructions (signature);
+CREATE TABLE IF NOT EXISTS leaves (
+    tree INTEGER NOT NULL,
+    leaf_index INTEGER NOT NULL,
+    commitment BLOB NOT NULL,
+    PRIMARY KEY (tree, leaf_index)
+);
+CREATE INDEX IF NOT EXISTS leaves_commitment ON leaves (commitment);
+CREATE TABLE IF NOT EXISTS asp_leaves (
+    leaf_index INTEGER PRIMARY KEY,
+    label BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS roots (
+    id INTEGER PRIMARY KEY,
+    tree INTEGER NOT NULL,
+    root BLOB NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS nullifiers (
+    nullifier_hash BLOB PRIMARY KEY,
+    signature TEXT NOT NULL
+);
+";
+
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+}
+
+fn tree_id(tree: StateTree) -> i64 {
+    match tree {
+        StateTree::Pool => -1,
+        StateTree::Shard(index) => index as i64,
+    }
+}
+
+impl IndexStore for SqliteStore {
+    type Error = rusqlite::Error;
+
+    fn transactions(&self) -> Result<Vec<PoolTransaction>, Self::Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT signature, slot, data, account_count FROM instructions ORDER BY id")?;
+        let rows = statement.query_map([], |row| {
+            Ok(PoolTransaction {
+                signature: row.get(0)?,
+                slot: row.get::<_, i64>(1)? as u64,
+                instruction_data: row.get(2)?,
+                account_count: row.get::<_, i64>(3)? as usize,
+            })
+        })?;
+        rows.collect()
+    }
+
+    fn append(&mut self, instructions: &[PoolTransaction], changes: &[IndexChange]) -> Result<(), Self::Error> {
+        let transaction = self.connection.transaction()?;
+        for instruction in instructions {
+            transaction.execute(
+                "INSERT INTO instructions (signature, slot, data, account_count) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    instruction.signature,
+                    instruction.slot as i64,
+                    instruction.instruction_data,
+                    instruction.account_count as i64
+                ],
+            )?;
+        }
+
+        for change in changes {
+            match change {
+                IndexChange::Leaf { tree, leaf_index, commitment } => transaction.execute(
+                    "INSERT INTO leaves (tree, leaf_index, commitment) VALUES (?1, ?2, ?3)",
+                    params![tree_id(*tree), *leaf_index as i64, &commitment[..]],
+                )?,
+                IndexChange::AspLeaf { leaf_index, label } => transaction.execute(
+                    "INSERT INTO asp_leaves (leaf_index, label) VALUES (?1, ?2)",
+                    params![*leaf_index as i64, &label[..]],
+                )?,
+                IndexChange::Root { tree, root, slot } => transaction.execute(
+                    "INSERT INTO roots (tree, root, slot) VALUES (?1, ?2, ?3)",
+                    params![tree_id(*tree), &root[..], *slot as i64],
+                )?,
+                // A nullifier can only be spent once on-chain; keep the first spend if replayed
+                IndexChange::Nullifier { nullifier_hash, signature } => transaction.execute(
+                    "INSERT OR IGNORE INTO nullifiers (nullifier_hash, signature) VALUES (?1, ?2)",
+                    params![&nullifier_hash[..], signature],
+                )?,
+            };
+        }
+        transaction.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_store_round_trip() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let instruction = PoolTransaction {
+            signature: "tx1".to_string(),
+            slot: 7,
+            instruction_data: vec![9, 1, 0],
+            account_count: 2,
+        };
+        let changes = [
+            IndexChange::Leaf { tree: StateTree::Shard(2), leaf_index: 0, commitment: [1u8; 32] },
+            IndexChange::AspLeaf { leaf_index: 0, label: [2u8; 32] },
+            IndexChange::Root { tree: StateTree::Shard(2), root: [1u8; 32], slot: 7 },
+            IndexChange::Nullifier { nullifier_hash: [3u8; 32], signature: "tx1".to_string() },
+        ];
+        store.append(&[instruction.clone()], &changes).unwrap();
+
+        assert_eq!(store.transactions().unwrap(), vec![instruction]);
+        let tree: i64 = store
+            .connection
+            .query_row("SELECT tree FROM leaves WHERE commitment = ?1", [&[1u8; 32][..]], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tree, 2);
+    }
+}
@@ -0,0 +1,47 @@
+//! Persistence for the indexer
+//!
+//! A store keeps the raw pool instructions, which is all `Indexer::open` needs to rebuild
+//! the trees, plus the derived leaves, roots and nullifiers for anything querying the store
+//! directly. `sqlite::SqliteStore` is the persistent implementation.
+
+use std::fmt;
+
+use crate::client::indexer::IndexChange;
+use crate::client::scanner::PoolTransaction;
+
+pub trait IndexStore {
+    type Error: fmt::Display;
+
+    /// Every stored instruction, in the order it was appended
+    fn transactions(&self) -> Result<Vec<PoolTransaction>, Self::Error>;
+
+    /// Persist one transaction's pool instructions and the changes they made, atomically
+    fn append(&mut self, instructions: &[PoolTransaction], changes: &[IndexChange]) -> Result<(), Self::Error>;
+}
+
+/// Store that keeps everything in memory, for tests and one-off replays
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    pub instructions: Vec<PoolTransaction>,
+    pub changes: Vec<IndexChange>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl IndexStore for MemoryStore {
+    type Error = std::convert::Infallible;
+
+    fn transactions(&self) -> Result<Vec<PoolTransaction>, Self::Error> {
+        Ok(self.instructions.clone())
+    }
+
+    fn append(&mut self, instructions: &[PoolTransaction], changes: &[IndexChange]) -> Result<(), Self::Error> {
+        self.instructions.extend_from_slice(instructions);
+        self.changes.extend_from_slice(changes);
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Instruction builders for the pool's user-facing instructions
+//!
+//! Each builder returns a `solana_program` instruction with the data encoded by
+//! `PrivacyPoolInstruction::try_to_vec` and the accounts in the order the handler reads them.
+//! The program doesn't derive any addresses: pool, depositor and nullifier accounts are
+//! created by the caller, so their keys are passed in rather than computed here.
+
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+
+use crate::instructions::{PrivacyPoolInstruction, RagequitProofData, WithdrawProofData, WithdrawalData};
+use crate::BorshSerialize;
+
+fn instruction(
+    program_id: &Pubkey,
+    data: PrivacyPoolInstruction,
+    accounts: Vec<AccountMeta>,
+) -> Result<Instruction, &'static str> {
+    let data = data.try_to_vec().map_err(|_| "Instruction data is too large to encode")?;
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Accounts: pool (writable), authority (signer)
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_pool(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    authority: &Pubkey,
+    entrypoint_authority: &Pubkey,
+    max_tree_depth: u8,
+    asset_mint: &Pubkey,
+    root_history_capacity: u32,
+    root_expiry_slots: u64,
+) -> Result<Instruction, &'static str> {
+    instruction(
+        program_id,
+        PrivacyPoolInstruction::InitializePool {
+            entrypoint_authority: entrypoint_authority.to_bytes(),
+            max_tree_depth,
+            asset_mint: asset_mint.to_bytes(),
+            root_history_capacity,
+            root_expiry_slots,
+        },
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+/// Accounts: pool (writable), depositor account (writable), depositor (signer),
+/// and the deposit queue recorded in the pool (writable) once it has one
+pub fn deposit(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    depositor_account: &Pubkey,
+    depositor: &Pubkey,
+    value: u64,
+    precommitment_hash: [u8; 32],
+    queue: Option<&Pubkey>,
+) -> Result<Instruction, &'static str> {
+    let mut accounts = vec![
+        AccountMeta::new(*pool, false),
+        AccountMeta::new(*depositor_account, false),
+        AccountMeta::new_readonly(*depositor, true),
+    ];
+    if let Some(queue) = queue {
+        accounts.push(AccountMeta::new(*queue, false));
+    }
+
+    instruction(
+        program_id,
+        PrivacyPoolInstruction::Deposit {
+            depositor: depositor.to_bytes(),
+            value,
+            precommitment_hash,
+        },
+        accounts,
+    )
+}
+
+/// Accounts: pool (writable), processooor (signer), nullifier account (writable)
+///
+/// The processooor is taken from `withdrawal_data`, since the program requires it to sign.
+pub fn withdraw(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    nullifier_account: &Pubkey,
+    withdrawal_data: WithdrawalData,
+    proof_data: WithdrawProofData,
+) -> Result<Instruction, &'static str> {
+    let processooor = Pubkey::new_from_array(withdrawal_data.processooor);
+    instruction(
+        program_id,
+        PrivacyPoolInstruction::Withdraw {
+            withdrawal_data,
+            proof_data,
+        },
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(processooor, true),
+            AccountMeta::new(*nullifier_account, false),
+        ],
+    )
+}
+
+/// Accounts: pool, depositor account, ragequitter (signer), nullifier account (writable)
+pub fn ragequit(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    depositor_account: &Pubkey,
+    ragequitter: &Pubkey,
+    nullifier_account: &Pubkey,
+    proof_data: RagequitProofData,
+) -> Result<Instruction, &'static str> {
+    instruction(
+        program_id,
+        PrivacyPoolInstruction::Ragequit { proof_data },
+        vec![
+            AccountMeta::new_readonly(*pool, false),
+            AccountMeta::new_readonly(*depositor_account, false),
+            AccountMeta::new_readonly(*ragequitter, true),
+            AccountMeta::new(*nullifier_account, false),
+        ],
+    )
+}
+
+/// Accounts: pool (writable), entrypoint authority (signer)
+pub fn wind_down(program_id: &Pubkey, pool: &Pubkey, entrypoint_authority: &Pubkey) -> Result<Instruction, &'static str> {
+    instruction(
+        program_id,
+        PrivacyPoolInstruction::WindDown,
+        vec![
+            AccountMeta::new(*pool, false),
+            AccountMeta::new_readonly(*entrypoint_authority, true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BorshDeserialize;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn proof() -> ([u8; 64], [u8; 128], [u8; 64], Vec<[u8; 32]>) {
+        ([1u8; 64], [2u8; 128], [3u8; 64], vec![[4u8; 32], [5u8; 32], [6u8; 32]])
+    }
+
+    fn parse(instruction: &Instruction) -> PrivacyPoolInstruction {
+        PrivacyPoolInstruction::try_from_slice(&instruction.data).unwrap()
+    }
+
+    fn flags(instruction: &Instruction) -> Vec<(Pubkey, bool, bool)> {
+        instruction
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+            .collect()
+    }
+
+    #[test]
+    fn test_initialize_pool_and_deposit_round_trip() {
+        let program_id = key(0xff);
+
+        let ix = initialize_pool(&program_id, &key(1), &key(2), &key(3), 20, &key(4), 64, 1_000).unwrap();
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(flags(&ix), vec![(key(1), false, true), (key(2), true, false)]);
+        match parse(&ix) {
+            PrivacyPoolInstruction::InitializePool {
+                entrypoint_authority,
+                max_tree_depth,
+                asset_mint,
+                root_history_capacity,
+                root_expiry_slots,
+            } => {
+                assert_eq!(entrypoint_authority, key(3).to_bytes());
+                assert_eq!(max_tree_depth, 20);
+                assert_eq!(asset_mint, key(4).to_bytes());
+                assert_eq!(root_history_capacity, 64);
+                assert_eq!(root_expiry_slots, 1_000);
+            }
+            _ => panic!("Expected InitializePool instruction"),
+        }
+
+        let ix = deposit(&program_id, &key(1), &key(5), &key(6), 42, [7u8; 32], None).unwrap();
+        assert_eq!(
+            flags(&ix),
+            vec![(key(1), false, true), (key(5), false, true), (key(6), true, false)]
+        );
+        match parse(&ix) {
+            PrivacyPoolInstruction::Deposit {
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                assert_eq!(depositor, key(6).to_bytes());
+                assert_eq!(value, 42);
+                assert_eq!(precommitment_hash, [7u8; 32]);
+            }
+            _ => panic!("Expected Deposit instruction"),
+        }
+
+        let ix = deposit(&program_id, &key(1), &key(5), &key(6), 42, [7u8; 32], Some(&key(8))).unwrap();
+        assert_eq!(ix.accounts.len(), 4);
+        assert_eq!(flags(&ix)[3], (key(8), false, true));
+    }
+
+    #[test]
+    fn test_withdraw_and_ragequit_round_trip() {
+        let program_id = key(0xff);
+        let (proof_a, proof_b, proof_c, public_signals) = proof();
+
+        let ix = withdraw(
+            &program_id,
+            &key(1),
+            &key(2),
+            WithdrawalData {
+                processooor: key(9).to_bytes(),
+                data: b"relay".to_vec(),
+            },
+            WithdrawProofData {
+                proof_a,
+                proof_b,
+                proof_c,
+                public_signals: public_signals.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            flags(&ix),
+            vec![(key(1), false, true), (key(9), true, false), (key(2), false, true)]
+        );
+        match parse(&ix) {
+            PrivacyPoolInstruction::Withdraw {
+                withdrawal_data,
+                proof_data,
+            } => {
+                assert_eq!(withdrawal_data.processooor, key(9).to_bytes());
+                assert_eq!(withdrawal_data.data, b"relay".to_vec());
+                assert_eq!(proof_data.proof_a, proof_a);
+                assert_eq!(proof_data.proof_b, proof_b);
+                assert_eq!(proof_data.proof_c, proof_c);
+                assert_eq!(proof_data.public_signals, public_signals);
+            }
+            _ => panic!("Expected Withdraw instruction"),
+        }
+
+        let ix = ragequit(
+            &program_id,
+            &key(1),
+            &key(5),
+            &key(6),
+            &key(2),
+            RagequitProofData {
+                proof_a,
+                proof_b,
+                proof_c,
+                public_signals: public_signals.clone(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            flags(&ix),
+            vec![
+                (key(1), false, false),
+                (key(5), false, false),
+                (key(6), true, false),
+                (key(2), false, true),
+            ]
+        );
+        match parse(&ix) {
+            PrivacyPoolInstruction::Ragequit { proof_data } => {
+                assert_eq!(proof_data.proof_a, proof_a);
+                assert_eq!(proof_data.proof_b, proof_b);
+                assert_eq!(proof_data.proof_c, proof_c);
+                assert_eq!(proof_data.public_signals, public_signals);
+            }
+            _ => panic!("Expected Ragequit instruction"),
+        }
+    }
+
+    #[test]
+    fn test_wind_down_and_unbuilt_variants_round_trip() {
+        let ix = wind_down(&key(0xff), &key(1), &key(3)).unwrap();
+        assert_eq!(ix.data, vec![4]);
+        assert_eq!(flags(&ix), vec![(key(1), false, true), (key(3), true, false)]);
+        assert!(matches!(parse(&ix), PrivacyPoolInstruction::WindDown));
+
+        // Variants without a builder still serialize to what the parser reads
+        let data = PrivacyPoolInstruction::ProcessQueue { max_batch: 12 }.try_to_vec().unwrap();
+        assert!(matches!(
+            PrivacyPoolInstruction::try_from_slice(&data).unwrap(),
+            PrivacyPoolInstruction::ProcessQueue { max_batch: 12 }
+        ));
+
+        let data = PrivacyPoolInstruction::ShardDeposit {
+            shard_index: 3,
+            depositor: [1u8; 32],
+            value: 9,
+            precommitment_hash: [2u8; 32],
+        }
+        .try_to_vec()
+        .unwrap();
+        match PrivacyPoolInstruction::try_from_slice(&data).unwrap() {
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index,
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                assert_eq!((shard_index, depositor, value, precommitment_hash), (3, [1u8; 32], 9, [2u8; 32]));
+            }
+            _ => panic!("Expected ShardDeposit instruction"),
+        }
+    }
+}
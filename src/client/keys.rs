@@ -0,0 +1,159 @@
+//! Deterministic note secrets derived from a wallet master key
+//!
+//! Two master values are derived from the wallet seed, then every note secret is a
+//! Poseidon hash of a master value, a context and an index:
+//!
+//! - deposits: `Poseidon(master, scope, index)`, `index` counting the wallet's deposits into the pool
+//! - withdrawal change: `Poseidon(master, label, index)`, `index` counting withdrawals from the
+//!   deposit with that label (the change note keeps the label)
+//!
+//! With the seed alone every note can be regenerated and matched against on-chain commitments.
+
+use std::fmt;
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use solana_program::keccak;
+
+use crate::client::note::Note;
+use crate::crypto::{field, poseidon};
+
+/// Message a wallet signs to obtain its seed with `MasterKeys::from_signature`
+///
+/// Changing it changes every derived note, so it is versioned and must stay fixed.
+pub const SIGNATURE_MESSAGE: &[u8] = b"Privacy Pools note keys v1\n\nSign this message to derive your Privacy Pools notes. Only sign it on a trusted site.";
+
+/// Shortest accepted seed, in bytes
+pub const MIN_SEED_LEN: usize = 16;
+
+const MASTER_NULLIFIER_DOMAIN: &[u8] = b"privacy-pools/master-nullifier";
+const MASTER_SECRET_DOMAIN: &[u8] = b"privacy-pools/master-secret";
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct MasterKeys {
+    master_nullifier: [u8; 32],
+    master_secret: [u8; 32],
+}
+
+// Keep the keys out of logs
+impl fmt::Debug for MasterKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterKeys").finish_non_exhaustive()
+    }
+}
+
+/// Nullifier and secret of a single note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteSecrets {
+    pub nullifier: [u8; 32],
+    pub secret: [u8; 32],
+}
+
+impl NoteSecrets {
+    /// Value sent with the deposit, or committed to as the change of a withdrawal
+    pub fn precommitment(&self) -> [u8; 32] {
+        poseidon::compute_precommitment(&self.nullifier, &self.secret)
+    }
+
+    pub fn into_note(self, value: u64, label: [u8; 32]) -> Note {
+        Note::new(value, label, self.nullifier, self.secret)
+    }
+}
+
+impl MasterKeys {
+    pub fn from_seed(seed: &[u8]) -> Result<Self, &'static str> {
+        if seed.len() < MIN_SEED_LEN {
+            return Err("Seed is too short");
+        }
+
+        Ok(Self {
+            master_nullifier: hash_to_field(&[MASTER_NULLIFIER_DOMAIN, seed]),
+            master_secret: hash_to_field(&[MASTER_SECRET_DOMAIN, seed]),
+        })
+    }
+
+    /// Keys from an ed25519 signature over `SIGNATURE_MESSAGE`
+    ///
+    /// Ed25519 signatures are deterministic, so the same wallet always yields the same keys.
+    /// The signature isn't verified here; a wrong one simply derives keys that own nothing.
+    pub fn from_signature(signature: &[u8; 64]) -> Self {
+        Self::from_seed(signature).expect("signature is longer than MIN_SEED_LEN")
+    }
+
+    /// Secrets of the `index`-th deposit into the pool with this scope
+    pub fn deposit_secrets(&self, scope: &[u8; 32], index: u64) -> NoteSecrets {
+        self.derive(&reduce(scope), index)
+    }
+
+    /// Secrets of the change note of the `index`-th withdrawal from the deposit with this label
+    pub fn withdrawal_secrets(&self, label: &[u8; 32], index: u64) -> NoteSecrets {
+        self.derive(&reduce(label), index)
+    }
+
+    fn derive(&self, context: &[u8; 32], index: u64) -> NoteSecrets {
+        let mut index_bytes = [0u8; 32];
+        index_bytes[..8].copy_from_slice(&index.to_le_bytes());
+
+        NoteSecrets {
+            nullifier: poseidon::hash_three(&self.master_nullifier, context, &index_bytes),
+            secret: poseidon::hash_three(&self.master_secret, context, &index_bytes),
+        }
+    }
+}
+
+/// keccak256 of the concatenated parts, reduced into the scalar field
+fn hash_to_field(parts: &[&[u8]]) -> [u8; 32] {
+    reduce(&keccak::hashv(parts).to_bytes())
+}
+
+/// Scopes and labels are raw keccak outputs; reduce them so Poseidon always gets canonical inputs
+fn reduce(bytes: &[u8; 32]) -> [u8; 32] {
+    field::bigint_to_bytes(&Fr::from_le_bytes_mod_order(bytes).into_bigint())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: &[u8] = b"correct horse battery staple seed";
+    const SCOPE: [u8; 32] = [0xffu8; 32];
+
+    #[test]
+    fn test_derivation_is_deterministic_and_separated() {
+        let keys = MasterKeys::from_seed(SEED).unwrap();
+        assert_eq!(keys, MasterKeys::from_seed(SEED).unwrap());
+        assert_ne!(keys.master_nullifier, keys.master_secret);
+        assert!(field::is_in_field(&keys.master_nullifier));
+
+        let first = keys.deposit_secrets(&SCOPE, 0);
+        assert_eq!(first, keys.deposit_secrets(&SCOPE, 0));
+        assert_ne!(first.nullifier, first.secret);
+        assert_ne!(first, keys.deposit_secrets(&SCOPE, 1));
+        assert_ne!(first, keys.deposit_secrets(&[1u8; 32], 0));
+
+        let other = MasterKeys::from_seed(b"another wallet seed entirely").unwrap();
+        assert_ne!(first, other.deposit_secrets(&SCOPE, 0));
+
+        assert!(MasterKeys::from_seed(&[7u8; MIN_SEED_LEN - 1]).is_err());
+        assert_eq!(format!("{:?}", keys), "MasterKeys { .. }");
+    }
+
+    #[test]
+    fn test_derived_note_matches_commitment() {
+        let keys = MasterKeys::from_signature(&[5u8; 64]);
+        let label = poseidon::compute_label(&SCOPE, 3);
+
+        let deposit = keys.deposit_secrets(&SCOPE, 0);
+        let note = deposit.into_note(1_000, label);
+        assert_eq!(note.precommitment(), deposit.precommitment());
+        assert_eq!(
+            note.commitment(),
+            poseidon::compute_commitment(1_000, &label, &deposit.precommitment())
+        );
+
+        // Change notes keep the label but never reuse the deposit's nullifier
+        let change = keys.withdrawal_secrets(&label, 0);
+        assert_ne!(change.nullifier, deposit.nullifier);
+        assert_ne!(change, keys.withdrawal_secrets(&label, 1));
+    }
+}
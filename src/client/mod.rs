@@ -0,0 +1,25 @@
+//! Host-side helpers for wallets and relayers
+//! Everything here runs off-chain and is only built with the `client` feature.
+
+pub mod accounts;
+pub mod asp;
+pub mod backup;
+pub mod hex;
+pub mod idl;
+pub mod indexer;
+pub mod instructions;
+pub mod keys;
+pub mod note;
+pub mod preflight;
+pub mod proof;
+#[cfg(feature = "prover")]
+pub mod prover;
+pub mod scanner;
+pub mod snarkjs;
+pub mod transaction;
+pub mod vkey;
+pub mod witness;
+
+pub use keys::{MasterKeys, NoteSecrets};
+pub use note::Note;
+pub use witness::{WithdrawWitnessBuilder, WithdrawWitnessInputs};
@@ -0,0 +1,37 @@
+use crate::crypto::poseidon;
+
+/// A deposit note as held by its owner
+/// `label` is the deposit label assigned on-chain; `nullifier` and `secret` never leave the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    pub value: u64,
+    pub label: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub secret: [u8; 32],
+}
+
+impl Note {
+    pub fn new(value: u64, label: [u8; 32], nullifier: [u8; 32], secret: [u8; 32]) -> Self {
+        Self {
+            value,
+            label,
+            nullifier,
+            secret,
+        }
+    }
+
+    /// Poseidon(nullifier, secret), the value sent with the deposit
+    pub fn precommitment(&self) -> [u8; 32] {
+        poseidon::compute_precommitment(&self.nullifier, &self.secret)
+    }
+
+    /// Leaf inserted into the state tree for this note
+    pub fn commitment(&self) -> [u8; 32] {
+        poseidon::compute_commitment(self.value, &self.label, &self.precommitment())
+    }
+
+    /// Hash revealed when this note is spent
+    pub fn nullifier_hash(&self) -> [u8; 32] {
+        poseidon::compute_nullifier_hash(&self.nullifier)
+    }
+}
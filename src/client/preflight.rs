@@ -0,0 +1,337 @@
+//! Pre-flight checks for withdrawals
+//!
+//! Relayers run these against a snapshot of the pool account before submitting,
+//! so a withdrawal that would fail on-chain is rejected without paying fees.
+
+use std::fmt;
+
+use crate::client::accounts::NullifierAccount;
+use crate::client::hex;
+use crate::crypto::{poseidon, verifying_key};
+use crate::instructions::{WithdrawProofData, WithdrawalData};
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+use crate::state::{PoolStateLeanIMT, RootHistory};
+
+/// Number of public signals of the withdraw circuit
+pub const WITHDRAW_PUBLIC_SIGNALS: usize = WithdrawProofData::PUBLIC_SIGNALS;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightError {
+    /// Account data is not a pool account (wrong size or corrupt root history)
+    InvalidPoolAccount,
+    PoolNotInitialized,
+    /// The proof doesn't carry the withdraw circuit's public signals
+    InvalidPublicSignals { count: usize },
+    /// The proof was generated for a different withdrawal or pool
+    ContextMismatch { expected: [u8; 32], actual: [u8; 32] },
+    StateTreeTooDeep { depth: u8, max: u8 },
+    AspTreeTooDeep { depth: u8, max: u8 },
+    /// The state root is not in the pool's root history, or has expired
+    UnknownStateRoot { root: [u8; 32] },
+    /// The ASP root is not the pool's current one
+    UnknownAspRoot { root: [u8; 32] },
+    NullifierAlreadySpent { nullifier_hash: [u8; 32] },
+    /// Groth16 verification against `WITHDRAW_VERIFYING_KEY` failed
+    InvalidProof,
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreflightError::InvalidPoolAccount => write!(f, "account data is not a pool account"),
+            PreflightError::PoolNotInitialized => write!(f, "pool is not initialized"),
+            PreflightError::InvalidPublicSignals { count } => {
+                write!(f, "expected {} public signals, got {}", WITHDRAW_PUBLIC_SIGNALS, count)
+            }
+            PreflightError::ContextMismatch { expected, actual } => write!(
+                f,
+                "context mismatch: expected {}, proof has {}",
+                hex::encode(expected),
+                hex::encode(actual)
+            ),
+            PreflightError::StateTreeTooDeep { depth, max } => {
+                write!(f, "state tree depth {} exceeds maximum {}", depth, max)
+            }
+            PreflightError::AspTreeTooDeep { depth, max } => {
+                write!(f, "ASP tree depth {} exceeds maximum {}", depth, max)
+            }
+            PreflightError::UnknownStateRoot { root } => write!(f, "unknown or expired state root {}", hex::encode(root)),
+            PreflightError::UnknownAspRoot { root } => write!(f, "ASP root {} is not the pool's", hex::encode(root)),
+            PreflightError::NullifierAlreadySpent { nullifier_hash } => {
+                write!(f, "nullifier {} is already spent", hex::encode(nullifier_hash))
+            }
+            PreflightError::InvalidProof => write!(f, "Groth16 proof does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for PreflightError {}
+
+/// Copy of a pool account's data, as fetched from an RPC node
+pub struct PoolSnapshot {
+    pub state: PoolStateLeanIMT,
+    root_history: Vec<u8>,
+}
+
+impl PoolSnapshot {
+    pub fn from_account_data(data: &[u8]) -> Result<Self, PreflightError> {
+        if data.len() < PoolStateLeanIMT::space(1) {
+            return Err(PreflightError::InvalidPoolAccount);
+        }
+
+        let state = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const PoolStateLeanIMT) };
+        let mut root_history = data[PoolStateLeanIMT::LEN..].to_vec();
+        RootHistory::from_bytes_mut(&mut root_history).map_err(|_| PreflightError::InvalidPoolAccount)?;
+
+        Ok(Self { state, root_history })
+    }
+
+    /// Same acceptance rule as the program: see `RootHistory::is_known_root`
+    pub fn is_known_state_root(&self, root: &[u8; 32], current_slot: u64) -> bool {
+        let mut bytes = self.root_history.clone();
+        match RootHistory::from_bytes_mut(&mut bytes) {
+            Ok(history) => history.is_known_root(root, current_slot),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Whether a nullifier account (if it exists) marks `nullifier_hash` as spent
+pub fn is_nullifier_spent(nullifier_account_data: Option<&[u8]>, nullifier_hash: &[u8; 32]) -> bool {
+    nullifier_account_data
+        .and_then(|data| NullifierAccount::decode(data).ok())
+        .is_some_and(|state| state.is_spent && state.nullifier_hash == *nullifier_hash)
+}
+
+/// Run every check the program applies to a withdrawal, cheapest first,
+/// returning the first one that fails
+///
+/// `nullifier_account_data` is the nullifier account's data, or `None` if it doesn't exist yet.
+/// `current_slot` is used for root expiry and should be a recent slot.
+pub fn preflight_withdraw(
+    pool: &PoolSnapshot,
+    withdrawal_data: &WithdrawalData,
+    proof_data: &WithdrawProofData,
+    nullifier_account_data: Option<&[u8]>,
+    current_slot: u64,
+) -> Result<(), PreflightError> {
+    if pool.state.is_initialized == 0 {
+        return Err(PreflightError::PoolNotInitialized);
+    }
+
+    // The accessors below index into the signals
+    if proof_data.public_signals.len() != WITHDRAW_PUBLIC_SIGNALS {
+        return Err(PreflightError::InvalidPublicSignals {
+            count: proof_data.public_signals.len(),
+        });
+    }
+
+    let expected_context = poseidon::compute_context(withdrawal_data, &pool.state.scope);
+    if expected_context != proof_data.context() {
+        return Err(PreflightError::ContextMismatch {
+            expected: expected_context,
+            actual: proof_data.context(),
+        });
+    }
+
+    let max_depth = MAX_TREE_DEPTH as u8;
+    if proof_data.state_tree_depth() > max_depth {
+        return Err(PreflightError::StateTreeTooDeep {
+            depth: proof_data.state_tree_depth(),
+            max: max_depth,
+        });
+    }
+    if proof_data.asp_tree_depth() > max_depth {
+        return Err(PreflightError::AspTreeTooDeep {
+            depth: proof_data.asp_tree_depth(),
+            max: max_depth,
+        });
+    }
+
+    if !pool.is_known_state_root(&proof_data.state_root(), current_slot) {
+        return Err(PreflightError::UnknownStateRoot {
+            root: proof_data.state_root(),
+        });
+    }
+
+    if proof_data.asp_root() != pool.state.get_asp_root() {
+        return Err(PreflightError::UnknownAspRoot {
+            root: proof_data.asp_root(),
+        });
+    }
+
+    let nullifier_hash = proof_data.existing_nullifier_hash();
+    if is_nullifier_spent(nullifier_account_data, &nullifier_hash) {
+        return Err(PreflightError::NullifierAlreadySpent { nullifier_hash });
+    }
+
+    // groth16-solana unwraps the curve syscall results, so malformed points or signals panic
+    // instead of returning false; on-chain that aborts the transaction, here it's an invalid proof
+    let verified = std::panic::catch_unwind(|| verifying_key::verify_withdraw_proof(proof_data)).unwrap_or(false);
+    if !verified {
+        return Err(PreflightError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::field;
+    use pinocchio::pubkey::Pubkey;
+
+    const SCOPE: [u8; 32] = [3u8; 32];
+    const SLOT: u64 = 100;
+
+    fn pool_account_data() -> Vec<u8> {
+        let mut data = vec![0u8; PoolStateLeanIMT::space(8)];
+        let (state_bytes, history_bytes) = data.split_at_mut(PoolStateLeanIMT::LEN);
+        let pool = unsafe { &mut *(state_bytes.as_mut_ptr() as *mut PoolStateLeanIMT) };
+        let key = Pubkey::from([1u8; 32]);
+        pool.initialize(key, key, key, key, SCOPE);
+
+        let mut roots = RootHistory::initialize(history_bytes, 8, 0).unwrap();
+        pool.insert_state_commitment([5u8; 32], &mut roots, SLOT).unwrap();
+        pool.insert_asp_label([6u8; 32]).unwrap();
+        data
+    }
+
+    fn withdrawal() -> WithdrawalData {
+        WithdrawalData {
+            processooor: Pubkey::from([9u8; 32]),
+            data: vec![1, 2, 3],
+        }
+    }
+
+    /// Proof data whose public signals pass every check before Groth16 verification
+    fn proof_for(pool: &PoolSnapshot, withdrawal: &WithdrawalData) -> WithdrawProofData {
+        let mut depth = [0u8; 32];
+        depth[0] = 1;
+        WithdrawProofData {
+            proof_a: [0u8; 64],
+            proof_b: [0u8; 128],
+            proof_c: [0u8; 64],
+            public_signals: vec![
+                [7u8; 32],
+                [8u8; 32],
+                [0u8; 32],
+                pool.state.get_state_root(),
+                depth,
+                pool.state.get_asp_root(),
+                depth,
+                poseidon::compute_context(withdrawal, &SCOPE),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_preflight_reports_failing_check() {
+        let data = pool_account_data();
+        let pool = PoolSnapshot::from_account_data(&data).unwrap();
+        let withdrawal = withdrawal();
+
+        let mut proof = proof_for(&pool, &withdrawal);
+        proof.public_signals[7] = [0u8; 32];
+        assert!(matches!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::ContextMismatch { .. })
+        ));
+
+        let mut proof = proof_for(&pool, &withdrawal);
+        proof.public_signals[4][0] = MAX_TREE_DEPTH as u8 + 1;
+        assert!(matches!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::StateTreeTooDeep { .. })
+        ));
+
+        let mut proof = proof_for(&pool, &withdrawal);
+        proof.public_signals[3] = [0xaau8; 32];
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::UnknownStateRoot { root: [0xaau8; 32] })
+        );
+
+        let mut proof = proof_for(&pool, &withdrawal);
+        proof.public_signals[5] = [0xbbu8; 32];
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::UnknownAspRoot { root: [0xbbu8; 32] })
+        );
+
+        let proof = proof_for(&pool, &withdrawal);
+        let mut nullifier = vec![1u8];
+        nullifier.extend_from_slice(&[8u8; 32]);
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, Some(&nullifier), SLOT),
+            Err(PreflightError::NullifierAlreadySpent { nullifier_hash: [8u8; 32] })
+        );
+
+        // Everything else checks out, only the (empty) proof is wrong
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::InvalidProof)
+        );
+
+        let mut proof = proof_for(&pool, &withdrawal);
+        proof.public_signals.pop();
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::InvalidPublicSignals { count: 7 })
+        );
+    }
+
+    #[test]
+    fn test_preflight_realistic_proof() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../../scripts/realistic-withdraw-proof.json")).unwrap();
+        let proof = crate::client::snarkjs::withdraw_proof_from_snarkjs(
+            &fixture["rawProof"].to_string(),
+            &fixture["rawPublicSignals"].to_string(),
+        )
+        .unwrap();
+
+        let decimal = |value: &serde_json::Value| field::decimal_to_bytes(value.as_str().unwrap()).unwrap();
+        let trees = &fixture["trees"];
+        assert_eq!(proof.state_root(), decimal(&trees["stateRoot"]));
+        assert_eq!(proof.state_tree_depth() as u64, trees["stateDepth"].as_u64().unwrap());
+        assert_eq!(proof.asp_root(), decimal(&trees["aspRoot"]));
+        assert_eq!(proof.asp_tree_depth() as u64, trees["aspDepth"].as_u64().unwrap());
+        assert_eq!(proof.withdrawn_value().to_string(), fixture["withdrawal"]["withdrawnValue"].as_str().unwrap());
+
+        // A pool holding the scenario's deposits knows the proof's state root
+        let mut data = vec![0u8; PoolStateLeanIMT::space(8)];
+        let (state_bytes, history_bytes) = data.split_at_mut(PoolStateLeanIMT::LEN);
+        let state = unsafe { &mut *(state_bytes.as_mut_ptr() as *mut PoolStateLeanIMT) };
+        let key = Pubkey::from([1u8; 32]);
+        state.initialize(key, key, key, key, SCOPE);
+        let mut roots = RootHistory::initialize(history_bytes, 8, 0).unwrap();
+        for deposit in fixture["deposits"].as_array().unwrap() {
+            state.insert_state_commitment(decimal(&deposit["commitment"]), &mut roots, SLOT).unwrap();
+        }
+        let pool = PoolSnapshot::from_account_data(&data).unwrap();
+        assert!(pool.is_known_state_root(&proof.state_root(), SLOT));
+
+        // The fixture's context is a constant rather than one computed for a pool
+        let withdrawal = withdrawal();
+        assert_eq!(
+            preflight_withdraw(&pool, &withdrawal, &proof, None, SLOT),
+            Err(PreflightError::ContextMismatch {
+                expected: poseidon::compute_context(&withdrawal, &SCOPE),
+                actual: decimal(&fixture["rawPublicSignals"][7]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_pool_snapshot_rejects_bad_data() {
+        assert!(PoolSnapshot::from_account_data(&[0u8; 16]).is_err());
+
+        let mut data = pool_account_data();
+        data.push(0);
+        assert_eq!(
+            PoolSnapshot::from_account_data(&data).err(),
+            Some(PreflightError::InvalidPoolAccount)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Encoding of arkworks Groth16 proofs into the byte layout the program accepts
+//!
+//! groth16-solana hands the proof straight to the alt_bn128 syscalls, which take
+//! big-endian coordinates, G2 elements as (c1, c0) pairs, and expect `proof_a`
+//! to be negated so the pairing check reduces to a single product equal to one.
+
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+
+use crate::crypto::field;
+use crate::instructions::{RagequitProofData, WithdrawProofData};
+
+/// Big-endian encoding of a base field element
+pub fn fq_to_be_bytes(value: &Fq) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.into_bigint().to_bytes_be());
+    bytes
+}
+
+fn fq2_to_be_bytes(value: &Fq2) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&fq_to_be_bytes(&value.c1));
+    bytes[32..].copy_from_slice(&fq_to_be_bytes(&value.c0));
+    bytes
+}
+
+/// `x || y`, both big-endian
+pub fn encode_g1(point: &G1Affine) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&fq_to_be_bytes(&point.x));
+    bytes[32..].copy_from_slice(&fq_to_be_bytes(&point.y));
+    bytes
+}
+
+/// Encoding used for `proof_a`: the point is negated before encoding
+pub fn encode_g1_negated(point: &G1Affine) -> [u8; 64] {
+    encode_g1(&-*point)
+}
+
+/// `x.c1 || x.c0 || y.c1 || y.c0`, all big-endian
+pub fn encode_g2(point: &G2Affine) -> [u8; 128] {
+    let mut bytes = [0u8; 128];
+    bytes[..64].copy_from_slice(&fq2_to_be_bytes(&point.x));
+    bytes[64..].copy_from_slice(&fq2_to_be_bytes(&point.y));
+    bytes
+}
+
+/// Public signals are carried in the instruction as 32-byte little-endian
+/// field elements, in the order snarkjs outputs them
+pub fn encode_public_signal(value: &Fr) -> [u8; 32] {
+    field::bigint_to_bytes(&value.into_bigint())
+}
+
+pub fn encode_withdraw_proof(a: &G1Affine, b: &G2Affine, c: &G1Affine, public_signals: &[Fr]) -> WithdrawProofData {
+    WithdrawProofData {
+        proof_a: encode_g1_negated(a),
+        proof_b: encode_g2(b),
+        proof_c: encode_g1(c),
+        public_signals: public_signals.iter().map(encode_public_signal).collect(),
+    }
+}
+
+pub fn encode_ragequit_proof(a: &G1Affine, b: &G2Affine, c: &G1Affine, public_signals: &[Fr]) -> RagequitProofData {
+    RagequitProofData {
+        proof_a: encode_g1_negated(a),
+        proof_b: encode_g2(b),
+        proof_c: encode_g1(c),
+        public_signals: public_signals.iter().map(encode_public_signal).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::g2::{G2_GENERATOR_X, G2_GENERATOR_Y};
+    use ark_ff::Field;
+
+    #[test]
+    fn test_g1_negation_and_layout() {
+        let generator = G1Affine::new(Fq::ONE, Fq::from(2u64));
+        let encoded = encode_g1(&generator);
+        let negated = encode_g1_negated(&generator);
+
+        // G1 generator is (1, 2)
+        assert_eq!(encoded[31], 1);
+        assert_eq!(encoded[63], 2);
+        assert_eq!(negated[..32], encoded[..32]);
+        assert_eq!(negated[32..], fq_to_be_bytes(&(Fq::ZERO - Fq::from(2u64))));
+    }
+
+    #[test]
+    fn test_g2_coefficient_order() {
+        let generator = G2Affine::new(G2_GENERATOR_X, G2_GENERATOR_Y);
+        let encoded = encode_g2(&generator);
+
+        assert_eq!(encoded[..32], fq_to_be_bytes(&generator.x.c1));
+        assert_eq!(encoded[32..64], fq_to_be_bytes(&generator.x.c0));
+        assert_eq!(encoded[64..96], fq_to_be_bytes(&generator.y.c1));
+        assert_eq!(encoded[96..], fq_to_be_bytes(&generator.y.c0));
+    }
+}
@@ -0,0 +1,82 @@
+//! The iden3 binary container shared by `.r1cs` and `.zkey` files
+//!
+//! A 4-byte magic, a version, then numbered sections, each prefixed with its id (u32) and
+//! byte length (u64). Every integer is little-endian.
+
+use std::collections::HashMap;
+use std::io;
+
+use ark_ff::BigInt;
+
+pub(super) fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Bounds-checked cursor over a file or section
+pub(super) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("file is truncated"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(super) fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    pub(super) fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    /// A 32-byte integer, not reduced or converted in any way
+    pub(super) fn u256(&mut self) -> io::Result<BigInt<4>> {
+        let bytes = self.take(32)?;
+        let mut limbs = [0u64; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().expect("8 bytes"));
+        }
+        Ok(BigInt::new(limbs))
+    }
+}
+
+/// Sections of a version 1 file, by id
+pub(super) struct Sections<'a> {
+    sections: HashMap<u32, &'a [u8]>,
+}
+
+impl<'a> Sections<'a> {
+    pub(super) fn parse(bytes: &'a [u8], magic: &[u8; 4]) -> io::Result<Self> {
+        let mut file = Reader { bytes };
+        if file.take(4)? != magic {
+            return Err(invalid(format!("not a {} file", String::from_utf8_lossy(magic))));
+        }
+        let version = file.u32()?;
+        if version != 1 {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+
+        let mut sections = HashMap::new();
+        for _ in 0..file.u32()? {
+            let id = file.u32()?;
+            let len = usize::try_from(file.u64()?).map_err(|_| invalid("section too large"))?;
+            // Only the first copy of a section is used, as snarkjs does
+            let body = file.take(len)?;
+            sections.entry(id).or_insert(body);
+        }
+        Ok(Self { sections })
+    }
+
+    pub(super) fn get(&self, id: u32) -> io::Result<Reader<'a>> {
+        self.sections
+            .get(&id)
+            .map(|bytes| Reader { bytes })
+            .ok_or_else(|| invalid(format!("section {} is missing", id)))
+    }
+}
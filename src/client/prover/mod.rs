@@ -0,0 +1,343 @@
+//! Native withdraw prover, replacing the snarkjs round trip
+//!
+//! Needs the three artifacts `build-circuits.sh` produces, all checked in:
+//! `trusted-setup/final-keys/withdraw_final.zkey` (from the same setup as
+//! `withdraw_vkey.json`, the key the program verifies against), `build/withdraw/withdraw.r1cs`
+//! and the witness generator `build/withdraw/withdraw_js/withdraw.wasm`. The zkey is checked
+//! against the r1cs when loading, and every witness against the r1cs before proving, so a
+//! stale wasm or a key from another circuit fails loudly instead of yielding proofs the
+//! program rejects. Everything runs locally, nothing is fetched.
+//!
+//! The circom formats are read in-crate (`r1cs`, `zkey`, `wasm`); `reduction` has the snarkjs
+//! witness map the zkey's H points are laid out for.
+
+mod binfile;
+pub mod r1cs;
+pub mod reduction;
+pub mod wasm;
+pub mod zkey;
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, ProvingKey};
+use ark_relations::r1cs::ConstraintMatrices;
+use ark_std::rand::thread_rng;
+use ark_std::UniformRand;
+use serde_json::Value;
+
+use crate::client::proof::encode_withdraw_proof;
+use crate::client::witness::WithdrawWitnessInputs;
+use crate::crypto::field;
+use crate::instructions::WithdrawProofData;
+
+use r1cs::R1cs;
+use reduction::CircomReduction;
+use wasm::WitnessCalculator;
+use zkey::{Coefficient, Zkey};
+
+#[derive(Debug)]
+pub enum ProverError {
+    /// An artifact could not be opened or parsed
+    Io(std::io::Error),
+    /// The zkey, r1cs and wasm don't describe the same circuit
+    Mismatch(&'static str),
+    /// The wasm could not be loaded or rejected the inputs
+    Witness(String),
+    /// The witness violates this constraint of the r1cs
+    Unsatisfied(usize),
+    /// Proof generation failed
+    Proof(String),
+    /// The generated proof doesn't verify against the zkey's own verifying key
+    InvalidProof,
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::Io(err) => write!(f, "failed to read circuit artifact: {}", err),
+            ProverError::Mismatch(err) => write!(f, "circuit artifacts don't match: {}", err),
+            ProverError::Witness(err) => write!(f, "witness generation failed: {}", err),
+            ProverError::Unsatisfied(index) => write!(f, "witness violates constraint {}", index),
+            ProverError::Proof(err) => write!(f, "proof generation failed: {}", err),
+            ProverError::InvalidProof => write!(f, "generated proof does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+impl From<std::io::Error> for ProverError {
+    fn from(err: std::io::Error) -> Self {
+        ProverError::Io(err)
+    }
+}
+
+pub struct WithdrawProver {
+    proving_key: ProvingKey<Bn254>,
+    r1cs: R1cs,
+    matrices: ConstraintMatrices<Fr>,
+    witness_calculator: WitnessCalculator,
+}
+
+impl WithdrawProver {
+    /// `zkey_path` is the withdraw proving key, `r1cs_path` the circuit's constraints and
+    /// `wasm_path` its circom witness generator
+    pub fn new(
+        zkey_path: impl AsRef<Path>,
+        r1cs_path: impl AsRef<Path>,
+        wasm_path: impl AsRef<Path>,
+    ) -> Result<Self, ProverError> {
+        let zkey = Zkey::from_reader(File::open(zkey_path)?)?;
+        let r1cs = R1cs::from_reader(File::open(r1cs_path)?)?;
+        let witness_calculator = WitnessCalculator::from_file(wasm_path)?;
+
+        if r1cs.num_public() != WithdrawProofData::PUBLIC_SIGNALS {
+            return Err(ProverError::Mismatch("r1cs doesn't have the withdraw public signals"));
+        }
+        check_zkey_matches(&zkey, &r1cs)?;
+
+        Ok(Self {
+            proving_key: zkey.proving_key,
+            matrices: r1cs.matrices(),
+            r1cs,
+            witness_calculator,
+        })
+    }
+
+    /// Generate a withdraw proof, encoded as the program expects it
+    pub fn prove(&mut self, inputs: &WithdrawWitnessInputs) -> Result<WithdrawProofData, ProverError> {
+        let circuit_inputs = circuit_inputs(&inputs.to_json())?;
+        let full_assignment = self.witness_calculator.calculate_witness(&circuit_inputs)?;
+        if full_assignment.len() != self.r1cs.num_wires {
+            return Err(ProverError::Mismatch("wasm witness size differs from the r1cs"));
+        }
+        if let Some(index) = self.r1cs.first_unsatisfied(&full_assignment) {
+            return Err(ProverError::Unsatisfied(index));
+        }
+
+        let mut rng = thread_rng();
+        let r = Fr::rand(&mut rng);
+        let s = Fr::rand(&mut rng);
+
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &self.proving_key,
+            r,
+            s,
+            &self.matrices,
+            self.matrices.num_instance_variables,
+            self.matrices.num_constraints,
+            &full_assignment,
+        )
+        .map_err(|err| ProverError::Proof(err.to_string()))?;
+
+        // Wire 0 is the constant one, public signals follow in snarkjs order
+        let public_signals = &full_assignment[1..self.matrices.num_instance_variables];
+
+        let verifying_key = prepare_verifying_key(&self.proving_key.vk);
+        let valid = Groth16::<Bn254>::verify_proof(&verifying_key, &proof, public_signals)
+            .map_err(|err| ProverError::Proof(err.to_string()))?;
+        if !valid {
+            return Err(ProverError::InvalidProof);
+        }
+
+        Ok(encode_withdraw_proof(&proof.a, &proof.b, &proof.c, public_signals))
+    }
+}
+
+/// The zkey must have been set up for exactly these constraints, or its proofs won't verify
+fn check_zkey_matches(zkey: &Zkey, r1cs: &R1cs) -> Result<(), ProverError> {
+    let num_constraints = r1cs.constraints.len();
+    if zkey.num_vars != r1cs.num_wires || zkey.num_public != r1cs.num_public() {
+        return Err(ProverError::Mismatch("zkey and r1cs have different wires"));
+    }
+    if zkey.domain_size != (num_constraints + r1cs.num_public() + 1).next_power_of_two() {
+        return Err(ProverError::Mismatch("zkey domain doesn't fit the r1cs"));
+    }
+
+    let mut expected = Vec::new();
+    for (index, constraint) in r1cs.constraints.iter().enumerate() {
+        for (matrix, lc) in [(0, &constraint.a), (1, &constraint.b)] {
+            expected.extend(lc.iter().map(|(wire, value)| Coefficient {
+                matrix,
+                constraint: index,
+                wire: *wire,
+                value: *value,
+            }));
+        }
+    }
+    expected.extend((0..=r1cs.num_public()).map(|wire| Coefficient {
+        matrix: 0,
+        constraint: num_constraints + wire,
+        wire,
+        value: Fr::from(1u64),
+    }));
+
+    let mut actual = zkey.coefficients.clone();
+    expected.sort();
+    actual.sort();
+    if actual != expected {
+        return Err(ProverError::Mismatch("zkey was set up for different constraints"));
+    }
+    Ok(())
+}
+
+/// Turn circuit input JSON (decimal strings and arrays of them) into witness calculator inputs
+fn circuit_inputs(json: &Value) -> Result<Vec<(String, Vec<Fr>)>, ProverError> {
+    let object = json
+        .as_object()
+        .ok_or_else(|| ProverError::Witness("inputs must be a JSON object".into()))?;
+
+    let parse = |value: &Value| -> Result<Fr, ProverError> {
+        value
+            .as_str()
+            .and_then(|s| field::decimal_to_bytes(s).ok())
+            .map(|bytes| Fr::from_le_bytes_mod_order(&bytes))
+            .ok_or_else(|| ProverError::Witness(format!("invalid input value {}", value)))
+    };
+
+    object
+        .iter()
+        .map(|(name, value)| {
+            let values = match value {
+                Value::Array(items) => items.iter().map(parse).collect::<Result<Vec<_>, _>>()?,
+                _ => vec![parse(value)?],
+            };
+            Ok((name.clone(), values))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::note::Note;
+    use crate::client::proof::{encode_g1, encode_g1_negated, encode_g2, encode_public_signal};
+    use crate::crypto::circuit_keys::COMMITMENT_VERIFYING_KEY;
+    use crate::crypto::merkle_tree::LeanIMT;
+    use crate::crypto::verifying_key::{verify_proof, verify_withdraw_proof};
+    use std::path::PathBuf;
+
+    /// `WITHDRAW_ZKEY` / `WITHDRAW_R1CS` / `WITHDRAW_WASM`, or the checked-in artifacts
+    fn artifact(var: &str, default: &str) -> PathBuf {
+        std::env::var_os(var)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join(default))
+    }
+
+    fn withdraw_prover() -> WithdrawProver {
+        WithdrawProver::new(
+            artifact("WITHDRAW_ZKEY", "trusted-setup/final-keys/withdraw_final.zkey"),
+            artifact("WITHDRAW_R1CS", "build/withdraw/withdraw.r1cs"),
+            artifact("WITHDRAW_WASM", "build/withdraw/withdraw_js/withdraw.wasm"),
+        )
+        .unwrap()
+    }
+
+    fn inputs(withdrawn_value: u64) -> (WithdrawWitnessInputs, LeanIMT, LeanIMT) {
+        let note = Note::new(5_000, [4u8; 32], [5u8; 32], [6u8; 32]);
+        let mut state_tree = LeanIMT::new(32);
+        let mut asp_tree = LeanIMT::new(32);
+        for i in 1..=3u8 {
+            state_tree.insert([i; 32]).unwrap();
+            asp_tree.insert([i + 20; 32]).unwrap();
+        }
+        state_tree.insert(note.commitment()).unwrap();
+        asp_tree.insert(note.label).unwrap();
+
+        let inputs = WithdrawWitnessInputs::builder(note)
+            .withdrawn_value(withdrawn_value)
+            .context([9u8; 32])
+            .new_note_secrets([7u8; 32], [8u8; 32])
+            .state_tree(&state_tree)
+            .unwrap()
+            .asp_tree(&asp_tree)
+            .unwrap()
+            .build()
+            .unwrap();
+        (inputs, state_tree, asp_tree)
+    }
+
+    #[test]
+    fn test_prove_and_verify_withdraw() {
+        let (inputs, state_tree, asp_tree) = inputs(1_000);
+        let proof = withdraw_prover().prove(&inputs).unwrap();
+
+        assert_eq!(proof.new_commitment_hash(), inputs.new_commitment());
+        assert_eq!(proof.existing_nullifier_hash(), inputs.note.nullifier_hash());
+        assert_eq!(proof.withdrawn_value(), 1_000);
+        assert_eq!(proof.state_root(), state_tree.root());
+        assert_eq!(proof.asp_root(), asp_tree.root());
+        assert_eq!(proof.context(), [9u8; 32]);
+        assert!(verify_withdraw_proof(&proof), "zkey doesn't match WITHDRAW_VERIFYING_KEY");
+    }
+
+    #[test]
+    fn test_overdrawn_note_is_rejected_by_the_circuit() {
+        // The builder refuses this, so bump the amount in the circuit inputs directly
+        let (inputs, _, _) = inputs(1_000);
+        let mut json = inputs.to_json();
+        json["withdrawnValue"] = Value::from("6000");
+
+        let mut prover = withdraw_prover();
+        let err = prover.witness_calculator.calculate_witness(&circuit_inputs(&json).unwrap());
+        assert!(matches!(err, Err(ProverError::Witness(message)) if message.contains("assert failed")));
+    }
+
+    #[test]
+    fn test_mismatched_artifacts_are_rejected() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let err = WithdrawProver::new(
+            root.join("trusted-setup/final-keys/commitment_final.zkey"),
+            root.join("build/withdraw/withdraw.r1cs"),
+            root.join("build/withdraw/withdraw_js/withdraw.wasm"),
+        );
+        assert!(matches!(err, Err(ProverError::Mismatch(_))));
+    }
+
+    /// The readers and the witness map against keys snarkjs itself generated
+    #[test]
+    fn test_snarkjs_commitment_key_proves() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let zkey = Zkey::from_reader(File::open(root.join("trusted-setup/final-keys/commitment_final.zkey")).unwrap()).unwrap();
+        let r1cs = R1cs::from_reader(File::open(root.join("build/commitment/commitment_main.r1cs")).unwrap()).unwrap();
+        check_zkey_matches(&zkey, &r1cs).unwrap();
+
+        let mut calculator =
+            WitnessCalculator::from_file(root.join("build/commitment/commitment_main_js/commitment_main.wasm")).unwrap();
+        let witness = calculator
+            .calculate_witness(&[
+                ("value".to_string(), vec![Fr::from(5_000u64)]),
+                ("label".to_string(), vec![Fr::from(11u64)]),
+                ("nullifier".to_string(), vec![Fr::from(12u64)]),
+                ("secret".to_string(), vec![Fr::from(13u64)]),
+            ])
+            .unwrap();
+        assert_eq!(r1cs.first_unsatisfied(&witness), None);
+
+        let matrices = r1cs.matrices();
+        let mut rng = thread_rng();
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &zkey.proving_key,
+            Fr::rand(&mut rng),
+            Fr::rand(&mut rng),
+            &matrices,
+            matrices.num_instance_variables,
+            matrices.num_constraints,
+            &witness,
+        )
+        .unwrap();
+
+        let signals: Vec<[u8; 32]> = witness[1..matrices.num_instance_variables].iter().map(encode_public_signal).collect();
+        assert!(verify_proof(
+            &encode_g1_negated(&proof.a),
+            &encode_g2(&proof.b),
+            &encode_g1(&proof.c),
+            &signals,
+            &COMMITMENT_VERIFYING_KEY,
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+//! Reader for circom's `.r1cs` constraint files
+//!
+//! The header gives the wire counts: the constant wire 0, then the outputs, public inputs and
+//! private inputs of the main component. Each constraint `A * B = C` is stored as three sparse
+//! linear combinations over wires, with 32-byte little-endian coefficients.
+
+use std::io::{self, Read};
+
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_relations::r1cs::ConstraintMatrices;
+
+use super::binfile::{invalid, Reader, Sections};
+
+const HEADER_SECTION: u32 = 1;
+const CONSTRAINTS_SECTION: u32 = 2;
+
+/// Sparse linear combination as (wire, coefficient) pairs
+pub type LinearCombination = Vec<(usize, Fr)>;
+
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+#[derive(Debug, Clone)]
+pub struct R1cs {
+    /// Wires, including the constant wire 0
+    pub num_wires: usize,
+    pub num_public_outputs: usize,
+    pub num_public_inputs: usize,
+    pub num_private_inputs: usize,
+    pub constraints: Vec<Constraint>,
+}
+
+fn field(reader: &mut Reader<'_>) -> io::Result<Fr> {
+    Fr::from_bigint(reader.u256()?).ok_or_else(|| invalid("coefficient is not reduced"))
+}
+
+fn linear_combination(reader: &mut Reader<'_>, num_wires: usize) -> io::Result<LinearCombination> {
+    let len = reader.u32()?;
+    (0..len)
+        .map(|_| {
+            let wire = reader.u32()? as usize;
+            if wire >= num_wires {
+                return Err(invalid(format!("constraint refers to unknown wire {}", wire)));
+            }
+            Ok((wire, field(reader)?))
+        })
+        .collect()
+}
+
+impl R1cs {
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let sections = Sections::parse(&bytes, b"r1cs")?;
+
+        let mut header = sections.get(HEADER_SECTION)?;
+        if header.u32()? != 32 || header.u256()? != Fr::MODULUS {
+            return Err(invalid("r1cs is not over the BN254 scalar field"));
+        }
+        let num_wires = header.u32()? as usize;
+        let num_public_outputs = header.u32()? as usize;
+        let num_public_inputs = header.u32()? as usize;
+        let num_private_inputs = header.u32()? as usize;
+        let _num_labels = header.u64()?;
+        let num_constraints = header.u32()?;
+        if num_public_outputs + num_public_inputs + num_private_inputs >= num_wires {
+            return Err(invalid("inconsistent r1cs header"));
+        }
+
+        let mut body = sections.get(CONSTRAINTS_SECTION)?;
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok(Constraint {
+                    a: linear_combination(&mut body, num_wires)?,
+                    b: linear_combination(&mut body, num_wires)?,
+                    c: linear_combination(&mut body, num_wires)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            num_wires,
+            num_public_outputs,
+            num_public_inputs,
+            num_private_inputs,
+            constraints,
+        })
+    }
+
+    /// Public signals (outputs, then public inputs), not counting wire 0
+    pub fn num_public(&self) -> usize {
+        self.num_public_outputs + self.num_public_inputs
+    }
+
+    /// Index of the first constraint `witness` violates, if any
+    pub fn first_unsatisfied(&self, witness: &[Fr]) -> Option<usize> {
+        let eval = |lc: &LinearCombination| {
+            lc.iter()
+                .fold(Fr::zero(), |acc, (wire, coeff)| acc + witness[*wire] * coeff)
+        };
+        self.constraints
+            .iter()
+            .position(|c| eval(&c.a) * eval(&c.b) != eval(&c.c))
+    }
+
+    /// The constraints in the layout `ark_groth16` proves from
+    pub fn matrices(&self) -> ConstraintMatrices<Fr> {
+        let rows = |lc: fn(&Constraint) -> &LinearCombination| -> Vec<Vec<(Fr, usize)>> {
+            self.constraints
+                .iter()
+                .map(|c| lc(c).iter().map(|(wire, coeff)| (*coeff, *wire)).collect())
+                .collect()
+        };
+        let (a, b, c) = (rows(|c| &c.a), rows(|c| &c.b), rows(|c| &c.c));
+        ConstraintMatrices {
+            num_instance_variables: self.num_public() + 1,
+            num_witness_variables: self.num_wires - self.num_public() - 1,
+            num_constraints: self.constraints.len(),
+            a_num_non_zero: a.iter().map(Vec::len).sum(),
+            b_num_non_zero: b.iter().map(Vec::len).sum(),
+            c_num_non_zero: c.iter().map(Vec::len).sum(),
+            a,
+            b,
+            c,
+        }
+    }
+}
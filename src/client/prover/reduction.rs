@@ -0,0 +1,76 @@
+//! The snarkjs R1CS-to-QAP witness map
+//!
+//! arkworks computes H as (AB - C) / Z over the evaluation domain. snarkjs instead evaluates
+//! AB - C on the odd points of a domain twice that size, where Z is constant, and its zkeys
+//! carry the H bases for exactly those points; proving against a snarkjs key needs the same
+//! map. The instance map is the usual one: like libsnark, snarkjs appends a `wire * 1 = 0`
+//! row for wire 0 and every public signal.
+
+use ark_ff::PrimeField;
+use ark_groth16::r1cs_to_qap::{evaluate_constraint, LibsnarkReduction, R1CSToQAP};
+use ark_poly::EvaluationDomain;
+use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
+
+pub struct CircomReduction;
+
+impl R1CSToQAP for CircomReduction {
+    #[allow(clippy::type_complexity)]
+    fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
+        cs: ConstraintSystemRef<F>,
+        t: &F,
+    ) -> Result<(Vec<F>, Vec<F>, Vec<F>, F, usize, usize), SynthesisError> {
+        LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
+    }
+
+    fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
+        matrices: &ConstraintMatrices<F>,
+        num_inputs: usize,
+        num_constraints: usize,
+        full_assignment: &[F],
+    ) -> Result<Vec<F>, SynthesisError> {
+        let domain = D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        let domain_size = domain.size();
+
+        let mut a = vec![F::zero(); domain_size];
+        let mut b = vec![F::zero(); domain_size];
+        for (i, (a_row, b_row)) in matrices.a.iter().zip(&matrices.b).enumerate().take(num_constraints) {
+            a[i] = evaluate_constraint(a_row, full_assignment);
+            b[i] = evaluate_constraint(b_row, full_assignment);
+        }
+        // The appended input rows only have an A term
+        a[num_constraints..num_constraints + num_inputs].copy_from_slice(&full_assignment[..num_inputs]);
+
+        let mut c = vec![F::zero(); domain_size];
+        for i in 0..num_constraints {
+            c[i] = a[i] * b[i];
+        }
+
+        // Move each polynomial from the domain to its coset by the 2n-th root of unity
+        let shift = D::new(2 * domain_size)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?
+            .element(1);
+        let to_odd_points = |evals: &mut Vec<F>| {
+            domain.ifft_in_place(evals);
+            D::distribute_powers_and_mul_by_const(evals, shift, F::one());
+            domain.fft_in_place(evals);
+        };
+        to_odd_points(&mut a);
+        to_odd_points(&mut b);
+        to_odd_points(&mut c);
+
+        Ok(a.iter().zip(&b).zip(&c).map(|((a, b), c)| *a * b - c).collect())
+    }
+
+    fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
+        max_power: usize,
+        t: F,
+        _: F,
+        delta_inverse: F,
+    ) -> Result<Vec<F>, SynthesisError> {
+        // Lagrange bases of the doubled domain at t, odd points only, over delta
+        let mut scalars: Vec<F> = (0..2 * max_power + 1).map(|i| delta_inverse * t.pow([i as u64])).collect();
+        let domain = D::new(scalars.len()).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        domain.ifft_in_place(&mut scalars);
+        Ok(scalars.into_iter().skip(1).step_by(2).collect())
+    }
+}
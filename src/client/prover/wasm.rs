@@ -0,0 +1,158 @@
+//! Witness generation with the circom 2 wasm, run under `wasmi`
+//!
+//! This is the same exchange circom's `witness_calculator.js` performs: every field element
+//! goes through the module's shared read/write memory as 32-bit limbs, inputs are addressed by
+//! the FNV-1a hash of their name, and the full witness is read back one wire at a time.
+
+use std::path::Path;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use wasmi::core::Trap;
+use wasmi::{Engine, Instance, Linker, Module, Store, TypedFunc};
+
+use super::ProverError;
+
+pub struct WitnessCalculator {
+    store: Store<()>,
+    instance: Instance,
+    n32: usize,
+}
+
+fn wasm_error(err: impl std::fmt::Display) -> ProverError {
+    ProverError::Witness(err.to_string())
+}
+
+/// Error codes the circom runtime passes to `exceptionHandler`, as `witness_calculator.js` names them
+fn exception_message(code: i32) -> &'static str {
+    match code {
+        1 => "signal not found",
+        2 => "too many signals set",
+        3 => "signal already set",
+        4 => "assert failed",
+        5 => "not enough memory",
+        6 => "input signal array access exceeds the size",
+        _ => "unknown error",
+    }
+}
+
+/// 64-bit FNV-1a of a signal name, split into the two halves `setInputSignal` takes
+fn fnv(name: &str) -> (u32, u32) {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    ((hash >> 32) as u32, hash as u32)
+}
+
+impl WitnessCalculator {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ProverError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProverError> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, bytes).map_err(wasm_error)?;
+        let mut store = Store::new(&engine, ());
+
+        // The runtime imports only report errors and debug output; inputs the circuit
+        // rejects (a failed assert) surface as the trap from `exceptionHandler`
+        let mut linker = <Linker<()>>::new(&engine);
+        linker
+            .func_wrap("runtime", "exceptionHandler", |code: i32| -> Result<(), Trap> {
+                Err(Trap::new(exception_message(code)))
+            })
+            .and_then(|linker| linker.func_wrap("runtime", "printErrorMessage", || {}))
+            .and_then(|linker| linker.func_wrap("runtime", "writeBufferMessage", || {}))
+            .and_then(|linker| linker.func_wrap("runtime", "showSharedRWMemory", || {}))
+            .map_err(wasm_error)?;
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(wasm_error)?;
+
+        let mut calculator = Self {
+            store,
+            instance,
+            n32: 0,
+        };
+        let version: i32 = calculator.call("getVersion", ())?;
+        if version != 2 {
+            return Err(ProverError::Witness(format!("unsupported circom wasm version {}", version)));
+        }
+        calculator.n32 = calculator.call::<(), i32>("getFieldNumLen32", ())? as usize;
+
+        // Refuse a witness generator compiled for another curve
+        calculator.call::<(), ()>("getRawPrime", ())?;
+        if calculator.read_shared()? != Fr::MODULUS.to_bytes_le() {
+            return Err(ProverError::Witness("wasm was not compiled for BN254".into()));
+        }
+
+        Ok(calculator)
+    }
+
+    fn func<Params, Results>(&self, name: &str) -> Result<TypedFunc<Params, Results>, ProverError>
+    where
+        Params: wasmi::WasmParams,
+        Results: wasmi::WasmResults,
+    {
+        self.instance
+            .get_typed_func(&self.store, name)
+            .map_err(|err| ProverError::Witness(format!("{}: {}", name, err)))
+    }
+
+    fn call<Params, Results>(&mut self, name: &str, params: Params) -> Result<Results, ProverError>
+    where
+        Params: wasmi::WasmParams,
+        Results: wasmi::WasmResults,
+    {
+        let func = self.func::<Params, Results>(name)?;
+        func.call(&mut self.store, params).map_err(wasm_error)
+    }
+
+    /// Shared memory as little-endian bytes (limb 0 is the least significant)
+    fn read_shared(&mut self) -> Result<Vec<u8>, ProverError> {
+        let mut bytes = Vec::with_capacity(self.n32 * 4);
+        for i in 0..self.n32 {
+            let limb: i32 = self.call("readSharedRWMemory", i as i32)?;
+            bytes.extend_from_slice(&(limb as u32).to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    fn write_shared(&mut self, value: &Fr) -> Result<(), ProverError> {
+        let bytes = value.into_bigint().to_bytes_le();
+        for (i, limb) in bytes.chunks(4).enumerate() {
+            let limb = u32::from_le_bytes(limb.try_into().expect("4-byte chunk"));
+            self.call::<(i32, i32), ()>("writeSharedRWMemory", (i as i32, limb as i32))?;
+        }
+        Ok(())
+    }
+
+    /// Run the circuit on `inputs` (signal name, values) and return every wire, wire 0 first
+    pub fn calculate_witness(&mut self, inputs: &[(String, Vec<Fr>)]) -> Result<Vec<Fr>, ProverError> {
+        self.call::<i32, ()>("init", 1)?;
+
+        let set_input = self.func::<(i32, i32, i32), ()>("setInputSignal")?;
+        for (name, values) in inputs {
+            let (msb, lsb) = fnv(name);
+            for (i, value) in values.iter().enumerate() {
+                self.write_shared(value)?;
+                set_input
+                    .call(&mut self.store, (msb as i32, lsb as i32, i as i32))
+                    .map_err(|err| ProverError::Witness(format!("input {}[{}]: {}", name, i, err)))?;
+            }
+        }
+
+        let size: i32 = self.call("getWitnessSize", ())?;
+        let mut witness = Vec::with_capacity(size as usize);
+        for i in 0..size {
+            self.call::<i32, ()>("getWitness", i)?;
+            witness.push(Fr::from_le_bytes_mod_order(&self.read_shared()?));
+        }
+        Ok(witness)
+    }
+}
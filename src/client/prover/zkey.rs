@@ -0,0 +1,174 @@
+//! Reader for snarkjs Groth16 `.zkey` proving keys
+//!
+//! After the Groth16 header (sizes and the verifying key points) come IC, the A and B
+//! coefficients of the QAP, then the A, B1, B2, C (private wires only) and H point vectors.
+//! Coordinates are stored in Montgomery form and coefficients are scaled by R², the forms
+//! snarkjs computes with. The H points are the odd Lagrange bases of a domain twice the QAP's,
+//! which is what `CircomReduction` proves against.
+
+use std::io::{self, Read};
+
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+
+use super::binfile::{invalid, Reader, Sections};
+
+const HEADER_SECTION: u32 = 1;
+const GROTH_HEADER_SECTION: u32 = 2;
+const IC_SECTION: u32 = 3;
+const COEFFS_SECTION: u32 = 4;
+const A_SECTION: u32 = 5;
+const B1_SECTION: u32 = 6;
+const B2_SECTION: u32 = 7;
+const C_SECTION: u32 = 8;
+const H_SECTION: u32 = 9;
+
+const GROTH16: u32 = 1;
+
+/// One nonzero QAP coefficient
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Coefficient {
+    /// 0 for A, 1 for B
+    pub matrix: u32,
+    pub constraint: usize,
+    pub wire: usize,
+    pub value: Fr,
+}
+
+/// A zkey's proving key, plus the QAP it was generated for
+#[derive(Debug, Clone)]
+pub struct Zkey {
+    pub proving_key: ProvingKey<Bn254>,
+    /// Wires, including the constant wire 0
+    pub num_vars: usize,
+    /// Public signals, not counting wire 0
+    pub num_public: usize,
+    pub domain_size: usize,
+    /// The circuit's A and B coefficients, followed by the `wire * 1 = 0` rows snarkjs appends
+    /// for wire 0 and each public signal
+    pub coefficients: Vec<Coefficient>,
+}
+
+fn fq(reader: &mut Reader<'_>) -> io::Result<Fq> {
+    let value = reader.u256()?;
+    if value >= Fq::MODULUS {
+        return Err(invalid("coordinate is not reduced"));
+    }
+    Ok(Fq::new_unchecked(value))
+}
+
+/// Stored as value * R² mod r; reading it as Montgomery form leaves one factor of R
+fn coefficient(reader: &mut Reader<'_>) -> io::Result<Fr> {
+    let value = reader.u256()?;
+    if value >= Fr::MODULUS {
+        return Err(invalid("coefficient is not reduced"));
+    }
+    let r_inverse = Fr::from_bigint(Fr::R).and_then(|r| r.inverse()).expect("R is invertible");
+    Ok(Fr::new_unchecked(value) * r_inverse)
+}
+
+/// (0, 0) encodes the point at infinity
+fn g1(reader: &mut Reader<'_>) -> io::Result<G1Affine> {
+    let (x, y) = (fq(reader)?, fq(reader)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(invalid("G1 point is not on the curve"));
+    }
+    Ok(point)
+}
+
+fn g2(reader: &mut Reader<'_>) -> io::Result<G2Affine> {
+    let x = Fq2::new(fq(reader)?, fq(reader)?);
+    let y = Fq2::new(fq(reader)?, fq(reader)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(invalid("G2 point is not in the prime order subgroup"));
+    }
+    Ok(point)
+}
+
+fn g1_vec(mut reader: Reader<'_>, len: usize) -> io::Result<Vec<G1Affine>> {
+    (0..len).map(|_| g1(&mut reader)).collect()
+}
+
+impl Zkey {
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let sections = Sections::parse(&bytes, b"zkey")?;
+
+        if sections.get(HEADER_SECTION)?.u32()? != GROTH16 {
+            return Err(invalid("zkey is not a Groth16 key"));
+        }
+
+        let mut header = sections.get(GROTH_HEADER_SECTION)?;
+        if header.u32()? != 32 || header.u256()? != Fq::MODULUS || header.u32()? != 32 || header.u256()? != Fr::MODULUS {
+            return Err(invalid("zkey is not over BN254"));
+        }
+        let num_vars = header.u32()? as usize;
+        let num_public = header.u32()? as usize;
+        let domain_size = header.u32()? as usize;
+        if num_public >= num_vars || !domain_size.is_power_of_two() {
+            return Err(invalid("inconsistent zkey header"));
+        }
+        let alpha_g1 = g1(&mut header)?;
+        let beta_g1 = g1(&mut header)?;
+        let beta_g2 = g2(&mut header)?;
+        let gamma_g2 = g2(&mut header)?;
+        let delta_g1 = g1(&mut header)?;
+        let delta_g2 = g2(&mut header)?;
+
+        let mut coeffs = sections.get(COEFFS_SECTION)?;
+        let coefficients = (0..coeffs.u32()?)
+            .map(|_| {
+                let matrix = coeffs.u32()?;
+                let constraint = coeffs.u32()? as usize;
+                let wire = coeffs.u32()? as usize;
+                if matrix > 1 || constraint >= domain_size || wire >= num_vars {
+                    return Err(invalid("coefficient out of range"));
+                }
+                let value = coefficient(&mut coeffs)?;
+                Ok(Coefficient {
+                    matrix,
+                    constraint,
+                    wire,
+                    value,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        let mut b2 = sections.get(B2_SECTION)?;
+        let proving_key = ProvingKey {
+            vk: VerifyingKey {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                gamma_abc_g1: g1_vec(sections.get(IC_SECTION)?, num_public + 1)?,
+            },
+            beta_g1,
+            delta_g1,
+            a_query: g1_vec(sections.get(A_SECTION)?, num_vars)?,
+            b_g1_query: g1_vec(sections.get(B1_SECTION)?, num_vars)?,
+            b_g2_query: (0..num_vars).map(|_| g2(&mut b2)).collect::<io::Result<_>>()?,
+            h_query: g1_vec(sections.get(H_SECTION)?, domain_size)?,
+            l_query: g1_vec(sections.get(C_SECTION)?, num_vars - num_public - 1)?,
+        };
+
+        Ok(Self {
+            proving_key,
+            num_vars,
+            num_public,
+            domain_size,
+            coefficients,
+        })
+    }
+}
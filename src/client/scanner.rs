@@ -0,0 +1,558 @@
+//! Recovery of a wallet's notes from the pool's transaction history
+//!
+//! The program only logs events for queued and shard deposits, so the scanner replays the
+//! instructions themselves: every successful pool instruction, oldest first, rebuilds the
+//! state trees exactly as the program does (labels from the pool and shard nonces, queued
+//! deposits inserted by `ProcessQueue`). Deposits whose precommitment matches a derived
+//! deposit secret, and withdrawal change commitments matching a derived change secret,
+//! belong to the wallet; a note is spent once its nullifier hash shows up in a withdrawal
+//! or ragequit.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use pinocchio::pubkey::Pubkey;
+
+use crate::client::keys::MasterKeys;
+use crate::client::note::Note;
+use crate::crypto::merkle_tree::LeanIMT;
+use crate::crypto::poseidon;
+use crate::instructions::{PrivacyPoolInstruction, WithdrawProofData};
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+use crate::BorshDeserialize;
+
+/// Deposit indices tried past the last one found, like a BIP44 gap limit
+pub const DEPOSIT_GAP_LIMIT: u64 = 20;
+
+/// One successful instruction addressed to the pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub instruction_data: Vec<u8>,
+    /// Number of accounts the instruction was given; a deposit with a queue account is queued
+    pub account_count: usize,
+}
+
+/// Where the scanner gets a pool's history from, e.g. an RPC node or an indexer
+pub trait TransactionSource {
+    type Error;
+
+    /// Successful instructions addressed to `pool`, in execution order
+    fn pool_transactions(&self, pool: &Pubkey) -> Result<Vec<PoolTransaction>, Self::Error>;
+}
+
+/// Transaction source backed by a map, for tests and offline replays
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTransactionSource {
+    transactions: HashMap<Pubkey, Vec<PoolTransaction>>,
+}
+
+impl InMemoryTransactionSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, pool: Pubkey, transaction: PoolTransaction) {
+        self.transactions.entry(pool).or_default().push(transaction);
+    }
+}
+
+impl TransactionSource for InMemoryTransactionSource {
+    type Error = std::convert::Infallible;
+
+    fn pool_transactions(&self, pool: &Pubkey) -> Result<Vec<PoolTransaction>, Self::Error> {
+        Ok(self.transactions.get(pool).cloned().unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError<E> {
+    Source(E),
+    /// Instruction data the program would have rejected
+    InvalidTransaction { signature: String },
+    /// A state tree rejected an insert
+    Tree { signature: String, reason: &'static str },
+}
+
+impl<E: fmt::Display> fmt::Display for ScanError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Source(err) => write!(f, "transaction source failed: {}", err),
+            ScanError::InvalidTransaction { signature } => write!(f, "transaction {} is not a valid pool instruction", signature),
+            ScanError::Tree { signature, reason } => write!(f, "transaction {}: {}", signature, reason),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ScanError<E> {}
+
+/// The state tree a commitment lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StateTree {
+    Pool,
+    Shard(u16),
+}
+
+/// A note owned by the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedNote {
+    pub note: Note,
+    pub tree: StateTree,
+    pub leaf_index: u64,
+    /// Transaction that created the commitment
+    pub signature: String,
+    /// Transaction that revealed the nullifier hash, if any
+    pub spent_by: Option<String>,
+}
+
+impl ScannedNote {
+    pub fn is_spent(&self) -> bool {
+        self.spent_by.is_some()
+    }
+}
+
+/// Everything recovered from a scan
+#[derive(Debug)]
+pub struct ScanResult {
+    /// Owned notes in the order they were created
+    pub notes: Vec<ScannedNote>,
+    /// Rebuilt state trees, for generating withdrawal proofs
+    pub trees: HashMap<StateTree, LeanIMT>,
+    /// Index of the next deposit secret to use
+    pub next_deposit_index: u64,
+}
+
+impl ScanResult {
+    pub fn unspent(&self) -> impl Iterator<Item = &ScannedNote> {
+        self.notes.iter().filter(|note| !note.is_spent())
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.unspent().map(|note| note.note.value).sum()
+    }
+}
+
+struct QueuedDeposit {
+    commitment: [u8; 32],
+    owned: Option<Note>,
+    signature: String,
+}
+
+pub struct WalletScanner<'a> {
+    keys: &'a MasterKeys,
+    pool: Pubkey,
+    scope: [u8; 32],
+}
+
+impl<'a> WalletScanner<'a> {
+    pub fn new(keys: &'a MasterKeys, pool: Pubkey, scope: [u8; 32]) -> Self {
+        Self { keys, pool, scope }
+    }
+
+    pub fn scan<S: TransactionSource>(&self, source: &S) -> Result<ScanResult, ScanError<S::Error>> {
+        let transactions = source.pool_transactions(&self.pool).map_err(ScanError::Source)?;
+        let mut state = ScanState::new(self);
+        for transaction in &transactions {
+            state.apply(transaction)?;
+        }
+
+        Ok(ScanResult {
+            notes: state.notes,
+            trees: state.trees,
+            next_deposit_index: state.next_deposit_index,
+        })
+    }
+}
+
+struct ScanState<'s, 'a> {
+    scanner: &'s WalletScanner<'a>,
+    trees: HashMap<StateTree, LeanIMT>,
+    pool_nonce: u64,
+    shard_nonces: HashMap<u16, u64>,
+    queue: VecDeque<QueuedDeposit>,
+    /// Precommitments of deposit secrets not seen yet, within the gap limit
+    deposit_lookahead: HashMap<[u8; 32], u64>,
+    lookahead_end: u64,
+    next_deposit_index: u64,
+    /// Change notes created so far per label, the index of the next change secret
+    change_counts: HashMap<[u8; 32], u64>,
+    notes: Vec<ScannedNote>,
+    /// Nullifier hash of each unspent owned note, to its position in `notes`
+    unspent: HashMap<[u8; 32], usize>,
+}
+
+impl<'s, 'a> ScanState<'s, 'a> {
+    fn new(scanner: &'s WalletScanner<'a>) -> Self {
+        let mut state = Self {
+            scanner,
+            trees: HashMap::new(),
+            pool_nonce: 0,
+            shard_nonces: HashMap::new(),
+            queue: VecDeque::new(),
+            deposit_lookahead: HashMap::new(),
+            lookahead_end: 0,
+            next_deposit_index: 0,
+            change_counts: HashMap::new(),
+            notes: Vec::new(),
+            unspent: HashMap::new(),
+        };
+        state.extend_lookahead();
+        state
+    }
+
+    /// Keep `DEPOSIT_GAP_LIMIT` unseen deposit indices past the last one found
+    fn extend_lookahead(&mut self) {
+        while self.lookahead_end < self.next_deposit_index + DEPOSIT_GAP_LIMIT {
+            let secrets = self.scanner.keys.deposit_secrets(&self.scanner.scope, self.lookahead_end);
+            self.deposit_lookahead.insert(secrets.precommitment(), self.lookahead_end);
+            self.lookahead_end += 1;
+        }
+    }
+
+    /// The wallet's note for a deposit, if the precommitment is one of its deposit secrets
+    fn match_deposit(&mut self, value: u64, label: [u8; 32], precommitment: &[u8; 32]) -> Option<Note> {
+        // Skipped indices stay in the lookahead, so deposits landing out of order are still found
+        let index = self.deposit_lookahead.remove(precommitment)?;
+        self.next_deposit_index = self.next_deposit_index.max(index + 1);
+        self.extend_lookahead();
+        Some(self.scanner.keys.deposit_secrets(&self.scanner.scope, index).into_note(value, label))
+    }
+
+    fn insert<E>(
+        &mut self,
+        tree: StateTree,
+        commitment: [u8; 32],
+        owned: Option<Note>,
+        signature: &str,
+    ) -> Result<(), ScanError<E>> {
+        let leaf_index = self
+            .trees
+            .entry(tree)
+            .or_insert_with(|| LeanIMT::new(MAX_TREE_DEPTH as u8))
+            .insert(commitment)
+            .map_err(|reason| ScanError::Tree {
+                signature: signature.to_string(),
+                reason,
+            })?;
+
+        if let Some(note) = owned {
+            self.unspent.insert(note.nullifier_hash(), self.notes.len());
+            self.notes.push(ScannedNote {
+                note,
+                tree,
+                leaf_index,
+                signature: signature.to_string(),
+                spent_by: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Mark the note with this nullifier hash spent, returning it if the wallet owns it
+    fn spend(&mut self, nullifier_hash: &[u8; 32], signature: &str) -> Option<Note> {
+        let position = self.unspent.remove(nullifier_hash)?;
+        let scanned = &mut self.notes[position];
+        scanned.spent_by = Some(signature.to_string());
+        Some(scanned.note)
+    }
+
+    fn withdraw<E>(
+        &mut self,
+        tree: StateTree,
+        proof_data: &WithdrawProofData,
+        signature: &str,
+    ) -> Result<(), ScanError<E>> {
+        if proof_data.public_signals.len() < 8 {
+            return Err(ScanError::InvalidTransaction {
+                signature: signature.to_string(),
+            });
+        }
+
+        let new_commitment = proof_data.new_commitment_hash();
+        let change = self
+            .spend(&proof_data.existing_nullifier_hash(), signature)
+            .and_then(|spent| {
+                let value = spent.value.checked_sub(proof_data.withdrawn_value())?;
+                let index = self.change_counts.get(&spent.label).copied().unwrap_or(0);
+                let secrets = self.scanner.keys.withdrawal_secrets(&spent.label, index);
+                let note = secrets.into_note(value, spent.label);
+                (note.commitment() == new_commitment).then_some(note)
+            });
+        if let Some(note) = &change {
+            *self.change_counts.entry(note.label).or_insert(0) += 1;
+        }
+
+        self.insert(tree, new_commitment, change, signature)
+    }
+
+    fn apply<E>(&mut self, transaction: &PoolTransaction) -> Result<(), ScanError<E>> {
+        let signature = transaction.signature.as_str();
+        let instruction = PrivacyPoolInstruction::try_from_slice(&transaction.instruction_data).map_err(|_| {
+            ScanError::InvalidTransaction {
+                signature: signature.to_string(),
+            }
+        })?;
+
+        match instruction {
+            PrivacyPoolInstruction::Deposit {
+                value,
+                precommitment_hash,
+                ..
+            } => {
+                self.pool_nonce += 1;
+                let label = poseidon::compute_label(&self.scanner.scope, self.pool_nonce);
+                let commitment = poseidon::compute_commitment(value, &label, &precommitment_hash);
+                let owned = self.match_deposit(value, label, &precommitment_hash);
+
+                if transaction.account_count > 3 {
+                    self.queue.push_back(QueuedDeposit {
+                        commitment,
+                        owned,
+                        signature: signature.to_string(),
+                    });
+                } else {
+                    self.insert(StateTree::Pool, commitment, owned, signature)?;
+                }
+            }
+            PrivacyPoolInstruction::ProcessQueue { max_batch } => {
+                let batch = self.queue.len().min(max_batch as usize);
+                for deposit in self.queue.drain(..batch).collect::<Vec<_>>() {
+                    self.insert(StateTree::Pool, deposit.commitment, deposit.owned, &deposit.signature)?;
+                }
+            }
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index,
+                value,
+                precommitment_hash,
+                ..
+            } => {
+                let nonce = self.shard_nonces.entry(shard_index).or_insert(0);
+                *nonce += 1;
+                let label = poseidon::compute_shard_label(&self.scanner.scope, shard_index, *nonce);
+                let commitment = poseidon::compute_commitment(value, &label, &precommitment_hash);
+                let owned = self.match_deposit(value, label, &precommitment_hash);
+                self.insert(StateTree::Shard(shard_index), commitment, owned, signature)?;
+            }
+            PrivacyPoolInstruction::Withdraw { proof_data, .. } => {
+                self.withdraw(StateTree::Pool, &proof_data, signature)?;
+            }
+            PrivacyPoolInstruction::ShardWithdraw {
+                shard_index,
+                proof_data,
+                ..
+            } => {
+                self.withdraw(StateTree::Shard(shard_index), &proof_data, signature)?;
+            }
+            PrivacyPoolInstruction::Ragequit { proof_data } => {
+                if proof_data.public_signals.len() < 4 {
+                    return Err(ScanError::InvalidTransaction {
+                        signature: signature.to_string(),
+                    });
+                }
+                self.spend(&proof_data.nullifier_hash(), signature);
+            }
+            PrivacyPoolInstruction::InitializePool { .. }
+            | PrivacyPoolInstruction::WindDown
+            | PrivacyPoolInstruction::InitializeShard { .. }
+            | PrivacyPoolInstruction::InitializeQueue => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::snarkjs;
+    use crate::crypto::field;
+    use crate::instructions::{RagequitProofData, WithdrawalData};
+    use crate::BorshSerialize;
+
+    const REALISTIC_PROOF: &str = include_str!("../../scripts/realistic-withdraw-proof.json");
+
+    const POOL: Pubkey = [1u8; 32];
+    const SCOPE: [u8; 32] = [2u8; 32];
+
+    struct History {
+        source: InMemoryTransactionSource,
+        count: u64,
+    }
+
+    impl History {
+        fn new() -> Self {
+            Self {
+                source: InMemoryTransactionSource::new(),
+                count: 0,
+            }
+        }
+
+        fn push(&mut self, instruction: PrivacyPoolInstruction, account_count: usize) -> String {
+            self.count += 1;
+            let signature = format!("tx{}", self.count);
+            self.source.push(
+                POOL,
+                PoolTransaction {
+                    signature: signature.clone(),
+                    slot: self.count,
+                    instruction_data: instruction.try_to_vec().unwrap(),
+                    account_count,
+                },
+            );
+            signature
+        }
+
+        fn deposit(&mut self, value: u64, precommitment_hash: [u8; 32], queued: bool) -> String {
+            let instruction = PrivacyPoolInstruction::Deposit {
+                depositor: [9u8; 32],
+                value,
+                precommitment_hash,
+            };
+            self.push(instruction, if queued { 4 } else { 3 })
+        }
+
+        /// Withdrawal replayed from snarkjs output, so the signals land where the circuit
+        /// puts them: `[newCommitment, nullifier, withdrawnValue, ...]`
+        fn withdraw(&mut self, withdrawn: u64, nullifier_hash: [u8; 32], new_commitment: [u8; 32]) -> String {
+            let public_json = serde_json::json!([
+                field::bytes_to_decimal(&new_commitment),
+                field::bytes_to_decimal(&nullifier_hash),
+                withdrawn.to_string(),
+                "0",
+                "0",
+                "0",
+                "0",
+                "0",
+            ])
+            .to_string();
+            let fixture: serde_json::Value = serde_json::from_str(REALISTIC_PROOF).unwrap();
+            let proof_data = snarkjs::withdraw_proof_from_snarkjs(&fixture["rawProof"].to_string(), &public_json).unwrap();
+
+            let instruction = PrivacyPoolInstruction::Withdraw {
+                withdrawal_data: WithdrawalData {
+                    processooor: [9u8; 32],
+                    data: vec![],
+                },
+                proof_data,
+            };
+            self.push(instruction, 3)
+        }
+    }
+
+    #[test]
+    fn test_scan_follows_deposits_and_change_notes() {
+        let keys = MasterKeys::from_seed(b"scanner test wallet seed").unwrap();
+        let stranger = MasterKeys::from_seed(b"somebody else's wallet seed").unwrap();
+        let mut history = History::new();
+
+        history.deposit(10, stranger.deposit_secrets(&SCOPE, 0).precommitment(), false);
+        let first = history.deposit(100, keys.deposit_secrets(&SCOPE, 0).precommitment(), false);
+        history.deposit(50, keys.deposit_secrets(&SCOPE, 1).precommitment(), false);
+
+        // Partial withdrawal of the first note, change goes back to the wallet
+        let label = poseidon::compute_label(&SCOPE, 2);
+        let spent = keys.deposit_secrets(&SCOPE, 0).into_note(100, label);
+        let change = keys.withdrawal_secrets(&label, 0).into_note(60, label);
+        let withdrawal = history.withdraw(40, spent.nullifier_hash(), change.commitment());
+
+        // A stranger's withdrawal doesn't affect the wallet
+        history.withdraw(5, [7u8; 32], [8u8; 32]);
+
+        let result = WalletScanner::new(&keys, POOL, SCOPE).scan(&history.source).unwrap();
+        assert_eq!(result.notes.len(), 3);
+        assert_eq!(result.notes[0].note, spent);
+        assert_eq!(result.notes[0].signature, first);
+        assert_eq!(result.notes[0].leaf_index, 1);
+        assert_eq!(result.notes[0].spent_by, Some(withdrawal.clone()));
+
+        let unspent: Vec<&ScannedNote> = result.unspent().collect();
+        assert_eq!(unspent.len(), 2);
+        assert_eq!(unspent[0].note.value, 50);
+        assert_eq!(unspent[1].note, change);
+        assert_eq!(unspent[1].leaf_index, 3);
+        assert_eq!(unspent[1].signature, withdrawal);
+        assert_eq!(result.balance(), 110);
+        assert_eq!(result.next_deposit_index, 2);
+
+        // Rebuilt tree can prove the change note
+        let tree = &result.trees[&StateTree::Pool];
+        assert_eq!(tree.size(), 5);
+        assert_eq!(tree.index_of(&change.commitment()), Some(3));
+    }
+
+    #[test]
+    fn test_scan_handles_queue_shards_and_ragequit() {
+        let keys = MasterKeys::from_seed(b"scanner test wallet seed").unwrap();
+        let mut history = History::new();
+
+        // Deposit index 1 lands before index 0, both within the gap limit
+        history.deposit(20, keys.deposit_secrets(&SCOPE, 1).precommitment(), true);
+        history.deposit(30, keys.deposit_secrets(&SCOPE, 0).precommitment(), true);
+        history.push(PrivacyPoolInstruction::ProcessQueue { max_batch: 1 }, 2);
+        history.push(PrivacyPoolInstruction::ProcessQueue { max_batch: 10 }, 2);
+        history.push(
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index: 4,
+                depositor: [9u8; 32],
+                value: 70,
+                precommitment_hash: keys.deposit_secrets(&SCOPE, 2).precommitment(),
+            },
+            4,
+        );
+
+        let label = poseidon::compute_label(&SCOPE, 1);
+        let ragequit = history.push(
+            PrivacyPoolInstruction::Ragequit {
+                proof_data: RagequitProofData {
+                    proof_a: [0u8; 64],
+                    proof_b: [0u8; 128],
+                    proof_c: [0u8; 64],
+                    public_signals: vec![
+                        [0u8; 32],
+                        label,
+                        [0u8; 32],
+                        keys.deposit_secrets(&SCOPE, 1).into_note(20, label).nullifier_hash(),
+                    ],
+                },
+            },
+            4,
+        );
+
+        let result = WalletScanner::new(&keys, POOL, SCOPE).scan(&history.source).unwrap();
+        let summary: Vec<(u64, StateTree, u64, bool)> = result
+            .notes
+            .iter()
+            .map(|n| (n.note.value, n.tree, n.leaf_index, n.is_spent()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (20, StateTree::Pool, 0, true),
+                (30, StateTree::Pool, 1, false),
+                (70, StateTree::Shard(4), 0, false),
+            ]
+        );
+        assert_eq!(result.notes[0].spent_by, Some(ragequit));
+        assert_eq!(
+            result.notes[2].note.label,
+            poseidon::compute_shard_label(&SCOPE, 4, 1)
+        );
+        assert_eq!(result.next_deposit_index, 3);
+
+        // Garbage in the history is reported, not skipped
+        let mut broken = history;
+        broken.source.push(
+            POOL,
+            PoolTransaction {
+                signature: "bad".into(),
+                slot: 99,
+                instruction_data: vec![42],
+                account_count: 0,
+            },
+        );
+        assert_eq!(
+            WalletScanner::new(&keys, POOL, SCOPE).scan(&broken.source).unwrap_err(),
+            ScanError::InvalidTransaction { signature: "bad".into() }
+        );
+    }
+}
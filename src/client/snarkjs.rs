@@ -0,0 +1,278 @@
+//! Import of snarkjs `proof.json` / `public.json` output
+//!
+//! snarkjs writes every coordinate and signal as a decimal string; this converts them
+//! into the encoding the program expects (see `client::proof`), rejecting anything
+//! outside the field or off the curve instead of silently reducing it. Public signals keep
+//! the snarkjs order, which is the order `WithdrawProofData` reads them in.
+
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use serde::Deserialize;
+
+use crate::client::proof::{encode_ragequit_proof, encode_withdraw_proof};
+use crate::crypto::field;
+use crate::instructions::{RagequitProofData, WithdrawProofData};
+
+/// Contents of a snarkjs `proof.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnarkjsProof {
+    pub pi_a: Vec<String>,
+    pub pi_b: Vec<Vec<String>>,
+    pub pi_c: Vec<String>,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub curve: Option<String>,
+}
+
+impl SnarkjsProof {
+    pub fn from_json(json: &str) -> Result<Self, &'static str> {
+        serde_json::from_str(json).map_err(|_| "Invalid proof JSON")
+    }
+
+    /// Decode into curve points, checking field ranges, curve and subgroup membership
+    pub fn to_points(&self) -> Result<(G1Affine, G2Affine, G1Affine), &'static str> {
+        if self.protocol.as_deref().is_some_and(|p| p != "groth16") {
+            return Err("Only groth16 proofs are supported");
+        }
+        if self.curve.as_deref().is_some_and(|c| c != "bn128") {
+            return Err("Only bn128 proofs are supported");
+        }
+
+        let a = parse_g1(&self.pi_a)?;
+        let b = parse_g2(&self.pi_b)?;
+        let c = parse_g1(&self.pi_c)?;
+        Ok((a, b, c))
+    }
+}
+
+/// Parse a snarkjs `public.json` (an array of decimal strings)
+pub fn parse_public_signals(json: &str) -> Result<Vec<Fr>, &'static str> {
+    let signals: Vec<String> = serde_json::from_str(json).map_err(|_| "Invalid public signals JSON")?;
+    signals.iter().map(|signal| parse_fr(signal)).collect()
+}
+
+pub fn withdraw_proof_from_snarkjs(proof_json: &str, public_json: &str) -> Result<WithdrawProofData, &'static str> {
+    let (a, b, c) = SnarkjsProof::from_json(proof_json)?.to_points()?;
+    let public_signals = parse_public_signals(public_json)?;
+    Ok(encode_withdraw_proof(&a, &b, &c, &public_signals))
+}
+
+pub fn ragequit_proof_from_snarkjs(proof_json: &str, public_json: &str) -> Result<RagequitProofData, &'static str> {
+    let (a, b, c) = SnarkjsProof::from_json(proof_json)?.to_points()?;
+    let public_signals = parse_public_signals(public_json)?;
+    Ok(encode_ragequit_proof(&a, &b, &c, &public_signals))
+}
+
+fn parse_fr(value: &str) -> Result<Fr, &'static str> {
+    let bigint = field::parse_decimal(value)?;
+    Fr::from_bigint(bigint).ok_or("Public signal is not in the scalar field")
+}
+
+fn parse_fq(value: &str) -> Result<Fq, &'static str> {
+    let bigint = field::parse_decimal(value)?;
+    Fq::from_bigint(bigint).ok_or("Coordinate is not in the base field")
+}
+
+/// Projective `[x, y, z]` with `z = 1`
+fn parse_g1(coordinates: &[String]) -> Result<G1Affine, &'static str> {
+    if coordinates.len() != 3 || coordinates[2] != "1" {
+        return Err("G1 point must be [x, y, \"1\"]");
+    }
+
+    let point = G1Affine::new_unchecked(parse_fq(&coordinates[0])?, parse_fq(&coordinates[1])?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G1 point is not on the curve");
+    }
+    Ok(point)
+}
+
+/// Projective `[[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]]`
+fn parse_g2(coordinates: &[Vec<String>]) -> Result<G2Affine, &'static str> {
+    if coordinates.len() != 3
+        || coordinates.iter().any(|c| c.len() != 2)
+        || coordinates[2][0] != "1"
+        || coordinates[2][1] != "0"
+    {
+        return Err("G2 point must be [[x0, x1], [y0, y1], [\"1\", \"0\"]]");
+    }
+
+    let x = Fq2::new(parse_fq(&coordinates[0][0])?, parse_fq(&coordinates[0][1])?);
+    let y = Fq2::new(parse_fq(&coordinates[1][0])?, parse_fq(&coordinates[1][1])?);
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err("G2 point is not on the curve");
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::proof::fq_to_be_bytes;
+    use serde_json::Value;
+
+    const REALISTIC_PROOF: &str = include_str!("../../scripts/realistic-withdraw-proof.json");
+
+    fn fixture() -> (Value, String, String) {
+        let fixture: Value = serde_json::from_str(REALISTIC_PROOF).unwrap();
+        let proof_json = fixture["rawProof"].to_string();
+        let public_json = fixture["rawPublicSignals"].to_string();
+        (fixture, proof_json, public_json)
+    }
+
+    /// The fixture's `proof` bytes are the raw little-endian coordinates in snarkjs order
+    fn fixture_chunk(fixture: &Value, key: &str, index: usize) -> [u8; 32] {
+        let bytes: Vec<u8> = fixture["proof"][key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b.as_u64().unwrap() as u8)
+            .collect();
+        let mut chunk = [0u8; 32];
+        chunk.copy_from_slice(&bytes[index * 32..index * 32 + 32]);
+        chunk.reverse();
+        chunk
+    }
+
+    #[test]
+    fn test_import_realistic_withdraw_proof() {
+        let (fixture, proof_json, public_json) = fixture();
+        let proof = withdraw_proof_from_snarkjs(&proof_json, &public_json).unwrap();
+
+        // proof_a: x unchanged, y negated
+        assert_eq!(proof.proof_a[..32], fixture_chunk(&fixture, "proofA", 0));
+        let y = Fq::from_be_bytes_mod_order(&fixture_chunk(&fixture, "proofA", 1));
+        assert_eq!(proof.proof_a[32..], fq_to_be_bytes(&-y));
+
+        // proof_b: each coordinate pair swapped to (c1, c0)
+        assert_eq!(proof.proof_b[..32], fixture_chunk(&fixture, "proofB", 1));
+        assert_eq!(proof.proof_b[32..64], fixture_chunk(&fixture, "proofB", 0));
+        assert_eq!(proof.proof_b[64..96], fixture_chunk(&fixture, "proofB", 3));
+        assert_eq!(proof.proof_b[96..], fixture_chunk(&fixture, "proofB", 2));
+
+        assert_eq!(proof.proof_c[..32], fixture_chunk(&fixture, "proofC", 0));
+        assert_eq!(proof.proof_c[32..], fixture_chunk(&fixture, "proofC", 1));
+
+        let expected_signals: Vec<[u8; 32]> = serde_json::from_value(fixture["publicSignals"].clone()).unwrap();
+        assert_eq!(proof.public_signals, expected_signals);
+    }
+
+    #[test]
+    fn test_import_rejects_out_of_range_values() {
+        let (fixture, proof_json, _) = fixture();
+
+        // Scalar field modulus itself is out of range
+        let public_json = r#"["21888242871839275222246405745257275088548364400416034343698204186575808495617"]"#;
+        assert!(withdraw_proof_from_snarkjs(&proof_json, public_json).is_err());
+
+        // A point that isn't on the curve
+        let mut raw = fixture["rawProof"].clone();
+        raw["pi_a"][1] = Value::from("1");
+        assert!(withdraw_proof_from_snarkjs(&raw.to_string(), "[]").is_err());
+
+        let mut raw = fixture["rawProof"].clone();
+        raw["pi_c"][2] = Value::from("0");
+        assert!(withdraw_proof_from_snarkjs(&raw.to_string(), "[]").is_err());
+    }
+}
+
+/// Round trip through snarkjs JSON with a throwaway circuit that has as many public
+/// signals as the withdraw circuit, so the imported proof can be checked for real
+#[cfg(all(test, feature = "prover"))]
+mod verify_tests {
+    use super::*;
+    use crate::client::vkey::VerifyingKeyBytes;
+    use crate::crypto::verifying_key::verify_proof;
+    use ark_bn254::Bn254;
+    use ark_groth16::{Groth16, Proof, VerifyingKey};
+    use ark_relations::lc;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable};
+    use serde_json::{json, Value};
+
+    /// Exposes each value as a public signal and checks it against a private copy
+    struct EchoCircuit {
+        signals: Vec<Fr>,
+    }
+
+    impl ConstraintSynthesizer<Fr> for EchoCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            for signal in self.signals {
+                let public = cs.new_input_variable(|| Ok(signal))?;
+                let private = cs.new_witness_variable(|| Ok(signal))?;
+                cs.enforce_constraint(lc!() + public, lc!() + Variable::One, lc!() + private)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn decimal(value: &Fq) -> String {
+        value.into_bigint().to_string()
+    }
+
+    fn g1_json(point: &G1Affine) -> Value {
+        json!([decimal(&point.x), decimal(&point.y), "1"])
+    }
+
+    fn g2_json(point: &G2Affine) -> Value {
+        json!([
+            [decimal(&point.x.c0), decimal(&point.x.c1)],
+            [decimal(&point.y.c0), decimal(&point.y.c1)],
+            ["1", "0"]
+        ])
+    }
+
+    fn proof_json(proof: &Proof<Bn254>) -> String {
+        json!({
+            "pi_a": g1_json(&proof.a),
+            "pi_b": g2_json(&proof.b),
+            "pi_c": g1_json(&proof.c),
+            "protocol": "groth16",
+            "curve": "bn128",
+        })
+        .to_string()
+    }
+
+    fn vkey_json(vk: &VerifyingKey<Bn254>) -> String {
+        json!({
+            "protocol": "groth16",
+            "curve": "bn128",
+            "nPublic": vk.gamma_abc_g1.len() - 1,
+            "vk_alpha_1": g1_json(&vk.alpha_g1),
+            "vk_beta_2": g2_json(&vk.beta_g2),
+            "vk_gamma_2": g2_json(&vk.gamma_g2),
+            "vk_delta_2": g2_json(&vk.delta_g2),
+            "IC": vk.gamma_abc_g1.iter().map(g1_json).collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_imported_proof_verifies() {
+        // Large values so that reading a signal with the wrong byte order can't go unnoticed
+        let signals: Vec<Fr> = (1..=8u64).map(|i| -Fr::from(i)).collect();
+        let mut rng = ark_std::test_rng();
+        let proving_key = Groth16::<Bn254>::generate_random_parameters_with_reduction(
+            EchoCircuit { signals: signals.clone() },
+            &mut rng,
+        )
+        .unwrap();
+        let proof = Groth16::<Bn254>::create_random_proof_with_reduction(
+            EchoCircuit { signals: signals.clone() },
+            &proving_key,
+            &mut rng,
+        )
+        .unwrap();
+
+        let public_json = json!(signals.iter().map(|s| s.into_bigint().to_string()).collect::<Vec<_>>()).to_string();
+        let imported = withdraw_proof_from_snarkjs(&proof_json(&proof), &public_json).unwrap();
+        let key = VerifyingKeyBytes::from_snarkjs_json(&vkey_json(&proving_key.vk)).unwrap();
+        let vk = key.as_verifying_key();
+
+        assert!(verify_proof(&imported.proof_a, &imported.proof_b, &imported.proof_c, &imported.public_signals, &vk));
+
+        let mut tampered = imported.public_signals.clone();
+        tampered.swap(0, 1);
+        assert!(!verify_proof(&imported.proof_a, &imported.proof_b, &imported.proof_c, &tampered, &vk));
+    }
+}
@@ -0,0 +1,83 @@
+//! Unsigned transactions for offline signing
+//!
+//! Produces the legacy transaction wire format with zeroed signature slots, which any
+//! wallet or `solana` CLI signer can fill in. Nothing here talks to a cluster; the recent
+//! blockhash has to be supplied by the caller.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_program::hash::Hash;
+use solana_program::instruction::Instruction;
+use solana_program::message::Message;
+use solana_program::pubkey::Pubkey;
+
+/// `shortvec(signature count) | zeroed signatures | message`
+pub fn unsigned_transaction(instructions: &[Instruction], payer: &Pubkey, recent_blockhash: &Hash) -> Vec<u8> {
+    let message = Message::new_with_blockhash(instructions, Some(payer), recent_blockhash);
+    let signatures = message.header.num_required_signatures as usize;
+
+    let mut bytes = Vec::new();
+    encode_short_u16(&mut bytes, signatures as u16);
+    bytes.resize(bytes.len() + signatures * 64, 0);
+    bytes.extend_from_slice(&message.serialize());
+    bytes
+}
+
+pub fn unsigned_transaction_base64(instructions: &[Instruction], payer: &Pubkey, recent_blockhash: &Hash) -> String {
+    STANDARD.encode(unsigned_transaction(instructions, payer, recent_blockhash))
+}
+
+/// Compact-u16 length prefix used throughout the transaction format
+fn encode_short_u16(bytes: &mut Vec<u8>, mut value: u16) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        byte |= 0x80;
+        bytes.push(byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::instructions;
+    use solana_program::short_vec::decode_shortu16_len;
+
+    #[test]
+    fn test_unsigned_transaction_layout() {
+        let program_id = Pubkey::new_from_array([0xffu8; 32]);
+        let pool = Pubkey::new_from_array([1u8; 32]);
+        let depositor = Pubkey::new_from_array([2u8; 32]);
+        let payer = Pubkey::new_from_array([3u8; 32]);
+        let blockhash = Hash::new_from_array([4u8; 32]);
+        let deposit = instructions::deposit(&program_id, &pool, &pool, &depositor, 5, [6u8; 32], None).unwrap();
+
+        let bytes = unsigned_transaction(&[deposit.clone()], &payer, &blockhash);
+
+        // Payer and depositor both sign
+        let (signatures, prefix) = decode_shortu16_len(&bytes).unwrap();
+        assert_eq!(signatures, 2);
+        assert!(bytes[prefix..prefix + 128].iter().all(|b| *b == 0));
+
+        let message = Message::new_with_blockhash(&[deposit], Some(&payer), &blockhash);
+        assert_eq!(&bytes[prefix + 128..], message.serialize().as_slice());
+        assert_eq!(message.account_keys[0], payer);
+
+        assert_eq!(
+            STANDARD.decode(unsigned_transaction_base64(&[], &payer, &blockhash)).unwrap(),
+            unsigned_transaction(&[], &payer, &blockhash)
+        );
+    }
+
+    #[test]
+    fn test_short_u16_encoding() {
+        for value in [0u16, 1, 127, 128, 300, u16::MAX] {
+            let mut bytes = Vec::new();
+            encode_short_u16(&mut bytes, value);
+            assert_eq!(decode_shortu16_len(&bytes).unwrap(), (value as usize, bytes.len()));
+        }
+    }
+}
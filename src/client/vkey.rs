@@ -0,0 +1,217 @@
+//! Conversion of snarkjs verification keys (`*_vkey.json`) into groth16-solana constants
+//!
+//! Replaces `scripts/parse_vk_to_rust.js`. `src/crypto/circuit_keys.rs` is generated from
+//! `trusted-setup/final-keys` with `cargo run --bin generate-verifying-keys --features client`.
+
+use std::fmt::Write;
+
+use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::PrimeField;
+use groth16_solana::groth16::Groth16Verifyingkey;
+use serde::Deserialize;
+
+use crate::client::proof::{encode_g1, encode_g2};
+use crate::crypto::field;
+
+#[derive(Debug, Clone, Deserialize)]
+struct SnarkjsVerifyingKey {
+    protocol: String,
+    curve: String,
+    #[serde(rename = "nPublic")]
+    n_public: usize,
+    vk_alpha_1: Vec<String>,
+    vk_beta_2: Vec<Vec<String>>,
+    vk_gamma_2: Vec<Vec<String>>,
+    vk_delta_2: Vec<Vec<String>>,
+    #[serde(rename = "IC")]
+    ic: Vec<Vec<String>>,
+}
+
+/// A verifying key in the byte layout groth16-solana expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyingKeyBytes {
+    pub nr_pubinputs: usize,
+    pub vk_alpha_g1: [u8; 64],
+    pub vk_beta_g2: [u8; 128],
+    pub vk_gamma_g2: [u8; 128],
+    pub vk_delta_g2: [u8; 128],
+    pub vk_ic: Vec<[u8; 64]>,
+}
+
+impl VerifyingKeyBytes {
+    pub fn from_snarkjs_json(json: &str) -> Result<Self, &'static str> {
+        let vkey: SnarkjsVerifyingKey = serde_json::from_str(json).map_err(|_| "Invalid verifying key JSON")?;
+        if vkey.protocol != "groth16" || vkey.curve != "bn128" {
+            return Err("Only groth16 keys over bn128 are supported");
+        }
+        if vkey.ic.len() != vkey.n_public + 1 {
+            return Err("IC length doesn't match nPublic");
+        }
+
+        Ok(Self {
+            nr_pubinputs: vkey.n_public,
+            vk_alpha_g1: encode_g1(&parse_g1(&vkey.vk_alpha_1)?),
+            vk_beta_g2: encode_g2(&parse_g2(&vkey.vk_beta_2)?),
+            vk_gamma_g2: encode_g2(&parse_g2(&vkey.vk_gamma_2)?),
+            vk_delta_g2: encode_g2(&parse_g2(&vkey.vk_delta_2)?),
+            vk_ic: vkey
+                .ic
+                .iter()
+                .map(|point| parse_g1(point).map(|p| encode_g1(&p)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Borrow as the key type groth16-solana verifies against
+    pub fn as_verifying_key(&self) -> Groth16Verifyingkey<'_> {
+        Groth16Verifyingkey {
+            nr_pubinputs: self.nr_pubinputs,
+            vk_alpha_g1: self.vk_alpha_g1,
+            vk_beta_g2: self.vk_beta_g2,
+            vk_gamme_g2: self.vk_gamma_g2,
+            vk_delta_g2: self.vk_delta_g2,
+            vk_ic: &self.vk_ic,
+        }
+    }
+
+    /// Rust source declaring `{PREFIX}_VK_*` arrays and a `{PREFIX}_VERIFYING_KEY` constant
+    pub fn to_rust_source(&self, prefix: &str) -> String {
+        let mut out = String::new();
+        writeln!(out, "pub const {}_NR_PUBINPUTS: usize = {};", prefix, self.nr_pubinputs).unwrap();
+        writeln!(out).unwrap();
+        write_array(&mut out, &format!("{}_VK_ALPHA_G1", prefix), &self.vk_alpha_g1);
+        write_array(&mut out, &format!("{}_VK_BETA_G2", prefix), &self.vk_beta_g2);
+        write_array(&mut out, &format!("{}_VK_GAMMA_G2", prefix), &self.vk_gamma_g2);
+        write_array(&mut out, &format!("{}_VK_DELTA_G2", prefix), &self.vk_delta_g2);
+
+        writeln!(out, "pub const {}_VK_IC: [[u8; 64]; {}] = [", prefix, self.vk_ic.len()).unwrap();
+        for point in &self.vk_ic {
+            writeln!(out, "    [").unwrap();
+            write_bytes(&mut out, point, 8);
+            writeln!(out, "    ],").unwrap();
+        }
+        writeln!(out, "];").unwrap();
+        writeln!(out).unwrap();
+
+        writeln!(out, "pub const {}_VERIFYING_KEY: Groth16Verifyingkey = Groth16Verifyingkey {{", prefix).unwrap();
+        writeln!(out, "    nr_pubinputs: {}_NR_PUBINPUTS,", prefix).unwrap();
+        writeln!(out, "    vk_alpha_g1: {}_VK_ALPHA_G1,", prefix).unwrap();
+        writeln!(out, "    vk_beta_g2: {}_VK_BETA_G2,", prefix).unwrap();
+        writeln!(out, "    vk_gamme_g2: {}_VK_GAMMA_G2,", prefix).unwrap();
+        writeln!(out, "    vk_delta_g2: {}_VK_DELTA_G2,", prefix).unwrap();
+        writeln!(out, "    vk_ic: &{}_VK_IC,", prefix).unwrap();
+        writeln!(out, "}};").unwrap();
+        out
+    }
+}
+
+/// Rust constant prefix for a circuit name, e.g. `merkleTree` -> `MERKLE_TREE`
+pub fn constant_prefix(circuit: &str) -> String {
+    let mut prefix = String::new();
+    for (i, c) in circuit.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            prefix.push('_');
+        }
+        prefix.push(c.to_ascii_uppercase());
+    }
+    prefix
+}
+
+/// Full `circuit_keys.rs` source for `(circuit name, vkey JSON)` pairs
+pub fn generate_circuit_keys_source(keys: &[(String, VerifyingKeyBytes)]) -> String {
+    let mut out = String::new();
+    writeln!(out, "//! Verifying keys for every circuit in `trusted-setup/final-keys`").unwrap();
+    writeln!(out, "//!").unwrap();
+    writeln!(out, "//! @generated by `cargo run --bin generate-verifying-keys --features client`, do not edit").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use groth16_solana::groth16::Groth16Verifyingkey;").unwrap();
+    for (circuit, key) in keys {
+        writeln!(out).unwrap();
+        writeln!(out, "// {}_vkey.json", circuit).unwrap();
+        out.push_str(&key.to_rust_source(&constant_prefix(circuit)));
+    }
+    out
+}
+
+fn write_array(out: &mut String, name: &str, bytes: &[u8]) {
+    writeln!(out, "pub const {}: [u8; {}] = [", name, bytes.len()).unwrap();
+    write_bytes(out, bytes, 4);
+    writeln!(out, "];").unwrap();
+    writeln!(out).unwrap();
+}
+
+fn write_bytes(out: &mut String, bytes: &[u8], indent: usize) {
+    for row in bytes.chunks(32) {
+        let row: Vec<String> = row.iter().map(|b| b.to_string()).collect();
+        writeln!(out, "{}{},", " ".repeat(indent), row.join(", ")).unwrap();
+    }
+}
+
+fn parse_fq(value: &str) -> Result<Fq, &'static str> {
+    Fq::from_bigint(field::parse_decimal(value)?).ok_or("Coordinate is not in the base field")
+}
+
+fn parse_g1(coordinates: &[String]) -> Result<G1Affine, &'static str> {
+    if coordinates.len() != 3 || coordinates[2] != "1" {
+        return Err("G1 point must be [x, y, \"1\"]");
+    }
+    let point = G1Affine::new_unchecked(parse_fq(&coordinates[0])?, parse_fq(&coordinates[1])?);
+    if !point.is_on_curve() {
+        return Err("G1 point is not on the curve");
+    }
+    Ok(point)
+}
+
+fn parse_g2(coordinates: &[Vec<String>]) -> Result<G2Affine, &'static str> {
+    if coordinates.len() != 3 || coordinates.iter().any(|c| c.len() != 2) {
+        return Err("G2 point must be [[x0, x1], [y0, y1], [\"1\", \"0\"]]");
+    }
+    let x = Fq2::new(parse_fq(&coordinates[0][0])?, parse_fq(&coordinates[0][1])?);
+    let y = Fq2::new(parse_fq(&coordinates[1][0])?, parse_fq(&coordinates[1][1])?);
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err("G2 point is not on the curve");
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::circuit_keys::*;
+    use groth16_solana::groth16::Groth16Verifyingkey;
+
+    fn embedded(key: &Groth16Verifyingkey) -> VerifyingKeyBytes {
+        VerifyingKeyBytes {
+            nr_pubinputs: key.nr_pubinputs,
+            vk_alpha_g1: key.vk_alpha_g1,
+            vk_beta_g2: key.vk_beta_g2,
+            vk_gamma_g2: key.vk_gamme_g2,
+            vk_delta_g2: key.vk_delta_g2,
+            vk_ic: key.vk_ic.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_embedded_keys_match_vkey_json() {
+        let cases = [
+            (include_str!("../../trusted-setup/final-keys/commitment_vkey.json"), &COMMITMENT_VERIFYING_KEY),
+            (include_str!("../../trusted-setup/final-keys/merkleTree_vkey.json"), &MERKLE_TREE_VERIFYING_KEY),
+            (include_str!("../../trusted-setup/final-keys/withdraw_vkey.json"), &WITHDRAW_VERIFYING_KEY),
+        ];
+
+        for (json, key) in cases {
+            assert_eq!(
+                VerifyingKeyBytes::from_snarkjs_json(json).unwrap(),
+                embedded(key),
+                "circuit_keys.rs is stale, regenerate it with `cargo run --bin generate-verifying-keys --features client`"
+            );
+        }
+    }
+
+    #[test]
+    fn test_constant_prefix() {
+        assert_eq!(constant_prefix("merkleTree"), "MERKLE_TREE");
+        assert_eq!(constant_prefix("withdraw"), "WITHDRAW");
+    }
+}
@@ -0,0 +1,290 @@
+use serde_json::{json, Map, Value};
+
+use crate::client::note::Note;
+use crate::crypto::field;
+use crate::crypto::merkle_tree::{CircuitMerkleProof, LeanIMT};
+
+/// `maxTreeDepth` the withdraw circuit is compiled with (`Withdraw(20)` in `circuits/withdraw.circom`)
+pub const WITHDRAW_CIRCUIT_MAX_DEPTH: usize = 20;
+
+/// Complete set of inputs for the `withdraw` circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawWitnessInputs {
+    pub withdrawn_value: u64,
+    pub context: [u8; 32],
+    pub note: Note,
+    pub new_nullifier: [u8; 32],
+    pub new_secret: [u8; 32],
+    pub state_proof: CircuitMerkleProof,
+    pub asp_proof: CircuitMerkleProof,
+}
+
+impl WithdrawWitnessInputs {
+    pub fn builder(note: Note) -> WithdrawWitnessBuilder {
+        WithdrawWitnessBuilder::new(note)
+    }
+
+    /// Value of the change note created by the withdrawal
+    pub fn remaining_value(&self) -> u64 {
+        self.note.value - self.withdrawn_value
+    }
+
+    /// Commitment of the change note (the circuit's `newCommitmentHash` output)
+    pub fn new_commitment(&self) -> [u8; 32] {
+        Note::new(self.remaining_value(), self.note.label, self.new_nullifier, self.new_secret).commitment()
+    }
+
+    /// Inputs in the JSON shape the circuit consumes, with signals in declaration order
+    pub fn to_json(&self) -> Value {
+        let mut inputs = Map::new();
+        inputs.insert("withdrawnValue".into(), json!(self.withdrawn_value.to_string()));
+        inputs.insert("stateRoot".into(), json!(field::bytes_to_decimal(&self.state_proof.root)));
+        inputs.insert("stateTreeDepth".into(), json!(self.state_proof.depth.to_string()));
+        inputs.insert("ASPRoot".into(), json!(field::bytes_to_decimal(&self.asp_proof.root)));
+        inputs.insert("ASPTreeDepth".into(), json!(self.asp_proof.depth.to_string()));
+        inputs.insert("context".into(), json!(field::bytes_to_decimal(&self.context)));
+        inputs.insert("label".into(), json!(field::bytes_to_decimal(&self.note.label)));
+        inputs.insert("existingValue".into(), json!(self.note.value.to_string()));
+        inputs.insert("existingNullifier".into(), json!(field::bytes_to_decimal(&self.note.nullifier)));
+        inputs.insert("existingSecret".into(), json!(field::bytes_to_decimal(&self.note.secret)));
+        inputs.insert("newNullifier".into(), json!(field::bytes_to_decimal(&self.new_nullifier)));
+        inputs.insert("newSecret".into(), json!(field::bytes_to_decimal(&self.new_secret)));
+        inputs.insert("stateSiblings".into(), json!(self.state_proof.siblings_decimal()));
+        inputs.insert("stateIndex".into(), json!(self.state_proof.leaf_index.to_string()));
+        inputs.insert("ASPSiblings".into(), json!(self.asp_proof.siblings_decimal()));
+        inputs.insert("ASPIndex".into(), json!(self.asp_proof.leaf_index.to_string()));
+        Value::Object(inputs)
+    }
+
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap()
+    }
+}
+
+/// Collects everything a withdrawal needs and checks it before emitting circuit inputs,
+/// so a bad witness is reported here instead of as an opaque constraint failure
+pub struct WithdrawWitnessBuilder {
+    note: Note,
+    withdrawn_value: u64,
+    context: [u8; 32],
+    new_nullifier: Option<[u8; 32]>,
+    new_secret: Option<[u8; 32]>,
+    state_proof: Option<CircuitMerkleProof>,
+    asp_proof: Option<CircuitMerkleProof>,
+    max_depth: usize,
+}
+
+impl WithdrawWitnessBuilder {
+    pub fn new(note: Note) -> Self {
+        Self {
+            note,
+            withdrawn_value: 0,
+            context: [0u8; 32],
+            new_nullifier: None,
+            new_secret: None,
+            state_proof: None,
+            asp_proof: None,
+            max_depth: WITHDRAW_CIRCUIT_MAX_DEPTH,
+        }
+    }
+
+    pub fn withdrawn_value(mut self, value: u64) -> Self {
+        self.withdrawn_value = value;
+        self
+    }
+
+    /// Context binding the proof to the withdrawal, see `poseidon::compute_context`
+    pub fn context(mut self, context: [u8; 32]) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Nullifier and secret of the change note
+    pub fn new_note_secrets(mut self, nullifier: [u8; 32], secret: [u8; 32]) -> Self {
+        self.new_nullifier = Some(nullifier);
+        self.new_secret = Some(secret);
+        self
+    }
+
+    /// Sibling array length; must match the circuit's `maxTreeDepth`.
+    /// Set this before passing trees.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Prove the note's commitment against the current root of `tree`
+    pub fn state_tree(self, tree: &LeanIMT) -> Result<Self, &'static str> {
+        let index = tree.index_of(&self.note.commitment()).ok_or("Commitment not found in state tree")?;
+        let proof = tree.generate_circuit_proof(index, self.max_depth)?;
+        Ok(self.state_proof(proof))
+    }
+
+    /// Prove the note's label against the current root of `tree`
+    pub fn asp_tree(self, tree: &LeanIMT) -> Result<Self, &'static str> {
+        let index = tree.index_of(&self.note.label).ok_or("Label not found in ASP tree")?;
+        let proof = tree.generate_circuit_proof(index, self.max_depth)?;
+        Ok(self.asp_proof(proof))
+    }
+
+    /// Use an already built state tree proof, e.g. one against a historical root
+    pub fn state_proof(mut self, proof: CircuitMerkleProof) -> Self {
+        self.state_proof = Some(proof);
+        self
+    }
+
+    pub fn asp_proof(mut self, proof: CircuitMerkleProof) -> Self {
+        self.asp_proof = Some(proof);
+        self
+    }
+
+    pub fn build(self) -> Result<WithdrawWitnessInputs, &'static str> {
+        let new_nullifier = self.new_nullifier.ok_or("Missing new note secrets")?;
+        let new_secret = self.new_secret.ok_or("Missing new note secrets")?;
+        let state_proof = self.state_proof.ok_or("Missing state tree proof")?;
+        let asp_proof = self.asp_proof.ok_or("Missing ASP tree proof")?;
+
+        // Mirror the circuit constraints
+        if self.withdrawn_value > self.note.value {
+            return Err("Withdrawn value exceeds note value");
+        }
+        if new_nullifier == self.note.nullifier {
+            return Err("New nullifier must differ from the existing one");
+        }
+        if state_proof.leaf != self.note.commitment() {
+            return Err("State proof is not for this note");
+        }
+        if asp_proof.leaf != self.note.label {
+            return Err("ASP proof is not for this label");
+        }
+        if state_proof.siblings.len() != self.max_depth || asp_proof.siblings.len() != self.max_depth {
+            return Err("Proof siblings don't match the circuit depth");
+        }
+
+        Ok(WithdrawWitnessInputs {
+            withdrawn_value: self.withdrawn_value,
+            context: self.context,
+            note: self.note,
+            new_nullifier,
+            new_secret,
+            state_proof,
+            asp_proof,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../inputs/withdraw/default.json");
+
+    fn fixture_field(fixture: &Value, key: &str) -> [u8; 32] {
+        field::decimal_to_bytes(fixture[key].as_str().unwrap()).unwrap()
+    }
+
+    fn fixture_u64(fixture: &Value, key: &str) -> u64 {
+        fixture[key].as_str().unwrap().parse().unwrap()
+    }
+
+    fn fixture_proof(fixture: &Value, prefix: &str, leaf: [u8; 32]) -> CircuitMerkleProof {
+        let siblings = fixture[format!("{}Siblings", prefix)]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| field::decimal_to_bytes(s.as_str().unwrap()).unwrap())
+            .collect();
+        CircuitMerkleProof {
+            root: fixture_field(fixture, &format!("{}Root", prefix)),
+            leaf,
+            leaf_index: fixture_u64(fixture, &format!("{}Index", prefix)),
+            depth: fixture_u64(fixture, &format!("{}TreeDepth", prefix)) as u8,
+            siblings,
+        }
+    }
+
+    #[test]
+    fn test_withdraw_inputs_match_fixture() {
+        let fixture: Value = serde_json::from_str(FIXTURE).unwrap();
+        let note = Note::new(
+            fixture_u64(&fixture, "existingValue"),
+            fixture_field(&fixture, "label"),
+            fixture_field(&fixture, "existingNullifier"),
+            fixture_field(&fixture, "existingSecret"),
+        );
+        let max_depth = fixture["stateSiblings"].as_array().unwrap().len();
+
+        let inputs = WithdrawWitnessInputs::builder(note)
+            .max_depth(max_depth)
+            .withdrawn_value(fixture_u64(&fixture, "withdrawnValue"))
+            .context(fixture_field(&fixture, "context"))
+            .new_note_secrets(fixture_field(&fixture, "newNullifier"), fixture_field(&fixture, "newSecret"))
+            .state_proof(fixture_proof(&fixture, "state", note.commitment()))
+            .asp_proof(fixture_proof(&fixture, "ASP", note.label))
+            .build()
+            .unwrap();
+
+        let json = inputs.to_json();
+        assert_eq!(json, fixture);
+        // Same keys in the same order as the checked-in file
+        let keys: Vec<&String> = json.as_object().unwrap().keys().collect();
+        let fixture_keys: Vec<&String> = fixture.as_object().unwrap().keys().collect();
+        assert_eq!(keys, fixture_keys);
+    }
+
+    #[test]
+    fn test_withdraw_inputs_from_trees() {
+        let note = Note::new(5_000, [4u8; 32], [5u8; 32], [6u8; 32]);
+        let mut state_tree = LeanIMT::new(32);
+        let mut asp_tree = LeanIMT::new(32);
+        for i in 1..=3u8 {
+            state_tree.insert([i; 32]).unwrap();
+            asp_tree.insert([i + 100; 32]).unwrap();
+        }
+        state_tree.insert(note.commitment()).unwrap();
+        asp_tree.insert(note.label).unwrap();
+
+        let inputs = WithdrawWitnessInputs::builder(note)
+            .withdrawn_value(1_000)
+            .context([9u8; 32])
+            .new_note_secrets([7u8; 32], [8u8; 32])
+            .state_tree(&state_tree)
+            .unwrap()
+            .asp_tree(&asp_tree)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(inputs.remaining_value(), 4_000);
+        assert_eq!(inputs.state_proof.compute_root(), state_tree.root());
+        assert_eq!(inputs.asp_proof.compute_root(), asp_tree.root());
+
+        let json = inputs.to_json();
+        assert_eq!(json["stateIndex"], "3");
+        assert_eq!(json["stateTreeDepth"], "2");
+        assert_eq!(json["stateSiblings"].as_array().unwrap().len(), WITHDRAW_CIRCUIT_MAX_DEPTH);
+        assert_eq!(json["ASPIndex"], "3");
+    }
+
+    #[test]
+    fn test_withdraw_inputs_reject_invalid_witness() {
+        let note = Note::new(5_000, [4u8; 32], [5u8; 32], [6u8; 32]);
+        let mut tree = LeanIMT::new(32);
+        tree.insert(note.commitment()).unwrap();
+        let mut asp_tree = LeanIMT::new(32);
+        asp_tree.insert(note.label).unwrap();
+
+        let builder = || {
+            WithdrawWitnessInputs::builder(note)
+                .state_tree(&tree)
+                .unwrap()
+                .asp_tree(&asp_tree)
+                .unwrap()
+        };
+
+        assert!(builder().new_note_secrets([7u8; 32], [8u8; 32]).withdrawn_value(6_000).build().is_err());
+        assert!(builder().new_note_secrets(note.nullifier, [8u8; 32]).build().is_err());
+        assert!(builder().build().is_err());
+        assert!(WithdrawWitnessInputs::builder(note).asp_tree(&tree).is_err());
+    }
+}
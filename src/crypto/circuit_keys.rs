@@ -0,0 +1,184 @@
+//! Verifying keys for every circuit in `trusted-setup/final-keys`
+//!
+//! @generated by `cargo run --bin generate-verifying-keys --features client`, do not edit
+
+use groth16_solana::groth16::Groth16Verifyingkey;
+
+// commitment_vkey.json
+pub const COMMITMENT_NR_PUBINPUTS: usize = 2;
+
+pub const COMMITMENT_VK_ALPHA_G1: [u8; 64] = [
+    45, 77, 154, 167, 227, 2, 217, 223, 65, 116, 157, 85, 7, 148, 157, 5, 219, 234, 51, 251, 177, 108, 100, 59, 34, 245, 153, 162, 190, 109, 242, 226,
+    20, 190, 221, 80, 60, 55, 206, 176, 97, 216, 236, 96, 32, 159, 227, 69, 206, 137, 131, 10, 25, 35, 3, 1, 240, 118, 202, 255, 0, 77, 25, 38,
+];
+
+pub const COMMITMENT_VK_BETA_G2: [u8; 128] = [
+    9, 103, 3, 47, 203, 247, 118, 209, 175, 201, 133, 248, 136, 119, 241, 130, 211, 132, 128, 166, 83, 242, 222, 202, 169, 121, 76, 188, 59, 243, 6, 12,
+    14, 24, 120, 71, 173, 76, 121, 131, 116, 208, 214, 115, 43, 245, 1, 132, 125, 214, 139, 192, 224, 113, 36, 30, 2, 19, 188, 127, 193, 61, 183, 171,
+    48, 76, 251, 209, 224, 138, 112, 74, 153, 245, 232, 71, 217, 63, 140, 60, 170, 253, 222, 196, 107, 122, 13, 55, 157, 166, 154, 77, 17, 35, 70, 167,
+    23, 57, 193, 177, 164, 87, 168, 199, 49, 49, 35, 210, 77, 47, 145, 146, 248, 150, 183, 198, 62, 234, 5, 169, 213, 127, 6, 84, 122, 208, 206, 200,
+];
+
+pub const COMMITMENT_VK_GAMMA_G2: [u8; 128] = [
+    25, 142, 147, 147, 146, 13, 72, 58, 114, 96, 191, 183, 49, 251, 93, 37, 241, 170, 73, 51, 53, 169, 231, 18, 151, 228, 133, 183, 174, 243, 18, 194,
+    24, 0, 222, 239, 18, 31, 30, 118, 66, 106, 0, 102, 94, 92, 68, 121, 103, 67, 34, 212, 247, 94, 218, 221, 70, 222, 189, 92, 217, 146, 246, 237,
+    9, 6, 137, 208, 88, 95, 240, 117, 236, 158, 153, 173, 105, 12, 51, 149, 188, 75, 49, 51, 112, 179, 142, 243, 85, 172, 218, 220, 209, 34, 151, 91,
+    18, 200, 94, 165, 219, 140, 109, 235, 74, 171, 113, 128, 141, 203, 64, 143, 227, 209, 231, 105, 12, 67, 211, 123, 76, 230, 204, 1, 102, 250, 125, 170,
+];
+
+pub const COMMITMENT_VK_DELTA_G2: [u8; 128] = [
+    33, 77, 4, 251, 84, 41, 82, 12, 214, 250, 207, 202, 228, 157, 221, 205, 158, 37, 13, 206, 197, 110, 252, 213, 158, 99, 189, 231, 228, 54, 187, 26,
+    43, 163, 234, 142, 38, 21, 112, 125, 61, 35, 158, 25, 58, 98, 197, 224, 213, 82, 84, 216, 203, 200, 146, 101, 153, 43, 67, 182, 110, 180, 22, 131,
+    26, 190, 73, 241, 244, 141, 93, 64, 44, 13, 118, 246, 112, 107, 52, 88, 23, 187, 193, 73, 132, 120, 174, 119, 161, 15, 225, 223, 200, 181, 216, 44,
+    30, 170, 171, 194, 118, 38, 26, 33, 243, 26, 44, 206, 110, 151, 138, 82, 3, 129, 90, 232, 186, 132, 148, 218, 237, 105, 153, 245, 130, 145, 20, 153,
+];
+
+pub const COMMITMENT_VK_IC: [[u8; 64]; 3] = [
+    [
+        5, 4, 196, 173, 23, 59, 146, 199, 85, 165, 197, 166, 212, 201, 55, 234, 120, 172, 141, 184, 11, 87, 124, 97, 182, 152, 246, 44, 69, 208, 220, 227,
+        9, 234, 106, 126, 198, 28, 39, 178, 83, 236, 208, 54, 228, 172, 162, 144, 118, 36, 253, 200, 210, 43, 121, 205, 74, 232, 149, 92, 236, 30, 87, 147,
+    ],
+    [
+        21, 55, 107, 134, 173, 89, 251, 18, 224, 11, 144, 97, 88, 87, 22, 4, 58, 201, 93, 8, 232, 246, 130, 158, 100, 190, 57, 255, 92, 47, 251, 250,
+        4, 174, 43, 32, 134, 99, 254, 24, 99, 206, 17, 165, 180, 245, 39, 14, 107, 11, 9, 239, 219, 167, 144, 74, 48, 43, 104, 177, 14, 161, 6, 157,
+    ],
+    [
+        14, 31, 114, 161, 184, 17, 119, 210, 2, 216, 57, 210, 206, 181, 221, 253, 41, 177, 174, 81, 171, 50, 34, 38, 133, 133, 67, 191, 23, 255, 227, 92,
+        17, 43, 253, 215, 179, 1, 226, 218, 165, 172, 35, 189, 148, 88, 213, 77, 14, 174, 178, 221, 173, 75, 221, 79, 79, 164, 204, 61, 9, 217, 99, 87,
+    ],
+];
+
+pub const COMMITMENT_VERIFYING_KEY: Groth16Verifyingkey = Groth16Verifyingkey {
+    nr_pubinputs: COMMITMENT_NR_PUBINPUTS,
+    vk_alpha_g1: COMMITMENT_VK_ALPHA_G1,
+    vk_beta_g2: COMMITMENT_VK_BETA_G2,
+    vk_gamme_g2: COMMITMENT_VK_GAMMA_G2,
+    vk_delta_g2: COMMITMENT_VK_DELTA_G2,
+    vk_ic: &COMMITMENT_VK_IC,
+};
+
+// merkleTree_vkey.json
+pub const MERKLE_TREE_NR_PUBINPUTS: usize = 1;
+
+pub const MERKLE_TREE_VK_ALPHA_G1: [u8; 64] = [
+    45, 77, 154, 167, 227, 2, 217, 223, 65, 116, 157, 85, 7, 148, 157, 5, 219, 234, 51, 251, 177, 108, 100, 59, 34, 245, 153, 162, 190, 109, 242, 226,
+    20, 190, 221, 80, 60, 55, 206, 176, 97, 216, 236, 96, 32, 159, 227, 69, 206, 137, 131, 10, 25, 35, 3, 1, 240, 118, 202, 255, 0, 77, 25, 38,
+];
+
+pub const MERKLE_TREE_VK_BETA_G2: [u8; 128] = [
+    9, 103, 3, 47, 203, 247, 118, 209, 175, 201, 133, 248, 136, 119, 241, 130, 211, 132, 128, 166, 83, 242, 222, 202, 169, 121, 76, 188, 59, 243, 6, 12,
+    14, 24, 120, 71, 173, 76, 121, 131, 116, 208, 214, 115, 43, 245, 1, 132, 125, 214, 139, 192, 224, 113, 36, 30, 2, 19, 188, 127, 193, 61, 183, 171,
+    48, 76, 251, 209, 224, 138, 112, 74, 153, 245, 232, 71, 217, 63, 140, 60, 170, 253, 222, 196, 107, 122, 13, 55, 157, 166, 154, 77, 17, 35, 70, 167,
+    23, 57, 193, 177, 164, 87, 168, 199, 49, 49, 35, 210, 77, 47, 145, 146, 248, 150, 183, 198, 62, 234, 5, 169, 213, 127, 6, 84, 122, 208, 206, 200,
+];
+
+pub const MERKLE_TREE_VK_GAMMA_G2: [u8; 128] = [
+    25, 142, 147, 147, 146, 13, 72, 58, 114, 96, 191, 183, 49, 251, 93, 37, 241, 170, 73, 51, 53, 169, 231, 18, 151, 228, 133, 183, 174, 243, 18, 194,
+    24, 0, 222, 239, 18, 31, 30, 118, 66, 106, 0, 102, 94, 92, 68, 121, 103, 67, 34, 212, 247, 94, 218, 221, 70, 222, 189, 92, 217, 146, 246, 237,
+    9, 6, 137, 208, 88, 95, 240, 117, 236, 158, 153, 173, 105, 12, 51, 149, 188, 75, 49, 51, 112, 179, 142, 243, 85, 172, 218, 220, 209, 34, 151, 91,
+    18, 200, 94, 165, 219, 140, 109, 235, 74, 171, 113, 128, 141, 203, 64, 143, 227, 209, 231, 105, 12, 67, 211, 123, 76, 230, 204, 1, 102, 250, 125, 170,
+];
+
+pub const MERKLE_TREE_VK_DELTA_G2: [u8; 128] = [
+    7, 172, 216, 221, 6, 51, 80, 38, 84, 29, 62, 77, 170, 222, 135, 112, 103, 161, 50, 13, 215, 167, 240, 3, 233, 109, 146, 69, 245, 189, 210, 130,
+    17, 100, 28, 186, 95, 190, 83, 149, 158, 178, 174, 87, 78, 11, 205, 163, 147, 9, 131, 216, 200, 110, 26, 18, 252, 1, 38, 152, 7, 51, 219, 135,
+    31, 116, 214, 187, 103, 93, 115, 92, 66, 240, 215, 126, 197, 123, 238, 244, 118, 161, 76, 31, 59, 121, 152, 121, 206, 23, 187, 169, 197, 81, 149, 143,
+    11, 42, 131, 140, 18, 27, 0, 95, 59, 153, 222, 223, 194, 69, 49, 97, 88, 88, 141, 104, 4, 45, 6, 214, 191, 37, 84, 158, 168, 76, 122, 22,
+];
+
+pub const MERKLE_TREE_VK_IC: [[u8; 64]; 2] = [
+    [
+        23, 180, 161, 101, 61, 85, 75, 71, 145, 58, 221, 27, 230, 2, 98, 141, 44, 187, 75, 44, 221, 142, 228, 20, 77, 96, 119, 31, 147, 123, 151, 232,
+        29, 180, 107, 170, 60, 17, 252, 190, 80, 101, 241, 69, 35, 23, 130, 102, 4, 226, 161, 158, 105, 166, 130, 68, 224, 102, 120, 82, 93, 42, 207, 197,
+    ],
+    [
+        31, 206, 184, 27, 197, 255, 30, 161, 210, 112, 6, 35, 184, 153, 11, 234, 227, 32, 236, 84, 143, 182, 159, 162, 29, 94, 1, 219, 201, 1, 19, 19,
+        45, 18, 0, 228, 37, 126, 47, 45, 78, 211, 14, 176, 191, 48, 148, 132, 30, 48, 91, 196, 98, 46, 52, 67, 2, 107, 134, 1, 31, 241, 249, 208,
+    ],
+];
+
+pub const MERKLE_TREE_VERIFYING_KEY: Groth16Verifyingkey = Groth16Verifyingkey {
+    nr_pubinputs: MERKLE_TREE_NR_PUBINPUTS,
+    vk_alpha_g1: MERKLE_TREE_VK_ALPHA_G1,
+    vk_beta_g2: MERKLE_TREE_VK_BETA_G2,
+    vk_gamme_g2: MERKLE_TREE_VK_GAMMA_G2,
+    vk_delta_g2: MERKLE_TREE_VK_DELTA_G2,
+    vk_ic: &MERKLE_TREE_VK_IC,
+};
+
+// withdraw_vkey.json
+pub const WITHDRAW_NR_PUBINPUTS: usize = 8;
+
+pub const WITHDRAW_VK_ALPHA_G1: [u8; 64] = [
+    45, 114, 227, 46, 53, 160, 185, 23, 186, 245, 255, 46, 253, 100, 57, 2, 115, 126, 12, 223, 123, 184, 231, 56, 100, 160, 159, 104, 79, 223, 187, 250,
+    38, 219, 127, 173, 184, 113, 209, 111, 136, 133, 16, 60, 58, 116, 145, 69, 189, 86, 95, 33, 45, 88, 204, 60, 164, 162, 16, 23, 136, 210, 11, 31,
+];
+
+pub const WITHDRAW_VK_BETA_G2: [u8; 128] = [
+    37, 134, 1, 108, 169, 20, 13, 17, 205, 27, 248, 209, 5, 245, 140, 19, 64, 224, 160, 194, 70, 233, 197, 63, 1, 11, 88, 93, 248, 123, 141, 80,
+    24, 81, 73, 28, 229, 42, 229, 77, 11, 119, 86, 196, 124, 77, 201, 65, 239, 217, 87, 163, 225, 7, 118, 160, 129, 73, 57, 222, 141, 35, 50, 189,
+    36, 7, 160, 111, 223, 21, 253, 76, 24, 58, 252, 153, 199, 246, 103, 69, 223, 102, 71, 58, 214, 45, 196, 215, 123, 222, 103, 11, 236, 150, 181, 136,
+    37, 37, 148, 69, 16, 147, 126, 68, 3, 88, 222, 182, 201, 85, 206, 79, 64, 76, 254, 110, 205, 98, 254, 62, 61, 193, 34, 4, 221, 128, 17, 210,
+];
+
+pub const WITHDRAW_VK_GAMMA_G2: [u8; 128] = [
+    8, 131, 22, 153, 107, 86, 198, 195, 175, 231, 97, 175, 125, 83, 242, 217, 162, 75, 5, 32, 9, 45, 162, 58, 76, 40, 137, 189, 135, 15, 98, 241,
+    10, 150, 40, 70, 150, 63, 151, 144, 189, 37, 95, 232, 226, 26, 179, 225, 242, 182, 43, 145, 137, 132, 195, 217, 255, 127, 239, 207, 123, 209, 141, 60,
+    24, 5, 245, 174, 75, 24, 101, 145, 114, 57, 0, 230, 163, 73, 166, 29, 64, 152, 211, 45, 124, 131, 100, 230, 47, 97, 167, 112, 12, 197, 27, 244,
+    34, 193, 63, 158, 225, 82, 47, 237, 39, 97, 112, 254, 91, 170, 101, 240, 49, 230, 225, 212, 121, 133, 253, 151, 108, 125, 84, 191, 6, 198, 227, 250,
+];
+
+pub const WITHDRAW_VK_DELTA_G2: [u8; 128] = [
+    27, 55, 105, 247, 126, 55, 199, 161, 138, 107, 191, 48, 141, 241, 162, 127, 129, 130, 135, 138, 5, 39, 91, 5, 120, 44, 87, 75, 124, 32, 214, 223,
+    46, 64, 166, 221, 188, 135, 168, 248, 156, 231, 130, 252, 252, 217, 6, 106, 93, 70, 7, 243, 54, 16, 169, 4, 251, 208, 147, 214, 125, 85, 164, 95,
+    27, 246, 27, 199, 69, 242, 185, 19, 132, 46, 124, 0, 213, 194, 252, 131, 228, 34, 23, 70, 137, 40, 93, 57, 106, 124, 71, 83, 253, 73, 3, 224,
+    38, 122, 195, 20, 200, 145, 9, 64, 69, 13, 56, 221, 252, 94, 142, 253, 229, 200, 101, 113, 153, 11, 138, 43, 61, 68, 94, 253, 65, 16, 39, 247,
+];
+
+pub const WITHDRAW_VK_IC: [[u8; 64]; 9] = [
+    [
+        11, 106, 28, 154, 251, 63, 62, 232, 173, 3, 58, 110, 32, 188, 60, 253, 241, 218, 202, 45, 33, 7, 175, 30, 51, 180, 188, 250, 121, 40, 195, 213,
+        20, 21, 28, 2, 12, 76, 167, 151, 233, 106, 24, 140, 118, 63, 43, 240, 50, 91, 81, 10, 176, 239, 41, 154, 15, 109, 158, 146, 224, 61, 114, 162,
+    ],
+    [
+        6, 93, 119, 205, 195, 151, 154, 67, 53, 45, 171, 89, 213, 170, 238, 70, 101, 123, 26, 241, 109, 81, 57, 6, 78, 142, 58, 151, 87, 159, 223, 152,
+        48, 6, 252, 175, 130, 77, 183, 187, 24, 112, 233, 12, 197, 36, 119, 100, 215, 94, 227, 111, 135, 127, 125, 54, 85, 99, 233, 223, 159, 201, 235, 95,
+    ],
+    [
+        29, 48, 44, 26, 49, 46, 160, 186, 211, 40, 225, 182, 105, 92, 137, 248, 223, 87, 171, 95, 153, 63, 252, 176, 190, 168, 139, 234, 200, 80, 87, 118,
+        14, 228, 111, 63, 38, 192, 122, 71, 74, 5, 47, 102, 146, 198, 72, 57, 255, 55, 251, 92, 24, 134, 55, 60, 60, 51, 226, 182, 200, 204, 251, 41,
+    ],
+    [
+        23, 136, 108, 18, 167, 95, 154, 119, 43, 170, 73, 80, 143, 168, 15, 33, 1, 178, 111, 83, 208, 253, 29, 123, 162, 33, 247, 7, 132, 85, 227, 2,
+        24, 201, 107, 119, 59, 162, 208, 201, 78, 119, 130, 183, 20, 123, 133, 234, 140, 17, 98, 151, 54, 143, 208, 0, 144, 75, 190, 57, 228, 231, 125, 191,
+    ],
+    [
+        39, 154, 171, 224, 208, 250, 169, 252, 61, 112, 241, 200, 99, 68, 32, 245, 125, 91, 59, 77, 224, 179, 222, 110, 13, 133, 57, 61, 81, 11, 101, 104,
+        20, 60, 114, 159, 152, 175, 120, 243, 33, 236, 136, 218, 48, 243, 9, 144, 140, 45, 214, 163, 239, 159, 78, 153, 200, 254, 180, 58, 137, 147, 57, 40,
+    ],
+    [
+        8, 18, 197, 68, 26, 128, 242, 192, 158, 169, 130, 84, 30, 159, 235, 156, 33, 88, 217, 251, 36, 1, 152, 82, 88, 33, 57, 177, 32, 145, 97, 213,
+        34, 218, 222, 40, 197, 117, 4, 187, 254, 2, 32, 171, 33, 222, 193, 49, 19, 156, 93, 55, 177, 83, 203, 168, 234, 71, 83, 147, 250, 218, 26, 233,
+    ],
+    [
+        23, 133, 91, 80, 166, 253, 153, 197, 17, 135, 186, 126, 10, 138, 242, 138, 12, 111, 246, 158, 235, 136, 120, 163, 135, 89, 114, 0, 227, 203, 129, 74,
+        6, 16, 127, 94, 61, 254, 105, 149, 224, 96, 245, 61, 241, 245, 68, 159, 61, 32, 134, 199, 186, 163, 9, 99, 249, 97, 72, 79, 247, 178, 163, 220,
+    ],
+    [
+        37, 197, 52, 215, 229, 4, 168, 192, 132, 246, 35, 92, 158, 100, 236, 181, 32, 150, 212, 36, 109, 64, 144, 186, 112, 73, 230, 41, 4, 246, 223, 224,
+        4, 25, 70, 127, 16, 21, 106, 251, 38, 54, 147, 252, 203, 198, 105, 160, 201, 70, 4, 197, 22, 50, 217, 20, 190, 63, 185, 116, 0, 61, 22, 172,
+    ],
+    [
+        8, 163, 129, 54, 16, 99, 157, 186, 188, 45, 147, 243, 64, 71, 67, 55, 108, 242, 250, 73, 230, 236, 181, 64, 205, 3, 170, 185, 145, 102, 83, 26,
+        21, 184, 135, 226, 151, 234, 33, 48, 28, 184, 79, 107, 244, 89, 187, 81, 165, 77, 253, 118, 164, 11, 76, 84, 102, 208, 30, 129, 221, 175, 141, 151,
+    ],
+];
+
+pub const WITHDRAW_VERIFYING_KEY: Groth16Verifyingkey = Groth16Verifyingkey {
+    nr_pubinputs: WITHDRAW_NR_PUBINPUTS,
+    vk_alpha_g1: WITHDRAW_VK_ALPHA_G1,
+    vk_beta_g2: WITHDRAW_VK_BETA_G2,
+    vk_gamme_g2: WITHDRAW_VK_GAMMA_G2,
+    vk_delta_g2: WITHDRAW_VK_DELTA_G2,
+    vk_ic: &WITHDRAW_VK_IC,
+};
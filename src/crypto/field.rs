@@ -0,0 +1,93 @@
+//! Conversions between the 32-byte little-endian field elements used on-chain
+//! and the decimal strings used by circom/snarkjs
+
+use ark_bn254::Fr;
+use ark_ff::{BigInt, BigInteger, PrimeField};
+
+/// Interpret 32 little-endian bytes as an integer
+pub fn bytes_to_bigint(bytes: &[u8; 32]) -> BigInt<4> {
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        *limb = u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+    }
+    BigInt::new(limbs)
+}
+
+/// Encode an integer as 32 little-endian bytes
+pub fn bigint_to_bytes(value: &BigInt<4>) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&value.to_bytes_le());
+    bytes
+}
+
+/// Format a little-endian field element as a decimal string
+pub fn bytes_to_decimal(bytes: &[u8; 32]) -> String {
+    bytes_to_bigint(bytes).to_string()
+}
+
+/// Parse a decimal string into 32 little-endian bytes
+/// Values that don't fit in the BN254 scalar field are rejected rather than reduced
+pub fn decimal_to_bytes(value: &str) -> Result<[u8; 32], &'static str> {
+    let bigint = parse_decimal(value)?;
+    if bigint >= Fr::MODULUS {
+        return Err("Value is not in the scalar field");
+    }
+    Ok(bigint_to_bytes(&bigint))
+}
+
+/// Parse a decimal string into a 256-bit integer without any field reduction
+pub fn parse_decimal(value: &str) -> Result<BigInt<4>, &'static str> {
+    if value.is_empty() {
+        return Err("Empty number");
+    }
+
+    let mut limbs = [0u64; 4];
+    for c in value.chars() {
+        let digit = c.to_digit(10).ok_or("Invalid decimal digit")? as u128;
+        let mut carry = digit;
+        for limb in limbs.iter_mut() {
+            let product = (*limb as u128) * 10 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+        if carry != 0 {
+            return Err("Number does not fit in 256 bits");
+        }
+    }
+
+    Ok(BigInt::new(limbs))
+}
+
+/// Check that 32 little-endian bytes encode a canonical scalar field element
+pub fn is_in_field(bytes: &[u8; 32]) -> bool {
+    bytes_to_bigint(bytes) < Fr::MODULUS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_round_trip() {
+        let value = "1500000000";
+        let bytes = decimal_to_bytes(value).unwrap();
+        assert_eq!(&bytes[..8], &1_500_000_000u64.to_le_bytes());
+        assert_eq!(bytes_to_decimal(&bytes), value);
+
+        let large = "19824143505128883090700010382924500201830337403554510873072627483841559863720";
+        assert_eq!(bytes_to_decimal(&decimal_to_bytes(large).unwrap()), large);
+        assert_eq!(bytes_to_decimal(&[0u8; 32]), "0");
+    }
+
+    #[test]
+    fn test_decimal_rejects_out_of_field() {
+        let modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        assert!(decimal_to_bytes(modulus).is_err());
+        assert!(decimal_to_bytes("").is_err());
+        assert!(decimal_to_bytes("12a").is_err());
+        assert!(parse_decimal(&"9".repeat(78)).is_err());
+
+        let below = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        assert!(is_in_field(&decimal_to_bytes(below).unwrap()));
+    }
+}
@@ -0,0 +1,222 @@
+//! Frontier of a Lean IMT: what `LeanIMTStateZC` keeps of a tree
+//! Enough to keep appending and to know the root, but not to prove leaves already inserted
+
+use crate::crypto::merkle_tree::LeanIMT;
+use crate::crypto::poseidon;
+use crate::state::lean_imt::{LeanIMTStateZC, MAX_TREE_DEPTH};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeanIMTFrontier {
+    size: u64,
+    /// `side_nodes[level]` for `level` in `0..=depth`; the last one is the root
+    side_nodes: Vec<[u8; 32]>,
+}
+
+impl Default for LeanIMTFrontier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LeanIMTFrontier {
+    /// Frontier of an empty tree
+    pub fn new() -> Self {
+        LeanIMTFrontier {
+            size: 0,
+            side_nodes: vec![[0u8; 32]],
+        }
+    }
+
+    /// Frontier of a tree with `size` leaves, e.g. from a decoded account
+    /// `side_nodes` must hold one node per level from the leaves up to the root
+    pub fn from_parts(size: u64, side_nodes: Vec<[u8; 32]>) -> Result<Self, &'static str> {
+        if size > 1u64 << MAX_TREE_DEPTH {
+            return Err("Tree is full");
+        }
+        if side_nodes.len() != LeanIMT::depth_at(size) as usize + 1 {
+            return Err("Side nodes don't match the tree depth");
+        }
+        Ok(LeanIMTFrontier { size, side_nodes })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn depth(&self) -> u8 {
+        (self.side_nodes.len() - 1) as u8
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.side_nodes[self.side_nodes.len() - 1]
+    }
+
+    /// `side_nodes[0..=depth]`, as `TreeAccount` decodes them
+    pub fn side_nodes(&self) -> &[[u8; 32]] {
+        &self.side_nodes
+    }
+
+    /// The side nodes laid out as `LeanIMTStateZC::side_nodes`, zero above the root
+    pub fn to_side_nodes(&self) -> [[u8; 32]; MAX_TREE_DEPTH + 1] {
+        let mut side_nodes = [[0u8; 32]; MAX_TREE_DEPTH + 1];
+        side_nodes[..self.side_nodes.len()].copy_from_slice(&self.side_nodes);
+        side_nodes
+    }
+
+    /// Append a leaf the way `LeanIMTStateZC::insert` does, returning its index
+    /// Duplicates aren't rejected: the frontier doesn't know the earlier leaves
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64, &'static str> {
+        self.insert_visiting(leaf, |_, _| {})
+    }
+
+    /// `insert`, showing `visit` the node the new leaf changed at each level below the root
+    /// (the one at `index >> level`)
+    pub(crate) fn insert_visiting(
+        &mut self,
+        leaf: [u8; 32],
+        mut visit: impl FnMut(usize, &[u8; 32]),
+    ) -> Result<u64, &'static str> {
+        let index = self.size;
+        if index >= 1u64 << MAX_TREE_DEPTH {
+            return Err("Tree is full");
+        }
+
+        let depth = LeanIMT::depth_at(index + 1) as usize;
+        if depth >= self.side_nodes.len() {
+            self.side_nodes.push([0u8; 32]);
+        }
+
+        let mut node = leaf;
+        for level in 0..depth {
+            visit(level, &node);
+            if (index >> level) & 1 == 1 {
+                node = poseidon::hash_two(&self.side_nodes[level], &node);
+            } else {
+                self.side_nodes[level] = node;
+            }
+        }
+
+        self.side_nodes[depth] = node;
+        self.size = index + 1;
+        Ok(index)
+    }
+
+    /// Append several leaves, returning the index of the first one
+    pub fn insert_many(&mut self, leaves: &[[u8; 32]]) -> Result<u64, &'static str> {
+        let first_index = self.size;
+        if first_index + leaves.len() as u64 > 1u64 << MAX_TREE_DEPTH {
+            return Err("Tree is full");
+        }
+        for leaf in leaves {
+            self.insert(*leaf)?;
+        }
+        Ok(first_index)
+    }
+}
+
+impl From<&LeanIMTStateZC> for LeanIMTFrontier {
+    fn from(tree: &LeanIMTStateZC) -> Self {
+        let side_nodes = tree.side_nodes;
+        let depth = (tree.depth as usize).min(MAX_TREE_DEPTH);
+        LeanIMTFrontier {
+            size: tree.size,
+            side_nodes: side_nodes[..=depth].to_vec(),
+        }
+    }
+}
+
+impl LeanIMT {
+    /// The frontier `LeanIMTStateZC` holds for this tree
+    /// The side node of a level is its last node with an even index: the left sibling the
+    /// next insert may hash with
+    pub fn frontier(&self) -> LeanIMTFrontier {
+        let size = self.size();
+        if size == 0 {
+            return LeanIMTFrontier::new();
+        }
+
+        let depth = self.depth();
+        let side_nodes = (0..=depth)
+            .map(|level| {
+                let index = ((size - 1) >> level) & !1;
+                self.node(level, index).expect("index is below the level size")
+            })
+            .collect();
+        LeanIMTFrontier { size, side_nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(i: u64) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[24..].copy_from_slice(&(i + 1).to_be_bytes());
+        leaf
+    }
+
+    fn zero_copy_tree() -> Vec<u8> {
+        let mut buffer = vec![0u8; LeanIMTStateZC::LEN];
+        let tree = unsafe { &mut *(buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+        tree.initialize();
+        buffer
+    }
+
+    #[test]
+    fn test_exported_frontier_matches_zero_copy_tree() {
+        let mut buffer = zero_copy_tree();
+        let on_chain = unsafe { &mut *(buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        assert_eq!(tree.frontier(), LeanIMTFrontier::from(&*on_chain));
+
+        for i in 0..40 {
+            on_chain.insert(leaf(i)).unwrap();
+            tree.insert(leaf(i)).unwrap();
+
+            let frontier = tree.frontier();
+            assert_eq!(frontier, LeanIMTFrontier::from(&*on_chain), "size {}", i + 1);
+            assert_eq!(frontier.to_side_nodes(), { on_chain.side_nodes });
+            assert_eq!(frontier.root(), tree.root());
+        }
+
+        // Batches keep the same side nodes
+        let batch: Vec<_> = (40..51).map(leaf).collect();
+        on_chain.insert_many(&batch).unwrap();
+        tree.insert_many(&batch).unwrap();
+        assert_eq!(tree.frontier(), LeanIMTFrontier::from(&*on_chain));
+    }
+
+    #[test]
+    fn test_seeded_frontier_keeps_appending() {
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        for i in 0..13 {
+            tree.insert(leaf(i)).unwrap();
+        }
+
+        let mut frontier = LeanIMTFrontier::from_parts(tree.size(), tree.frontier().side_nodes().to_vec()).unwrap();
+        for i in 13..70 {
+            assert_eq!(frontier.insert(leaf(i)).unwrap(), i);
+            tree.insert(leaf(i)).unwrap();
+            assert_eq!(frontier.root(), tree.root());
+            assert_eq!(frontier.depth(), tree.depth());
+        }
+
+        let batch: Vec<_> = (70..75).map(leaf).collect();
+        assert_eq!(frontier.insert_many(&batch).unwrap(), 70);
+        tree.insert_many(&batch).unwrap();
+        assert_eq!(frontier, tree.frontier());
+
+        let mut empty = LeanIMTFrontier::new();
+        empty.insert(leaf(0)).unwrap();
+        assert_eq!(empty.root(), leaf(0));
+    }
+
+    #[test]
+    fn test_from_parts_checks_depth() {
+        assert!(LeanIMTFrontier::from_parts(5, vec![[0u8; 32]; 4]).is_ok());
+        assert!(LeanIMTFrontier::from_parts(5, vec![[0u8; 32]; 3]).is_err());
+        assert!(LeanIMTFrontier::from_parts(0, vec![]).is_err());
+        assert!(LeanIMTFrontier::from_parts((1u64 << MAX_TREE_DEPTH) + 1, vec![[0u8; 32]; 34]).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Stateless Lean IMT inclusion proofs
+//! Lets clients check proofs an indexer served without holding the tree. This is library-only:
+//! the program never calls it, since withdrawals prove membership inside the circuit. Siblings
+//! follow the `CircuitMerkleProof` layout: one per level, zero where the node had no right
+//! sibling and was carried up unchanged.
+//!
+//! The walk itself doesn't allocate, but `verify_lean_imt_proof` hashes with
+//! `poseidon::hash_two`, which builds a `Poseidon` on every call; pass a cheaper hash to the
+//! `_with` variants where that matters.
+
+use crate::crypto::poseidon;
+use crate::state::lean_imt::MAX_TREE_DEPTH;
+
+/// Root a proof leads to, combining a left and a right node with `hash`
+/// `None` if `depth` is above `MAX_TREE_DEPTH` or the number of siblings, if `index` doesn't
+/// fit in `depth` levels, or if a right node has no left sibling (the tree never has those)
+pub fn compute_lean_imt_root_with<H>(
+    leaf: &[u8; 32],
+    index: u64,
+    siblings: &[[u8; 32]],
+    depth: u8,
+    mut hash: H,
+) -> Option<[u8; 32]>
+where
+    H: FnMut(&[u8; 32], &[u8; 32]) -> [u8; 32],
+{
+    let depth = depth as usize;
+    if depth > MAX_TREE_DEPTH || depth > siblings.len() || index >> depth != 0 {
+        return None;
+    }
+
+    let mut node = *leaf;
+    for (level, sibling) in siblings[..depth].iter().enumerate() {
+        let is_right = (index >> level) & 1 == 1;
+        if *sibling == [0u8; 32] {
+            if is_right {
+                return None;
+            }
+            continue;
+        }
+        node = if is_right {
+            hash(sibling, &node)
+        } else {
+            hash(&node, sibling)
+        };
+    }
+    Some(node)
+}
+
+/// Verify a Lean IMT proof with a custom hash, e.g. a syscall-backed Poseidon
+pub fn verify_lean_imt_proof_with<H>(
+    leaf: &[u8; 32],
+    index: u64,
+    siblings: &[[u8; 32]],
+    depth: u8,
+    root: &[u8; 32],
+    hash: H,
+) -> bool
+where
+    H: FnMut(&[u8; 32], &[u8; 32]) -> [u8; 32],
+{
+    compute_lean_imt_root_with(leaf, index, siblings, depth, hash).is_some_and(|computed| computed == *root)
+}
+
+/// Verify that `leaf` is at `index` in the Lean IMT with `root`, using Poseidon
+/// `siblings` holds at least `depth` entries; entries past `depth` (circuit padding) are ignored
+pub fn verify_lean_imt_proof(leaf: &[u8; 32], index: u64, siblings: &[[u8; 32]], depth: u8, root: &[u8; 32]) -> bool {
+    verify_lean_imt_proof_with(leaf, index, siblings, depth, root, poseidon::hash_two)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::merkle_tree::LeanIMT;
+
+    fn leaf(i: u64) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[24..].copy_from_slice(&(i + 1).to_be_bytes());
+        leaf
+    }
+
+    fn tree_of(size: u64) -> LeanIMT {
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        for i in 0..size {
+            tree.insert(leaf(i)).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_verifies_tree_proofs() {
+        for size in [1, 2, 3, 7, 8, 11] {
+            let tree = tree_of(size);
+            for index in 0..size {
+                let proof = tree.generate_circuit_proof(index, MAX_TREE_DEPTH).unwrap();
+                assert!(verify_lean_imt_proof(&proof.leaf, index, &proof.siblings, proof.depth, &tree.root()));
+                // Without the padding too
+                let unpadded = &proof.siblings[..proof.depth as usize];
+                assert!(verify_lean_imt_proof(&proof.leaf, index, unpadded, proof.depth, &tree.root()));
+                assert!(proof.verify());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_inputs() {
+        let tree = tree_of(6);
+        let proof = tree.generate_circuit_proof(4, MAX_TREE_DEPTH).unwrap();
+        let root = tree.root();
+        let depth = proof.depth;
+        assert!(verify_lean_imt_proof(&leaf(4), 4, &proof.siblings, depth, &root));
+
+        assert!(!verify_lean_imt_proof(&leaf(5), 4, &proof.siblings, depth, &root));
+        assert!(!verify_lean_imt_proof(&leaf(4), 5, &proof.siblings, depth, &root));
+        assert!(!verify_lean_imt_proof(&leaf(4), 4, &proof.siblings, depth, &leaf(0)));
+        assert!(!verify_lean_imt_proof(&leaf(4), 4, &proof.siblings[..2], depth, &root));
+        assert!(!verify_lean_imt_proof(&leaf(4), 4, &proof.siblings, depth - 1, &root));
+        assert!(!verify_lean_imt_proof(&leaf(4), 4 + (1 << depth), &proof.siblings, depth, &root));
+
+        // Leaf 4 is a left node without a right sibling at level 1; claiming to be its right
+        // neighbour there needs a non-zero left sibling
+        assert!(!verify_lean_imt_proof(&leaf(4), 6, &proof.siblings, depth, &root));
+
+        let single = tree_of(1).root();
+        assert!(verify_lean_imt_proof(&leaf(0), 0, &[], 0, &single));
+        assert!(!verify_lean_imt_proof(&leaf(0), 1, &[], 0, &single));
+    }
+
+    #[test]
+    fn test_custom_hash() {
+        let xor = |left: &[u8; 32], right: &[u8; 32]| {
+            let mut node = [0u8; 32];
+            for (byte, (l, r)) in node.iter_mut().zip(left.iter().zip(right)) {
+                *byte = l ^ r;
+            }
+            node
+        };
+        let siblings = [[1u8; 32], [0u8; 32], [2u8; 32]];
+        let root = compute_lean_imt_root_with(&[4u8; 32], 1, &siblings, 3, xor).unwrap();
+        assert_eq!(root, [7u8; 32]);
+        assert!(verify_lean_imt_proof_with(&[4u8; 32], 1, &siblings, 3, &root, xor));
+    }
+}
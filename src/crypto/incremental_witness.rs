@@ -0,0 +1,190 @@
+//! Incremental witness for one leaf of a Lean IMT, as in Zcash
+//! Keeps the leaf's authentication path current as leaves are appended after it, with
+//! nothing but the tree's frontier: no earlier leaves and no full tree are needed.
+
+use crate::crypto::frontier::LeanIMTFrontier;
+use crate::crypto::merkle_tree::{CircuitMerkleProof, MerkleProof};
+use crate::crypto::poseidon;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalWitness {
+    /// Frontier of the whole tree, to keep appending and know the root
+    tree: LeanIMTFrontier,
+    leaf: [u8; 32],
+    index: u64,
+    /// Sibling of the leaf's path at each level, once it exists
+    /// Left siblings are known from the start; right ones change until their subtree is full
+    siblings: Vec<Option<[u8; 32]>>,
+}
+
+impl IncrementalWitness {
+    /// Witness the last leaf of `tree`, e.g. a frontier read right after the deposit
+    pub fn from_frontier(tree: LeanIMTFrontier, leaf: [u8; 32]) -> Result<Self, &'static str> {
+        if tree.size() == 0 {
+            return Err("Tree is empty");
+        }
+
+        let index = tree.size() - 1;
+        let mut node = leaf;
+        let siblings: Vec<_> = (0..tree.depth() as usize)
+            .map(|level| {
+                // Being the last leaf, every sibling to the right is still missing
+                if (index >> level) & 1 == 1 {
+                    let left = tree.side_nodes()[level];
+                    node = poseidon::hash_two(&left, &node);
+                    Some(left)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if node != tree.root() {
+            return Err("Leaf is not the last one in the tree");
+        }
+        Ok(IncrementalWitness { tree, leaf, index, siblings })
+    }
+
+    /// Append a leaf after the witnessed one, returning its index
+    pub fn append(&mut self, leaf: [u8; 32]) -> Result<u64, &'static str> {
+        let witnessed = self.index;
+        let siblings = &mut self.siblings;
+        let new_index = self.tree.size();
+        self.tree.insert_visiting(leaf, |level, node| {
+            if level >= siblings.len() {
+                siblings.push(None);
+            }
+            // The new leaf is under the right sibling of the witnessed path at this level
+            let position = witnessed >> level;
+            if position & 1 == 0 && new_index >> level == position + 1 {
+                siblings[level] = Some(*node);
+            }
+        })
+    }
+
+    pub fn leaf(&self) -> [u8; 32] {
+        self.leaf
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Current root of the tree
+    pub fn root(&self) -> [u8; 32] {
+        self.tree.root()
+    }
+
+    pub fn tree(&self) -> &LeanIMTFrontier {
+        &self.tree
+    }
+
+    /// The leaf's proof against the current root, as `LeanIMT::generate_proof` makes it
+    pub fn proof(&self) -> MerkleProof {
+        let mut siblings = Vec::new();
+        let mut path = Vec::new();
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            if let Some(sibling) = sibling {
+                siblings.push(*sibling);
+                path.push((self.index >> level) & 1 == 1);
+            }
+        }
+        MerkleProof {
+            root: self.root(),
+            leaf: self.leaf,
+            siblings,
+            path,
+        }
+    }
+
+    /// The proof laid out for the circuit, as `LeanIMT::generate_circuit_proof` makes it
+    pub fn circuit_proof(&self, max_depth: usize) -> Result<CircuitMerkleProof, &'static str> {
+        if self.tree.depth() as usize > max_depth {
+            return Err("Tree is deeper than the circuit supports");
+        }
+
+        let mut siblings = vec![[0u8; 32]; max_depth];
+        for (slot, sibling) in siblings.iter_mut().zip(&self.siblings) {
+            *slot = sibling.unwrap_or([0u8; 32]);
+        }
+        Ok(CircuitMerkleProof {
+            root: self.root(),
+            leaf: self.leaf,
+            leaf_index: self.index,
+            depth: self.tree.depth(),
+            siblings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::merkle_tree::LeanIMT;
+    use crate::state::lean_imt::MAX_TREE_DEPTH;
+
+    fn leaf(i: u64) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[24..].copy_from_slice(&(i + 1).to_be_bytes());
+        leaf
+    }
+
+    fn assert_same_proof(witness: &IncrementalWitness, tree: &LeanIMT) {
+        let expected = tree.generate_proof(witness.index()).unwrap();
+        let proof = witness.proof();
+        assert_eq!(proof.root, expected.root);
+        assert_eq!(proof.leaf, expected.leaf);
+        assert_eq!(proof.siblings, expected.siblings);
+        assert_eq!(proof.path, expected.path);
+        assert!(tree.verify_proof(&proof));
+
+        let circuit = witness.circuit_proof(MAX_TREE_DEPTH).unwrap();
+        assert_eq!(circuit, tree.generate_circuit_proof(witness.index(), MAX_TREE_DEPTH).unwrap());
+    }
+
+    #[test]
+    fn test_witness_follows_appends() {
+        for deposit in [0, 1, 2, 5, 8, 13] {
+            let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+            for i in 0..=deposit {
+                tree.insert(leaf(i)).unwrap();
+            }
+
+            let mut witness = IncrementalWitness::from_frontier(tree.frontier(), leaf(deposit)).unwrap();
+            assert_same_proof(&witness, &tree);
+
+            for i in deposit + 1..deposit + 40 {
+                assert_eq!(witness.append(leaf(i)).unwrap(), i);
+                tree.insert(leaf(i)).unwrap();
+                assert_same_proof(&witness, &tree);
+            }
+        }
+    }
+
+    #[test]
+    fn test_witness_from_frontier_before_deposit() {
+        // A frontier fetched before the deposit lands, with the deposit appended locally
+        let mut frontier = LeanIMTFrontier::new();
+        frontier.insert_many(&[leaf(0), leaf(1), leaf(2)]).unwrap();
+        frontier.insert(leaf(3)).unwrap();
+
+        let mut witness = IncrementalWitness::from_frontier(frontier, leaf(3)).unwrap();
+        witness.append(leaf(4)).unwrap();
+
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        tree.insert_many(&[leaf(0), leaf(1), leaf(2), leaf(3), leaf(4)]).unwrap();
+        assert_same_proof(&witness, &tree);
+    }
+
+    #[test]
+    fn test_witness_rejects_other_leaves() {
+        let mut tree = LeanIMT::new(MAX_TREE_DEPTH as u8);
+        tree.insert_many(&[leaf(0), leaf(1), leaf(2)]).unwrap();
+
+        assert!(IncrementalWitness::from_frontier(tree.frontier(), leaf(1)).is_err());
+        assert!(IncrementalWitness::from_frontier(LeanIMTFrontier::new(), leaf(0)).is_err());
+
+        let witness = IncrementalWitness::from_frontier(tree.frontier(), leaf(2)).unwrap();
+        assert!(witness.circuit_proof(1).is_err());
+    }
+}
@@ -1,8 +1,9 @@
 /// Lean Incremental Merkle Tree implementation for Solana
 /// Based on the LeanIMT design from zk-kit
 
-use crate::crypto::poseidon;
+use crate::crypto::{field, inclusion, poseidon};
 
+#[derive(Debug, Clone)]
 pub struct LeanIMT {
     /// The matrix where all tree nodes are stored
     /// nodes[level][index] contains the node at that position
@@ -39,6 +40,16 @@ impl LeanIMT {
         }
     }
     
+    /// Node at `level`/`index`, if the tree has one there
+    pub fn node(&self, level: u8, index: u64) -> Option<[u8; 32]> {
+        self.nodes.get(level as usize)?.get(index as usize).copied()
+    }
+    
+    /// Index of the first occurrence of `leaf`, if present
+    pub fn index_of(&self, leaf: &[u8; 32]) -> Option<u64> {
+        self.nodes[0].iter().position(|node| node == leaf).map(|index| index as u64)
+    }
+    
     /// Insert a new leaf into the tree
     pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64, &'static str> {
         let index = self.size();
@@ -50,44 +61,115 @@ impl LeanIMT {
             }
         }
         
-        // Check if we need to add a new level
-        // For n leaves, we need ceil(log2(n+1)) levels
-        let required_depth = if index == 0 {
-            0
-        } else {
-            (64 - (index + 1).leading_zeros() - 1) as usize
-        };
-        
-        while self.nodes.len() <= required_depth + 1 {
+        // A new insertion can increase tree depth by at most 1
+        if (1u64 << self.depth()) < index + 1 {
             self.nodes.push(Vec::new());
         }
         
         let mut node = leaf;
         let mut current_index = index as usize;
         
-        for level in 0..=self.depth() {
-            // Ensure the vector at this level has enough capacity
-            while self.nodes[level as usize].len() <= current_index {
-                self.nodes[level as usize].push([0u8; 32]);
+        for level in 0..self.depth() as usize {
+            Self::set_node(&mut self.nodes[level], current_index, node);
+            
+            // Check if this is a right node (odd index)
+            if current_index & 1 == 1 {
+                // It's a right node, hash with left sibling
+                let sibling = self.nodes[level][current_index - 1];
+                node = poseidon::hash_two(&sibling, &node);
             }
+            // For left nodes the parent equals the left child until a right child is added
             
-            self.nodes[level as usize][current_index] = node;
+            current_index >>= 1;
+        }
+        
+        let depth = self.depth() as usize;
+        self.nodes[depth] = vec![node];
+        
+        Ok(index)
+    }
+    
+    /// Insert several leaves at once, hashing every affected level only once
+    /// Produces the same tree as inserting the leaves one by one (zk-kit `insertMany`)
+    /// Returns the index of the first inserted leaf
+    pub fn insert_many(&mut self, leaves: &[[u8; 32]]) -> Result<u64, &'static str> {
+        let first_index = self.size();
+        if leaves.is_empty() {
+            return Ok(first_index);
+        }
+        
+        let new_size = first_index + leaves.len() as u64;
+        if let Some(max_d) = self.max_depth {
+            if new_size > (1u64 << max_d) {
+                return Err("Tree is full");
+            }
+        }
+        
+        self.nodes[0].extend_from_slice(leaves);
+        
+        // Grow to ceil(log2(new_size)) levels above the leaves
+        while (1u64 << self.depth()) < new_size {
+            self.nodes.push(Vec::new());
+        }
+        
+        // Only nodes from the parent of the first new leaf onwards change
+        let mut start_index = (first_index >> 1) as usize;
+        for level in 0..self.depth() as usize {
+            let number_of_nodes = self.nodes[level].len().div_ceil(2);
+            for index in start_index..number_of_nodes {
+                let left = self.nodes[level][index * 2];
+                let parent = match self.nodes[level].get(index * 2 + 1) {
+                    Some(right) => poseidon::hash_two(&left, right),
+                    None => left,
+                };
+                Self::set_node(&mut self.nodes[level + 1], index, parent);
+            }
+            start_index >>= 1;
+        }
+        
+        Ok(first_index)
+    }
+    
+    /// Replace the leaf at `index`, recomputing only the nodes on its path to the root
+    pub fn update(&mut self, index: u64, new_leaf: [u8; 32]) -> Result<(), &'static str> {
+        if index >= self.size() {
+            return Err("Index out of bounds");
+        }
+        
+        let mut node = new_leaf;
+        let mut current_index = index as usize;
+        
+        for level in 0..self.depth() as usize {
+            self.nodes[level][current_index] = node;
             
-            if level < self.depth() {
-                // Check if this is a right node (odd index)
-                if current_index & 1 == 1 {
-                    // It's a right node, hash with left sibling
-                    let sibling = self.nodes[level as usize][current_index - 1];
-                    node = poseidon::hash_two(&sibling, &node);
-                }
-                // For left nodes, we don't compute the parent here during insertion
-                // The parent equals the left child until a right child is added
-                
-                current_index >>= 1;
+            if current_index & 1 == 1 {
+                let sibling = self.nodes[level][current_index - 1];
+                node = poseidon::hash_two(&sibling, &node);
+            } else if let Some(sibling) = self.nodes[level].get(current_index + 1) {
+                node = poseidon::hash_two(&node, sibling);
             }
+            
+            current_index >>= 1;
         }
         
-        Ok(index)
+        let depth = self.depth() as usize;
+        self.nodes[depth][0] = node;
+        
+        Ok(())
+    }
+    
+    /// Remove the leaf at `index` by setting it to zero, as zk-kit does
+    /// The tree keeps its size, so the indices of the other leaves don't change
+    pub fn remove(&mut self, index: u64) -> Result<(), &'static str> {
+        self.update(index, [0u8; 32])
+    }
+    
+    fn set_node(level_nodes: &mut Vec<[u8; 32]>, index: usize, node: [u8; 32]) {
+        if index < level_nodes.len() {
+            level_nodes[index] = node;
+        } else {
+            level_nodes.push(node);
+        }
     }
     
     /// Generate a Merkle proof for a leaf at the given index
@@ -151,6 +233,122 @@ impl LeanIMT {
         })
     }
     
+    /// Depth of the tree when it held `size` leaves
+    pub fn depth_at(size: u64) -> u8 {
+        if size <= 1 {
+            0
+        } else {
+            (64 - (size - 1).leading_zeros()) as u8
+        }
+    }
+    
+    /// Root of the tree as it was after its first `size` leaves were inserted
+    /// Only valid for histories made of insertions: `update`/`remove` rewrite past nodes
+    pub fn root_at(&self, size: u64) -> Result<[u8; 32], &'static str> {
+        if size > self.size() {
+            return Err("Size is larger than the tree");
+        }
+        if size == 0 {
+            return Ok([0u8; 32]);
+        }
+        
+        Ok(self.node_at(Self::depth_at(size) as usize, 0, size))
+    }
+    
+    /// Node at `level`/`index` in the tree as it was at `size` leaves
+    /// Nodes whose whole subtree was already filled are unchanged since then;
+    /// only the ones on the right edge are recomputed, so this costs at most one hash per level
+    fn node_at(&self, level: usize, index: usize, size: u64) -> [u8; 32] {
+        let subtree_end = ((index as u64) + 1) << level;
+        if level == 0 || subtree_end <= size {
+            return self.nodes[level][index];
+        }
+        
+        let left = self.node_at(level - 1, index * 2, size);
+        if Self::node_exists_at(level - 1, index * 2 + 1, size) {
+            poseidon::hash_two(&left, &self.node_at(level - 1, index * 2 + 1, size))
+        } else {
+            left
+        }
+    }
+    
+    fn node_exists_at(level: usize, index: usize, size: u64) -> bool {
+        ((index as u64) << level) < size
+    }
+    
+    /// Generate a Merkle proof for the leaf at `index` against the root the tree had at `size` leaves,
+    /// so a client can keep proving against a root it already fetched while new deposits land
+    pub fn generate_proof_at(&self, index: u64, size: u64) -> Result<MerkleProof, &'static str> {
+        if index >= size {
+            return Err("Index out of bounds");
+        }
+        
+        let root = self.root_at(size)?;
+        let mut siblings = Vec::new();
+        let mut path = Vec::new();
+        let mut current_index = index as usize;
+        
+        for level in 0..Self::depth_at(size) as usize {
+            let sibling_index = current_index ^ 1;
+            if Self::node_exists_at(level, sibling_index, size) {
+                siblings.push(self.node_at(level, sibling_index, size));
+                path.push(current_index & 1 == 1);
+            }
+            current_index >>= 1;
+        }
+        
+        Ok(MerkleProof {
+            root,
+            leaf: self.nodes[0][index as usize],
+            siblings,
+            path,
+        })
+    }
+    
+    /// Generate a proof laid out for the `LeanIMTInclusionProof(maxDepth)` circuit
+    /// `siblings[level]` is the sibling at that level, or zero where the node has no right sibling
+    /// (the circuit then propagates the node unchanged), padded with zeros up to `max_depth`
+    pub fn generate_circuit_proof(&self, index: u64, max_depth: usize) -> Result<CircuitMerkleProof, &'static str> {
+        self.generate_circuit_proof_at(index, self.size(), max_depth)
+    }
+    
+    /// Circuit proof for the leaf at `index` against the root the tree had at `size` leaves
+    pub fn generate_circuit_proof_at(
+        &self,
+        index: u64,
+        size: u64,
+        max_depth: usize,
+    ) -> Result<CircuitMerkleProof, &'static str> {
+        if index >= size {
+            return Err("Index out of bounds");
+        }
+        
+        let root = self.root_at(size)?;
+        let depth = Self::depth_at(size);
+        if depth as usize > max_depth {
+            return Err("Tree is deeper than the circuit supports");
+        }
+        
+        let mut siblings = vec![[0u8; 32]; max_depth];
+        let mut current_index = index as usize;
+        
+        for (level, sibling) in siblings.iter_mut().enumerate().take(depth as usize) {
+            let sibling_index = current_index ^ 1;
+            if Self::node_exists_at(level, sibling_index, size) {
+                *sibling = self.node_at(level, sibling_index, size);
+            }
+            current_index >>= 1;
+        }
+        
+        Ok(CircuitMerkleProof {
+            root,
+            leaf: self.nodes[0][index as usize],
+            leaf_index: index,
+            depth,
+            siblings,
+        })
+    }
+    
     /// Verify a Merkle proof
     pub fn verify_proof(&self, proof: &MerkleProof) -> bool {
         let mut node = proof.leaf;
@@ -171,9 +369,10 @@ impl LeanIMT {
     }
     
     // Helper functions for compatibility with existing tests
+    // For checking a proof without the tree, see `inclusion::verify_lean_imt_proof`
     pub fn verify_inclusion(
         &self,
-        _leaf: [u8; 32],
+        leaf: [u8; 32],
         index: u64,
         siblings: &[[u8; 32]],
         _depth: u8,
@@ -185,6 +384,9 @@ impl LeanIMT {
         // Generate the full proof to get the path information
         match self.generate_proof(index) {
             Ok(proof) => {
+                if proof.leaf != leaf {
+                    return false;
+                }
                 // Verify that the siblings match
                 if proof.siblings.len() != siblings.len() {
                     return false;
@@ -216,6 +418,60 @@ pub struct MerkleProof {
     pub path: Vec<bool>,
 }
 
+/// Merkle proof in the shape the withdraw circuit consumes
+/// A sibling that is itself zero (e.g. a removed leaf) is indistinguishable from a missing one,
+/// so such leaves can't be proven by the circuit
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitMerkleProof {
+    pub root: [u8; 32],
+    pub leaf: [u8; 32],
+    /// `leafIndex` input
+    pub leaf_index: u64,
+    /// `actualDepth` input
+    pub depth: u8,
+    /// `siblings[maxDepth]` input
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl CircuitMerkleProof {
+    /// Siblings as decimal field elements
+    pub fn siblings_decimal(&self) -> Vec<String> {
+        self.siblings.iter().map(field::bytes_to_decimal).collect()
+    }
+    
+    /// Recompute the root the way the circuit does
+    pub fn compute_root(&self) -> [u8; 32] {
+        let mut node = self.leaf;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            if *sibling == [0u8; 32] {
+                continue;
+            }
+            node = if (self.leaf_index >> level) & 1 == 1 {
+                poseidon::hash_two(sibling, &node)
+            } else {
+                poseidon::hash_two(&node, sibling)
+            };
+        }
+        node
+    }
+    
+    /// Check the proof against its root without the tree
+    pub fn verify(&self) -> bool {
+        inclusion::verify_lean_imt_proof(&self.leaf, self.leaf_index, &self.siblings, self.depth, &self.root)
+    }
+    
+    /// Circuit inputs for this proof, e.g. `prefix = "state"` gives
+    /// `stateSiblings`, `stateIndex` and `stateTreeDepth` (use `"ASP"` for the ASP tree)
+    #[cfg(feature = "client")]
+    pub fn to_circuit_inputs(&self, prefix: &str) -> serde_json::Map<String, serde_json::Value> {
+        let mut inputs = serde_json::Map::new();
+        inputs.insert(format!("{}Siblings", prefix), serde_json::json!(self.siblings_decimal()));
+        inputs.insert(format!("{}Index", prefix), serde_json::json!(self.leaf_index.to_string()));
+        inputs.insert(format!("{}TreeDepth", prefix), serde_json::json!(self.depth.to_string()));
+        inputs
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -278,5 +534,140 @@ mod tests {
         
         assert!(is_valid);
         assert!(is_valid2);
+        
+        // The leaf has to be the one at the index
+        assert!(!tree.verify_inclusion([0xffu8; 32], 8, &siblings, tree.depth()));
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_lean_imt_insert_many() {
+        let leaves: Vec<[u8; 32]> = (1..=13u8).map(|i| [i; 32]).collect();
+        
+        let mut single = LeanIMT::new(10);
+        for leaf in &leaves {
+            single.insert(*leaf).unwrap();
+        }
+        
+        let mut batched = LeanIMT::new(10);
+        assert_eq!(batched.insert_many(&leaves[..5]).unwrap(), 0);
+        assert_eq!(batched.insert_many(&leaves[5..]).unwrap(), 5);
+        
+        assert_eq!(batched.root(), single.root());
+        assert_eq!(batched.depth(), single.depth());
+        assert_eq!(batched.size(), 13);
+        
+        for index in 0..13 {
+            let proof = batched.generate_proof(index).unwrap();
+            assert!(batched.verify_proof(&proof));
+        }
+        
+        // Capacity applies to the whole batch
+        let mut small = LeanIMT::new(2);
+        assert!(small.insert_many(&leaves[..5]).is_err());
+        assert_eq!(small.size(), 0);
+    }
+    
+    #[test]
+    fn test_lean_imt_update_and_remove() {
+        let leaves: Vec<[u8; 32]> = (1..=7u8).map(|i| [i; 32]).collect();
+        let mut tree = LeanIMT::new(10);
+        tree.insert_many(&leaves).unwrap();
+        
+        // Updating a leaf gives the same root as building the tree with the new leaf
+        let new_leaf = [42u8; 32];
+        tree.update(4, new_leaf).unwrap();
+        let mut expected_leaves = leaves.clone();
+        expected_leaves[4] = new_leaf;
+        let mut expected = LeanIMT::new(10);
+        expected.insert_many(&expected_leaves).unwrap();
+        assert_eq!(tree.root(), expected.root());
+        
+        // Removing sets the leaf to zero without shrinking the tree
+        tree.remove(6).unwrap();
+        expected_leaves[6] = [0u8; 32];
+        let mut expected = LeanIMT::new(10);
+        expected.insert_many(&expected_leaves).unwrap();
+        assert_eq!(tree.root(), expected.root());
+        assert_eq!(tree.size(), 7);
+        
+        for index in 0..7 {
+            let proof = tree.generate_proof(index).unwrap();
+            assert!(tree.verify_proof(&proof));
+        }
+        
+        assert!(tree.update(7, new_leaf).is_err());
+        assert!(tree.remove(7).is_err());
+    }
+    
+    #[test]
+    fn test_lean_imt_update_and_remove_vectors() {
+        // Roots from @zk-kit/lean-imt with circomlibjs Poseidon (scripts/lean-imt-update-vectors.js)
+        let field = |value: u64| field::decimal_to_bytes(&value.to_string()).unwrap();
+        let root = |tree: &LeanIMT| field::bytes_to_decimal(&tree.root());
+        
+        let mut tree = LeanIMT::new(10);
+        tree.insert_many(&(1..=7).map(field).collect::<Vec<_>>()).unwrap();
+        assert_eq!(root(&tree), "9097114702656722376419439788149110565393180352312461170314908086900836776912");
+        
+        tree.update(4, field(42)).unwrap();
+        assert_eq!(root(&tree), "19476919871928035556924372204025689979999776484065429447097764221269967773716");
+        
+        tree.remove(6).unwrap();
+        assert_eq!(root(&tree), "14038459444231467153957072905061382213937537075170145191584204431925552597867");
+        
+        tree.remove(0).unwrap();
+        assert_eq!(root(&tree), "10283025188304095588716647819796766551305806991848187466487343032964362045211");
+        assert_eq!(tree.size(), 7);
+    }
+    
+    #[test]
+    fn test_lean_imt_circuit_proof() {
+        let leaves: Vec<[u8; 32]> = (1..=5u8).map(|i| [i; 32]).collect();
+        let mut tree = LeanIMT::new(20);
+        tree.insert_many(&leaves).unwrap();
+        
+        for index in 0..5 {
+            let proof = tree.generate_circuit_proof(index, 20).unwrap();
+            assert_eq!(proof.siblings.len(), 20);
+            assert_eq!(proof.depth, 3);
+            assert_eq!(proof.leaf_index, index);
+            assert_eq!(proof.compute_root(), tree.root());
+        }
+        
+        // Leaf 4 is a left node without right siblings below the top level
+        let proof = tree.generate_circuit_proof(4, 20).unwrap();
+        assert_eq!(proof.siblings[0], [0u8; 32]);
+        assert_eq!(proof.siblings[1], [0u8; 32]);
+        assert_ne!(proof.siblings[2], [0u8; 32]);
+        
+        assert!(tree.generate_circuit_proof(5, 20).is_err());
+        assert!(tree.generate_circuit_proof(0, 2).is_err());
+    }
+    
+    #[test]
+    fn test_lean_imt_historical_proofs() {
+        let mut tree = LeanIMT::new(20);
+        let mut past_roots = vec![tree.root()];
+        for i in 1..=9u8 {
+            tree.insert([i; 32]).unwrap();
+            past_roots.push(tree.root());
+        }
+        
+        for size in 1..=9u64 {
+            assert_eq!(tree.root_at(size).unwrap(), past_roots[size as usize]);
+            
+            for index in 0..size {
+                let proof = tree.generate_proof_at(index, size).unwrap();
+                assert_eq!(proof.root, past_roots[size as usize]);
+                assert!(tree.verify_proof(&proof));
+                
+                let circuit_proof = tree.generate_circuit_proof_at(index, size, 20).unwrap();
+                assert_eq!(circuit_proof.depth, LeanIMT::depth_at(size));
+                assert_eq!(circuit_proof.compute_root(), past_roots[size as usize]);
+            }
+        }
+        
+        assert!(tree.generate_proof_at(3, 3).is_err());
+        assert!(tree.root_at(10).is_err());
+    }
+}
@@ -1,7 +1,15 @@
+pub mod circuit_keys;
+pub mod field;
+pub mod frontier;
+pub mod inclusion;
+pub mod incremental_witness;
 pub mod merkle_tree;
 pub mod poseidon;
 pub mod verifying_key;
 
+pub use frontier::*;
+pub use inclusion::*;
+pub use incremental_witness::*;
 pub use merkle_tree::*;
 pub use poseidon::*;
 pub use verifying_key::*;
\ No newline at end of file
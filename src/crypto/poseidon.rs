@@ -1,3 +1,5 @@
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
 use poseidon_ark::Poseidon;
 use crate::instructions::types::WithdrawalData;
 
@@ -28,9 +30,23 @@ pub fn compute_label(scope: &[u8; 32], nonce: u64) -> [u8; 32] {
     hasher.hash(&nonce.to_le_bytes());
     let hash = hasher.result().to_bytes();
     
-    // With native Poseidon, we can use the hash directly
-    // The syscall handles field modular reduction internally
-    hash
+    // Labels are circuit inputs and ASP tree leaves, so they have to be field elements
+    reduce_to_field(&hash)
+}
+
+/// Compute label for a sharded deposit: keccak256(scope, shard_index, nonce) % SNARK_SCALAR_FIELD
+/// Each shard keeps its own nonce, so the shard index is mixed in to keep labels unique across the pool
+pub fn compute_shard_label(scope: &[u8; 32], shard_index: u16, nonce: u64) -> [u8; 32] {
+    use solana_program::keccak;
+    
+    let mut hasher = keccak::Hasher::default();
+    hasher.hash(scope);
+    hasher.hash(&shard_index.to_le_bytes());
+    hasher.hash(&nonce.to_le_bytes());
+    let hash = hasher.result().to_bytes();
+    
+    // Shard labels only reach an ASP tree through the ASP service, which needs field elements
+    reduce_to_field(&hash)
 }
 
 /// Compute commitment hash: PoseidonT4.hash([value, label, precommitment_hash])  
@@ -65,9 +81,13 @@ pub fn compute_context(withdrawal: &WithdrawalData, scope: &[u8; 32]) -> [u8; 32
     
     let hash = hasher.result().to_bytes();
     
-    // With native Poseidon, we can use the hash directly
-    // The syscall handles field modular reduction internally
-    hash
+    // The proof carries the context as a public signal, which is always a field element
+    reduce_to_field(&hash)
+}
+
+/// Reduce a keccak digest (read little-endian, like every field element here) into the scalar field
+fn reduce_to_field(hash: &[u8; 32]) -> [u8; 32] {
+    crate::crypto::field::bigint_to_bytes(&Fr::from_le_bytes_mod_order(hash).into_bigint())
 }
 
 #[cfg(test)]
@@ -126,6 +146,30 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_labels_and_contexts_are_field_elements() {
+        let scope = [3u8; 32];
+        for nonce in 1..=16u64 {
+            assert!(crate::crypto::field::is_in_field(&compute_label(&scope, nonce)));
+            
+            let withdrawal = WithdrawalData {
+                processooor: [nonce as u8; 32],
+                data: nonce.to_le_bytes().to_vec(),
+            };
+            assert!(crate::crypto::field::is_in_field(&compute_context(&withdrawal, &scope)));
+        }
+    }
+
+    #[test]
+    fn test_shard_labels_are_field_elements() {
+        let scope = [3u8; 32];
+        for nonce in 1..=16u64 {
+            let label = compute_shard_label(&scope, 2, nonce);
+            assert!(crate::crypto::field::is_in_field(&label));
+            assert_ne!(label, compute_shard_label(&scope, 3, nonce));
+        }
+    }
+
     #[test]
     fn debug_poseidon_integration() {
         // Test the poseidon-ark library directly
@@ -0,0 +1,27 @@
+//! Slot the current instruction runs in
+//! On-chain this comes from the Clock sysvar. Host builds have no sysvars, so tests and
+//! off-chain simulations set the slot with `set_host_slot` instead.
+
+use pinocchio::program_error::ProgramError;
+
+#[cfg(target_os = "solana")]
+pub fn current_slot() -> Result<u64, ProgramError> {
+    use pinocchio::sysvars::{clock::Clock, Sysvar};
+    Ok(Clock::get()?.slot)
+}
+
+#[cfg(not(target_os = "solana"))]
+std::thread_local! {
+    static HOST_SLOT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(not(target_os = "solana"))]
+pub fn current_slot() -> Result<u64, ProgramError> {
+    Ok(HOST_SLOT.with(|slot| slot.get()))
+}
+
+/// Slot `current_slot` reports on this thread in host builds
+#[cfg(not(target_os = "solana"))]
+pub fn set_host_slot(slot: u64) {
+    HOST_SLOT.with(|current| current.set(slot));
+}
@@ -1,13 +1,16 @@
 use pinocchio::{
     account_info::AccountInfo,
+    msg,
     program_error::ProgramError,
     pubkey::Pubkey,
     ProgramResult,
 };
 
-use crate::state::{PoolStateLeanIMT, DepositorStateZC};
+use crate::state::{PoolStateLeanIMT, DepositorStateZC, DepositQueueZC};
 
 /// Make a deposit to the privacy pool using Lean IMT
+/// Once the pool has a deposit queue attached, the queue must be passed after the signer;
+/// the commitment and label are then only appended to it and inserted later by `ProcessQueue`
 pub fn deposit(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -37,22 +40,202 @@ pub fn deposit(
         return Err(ProgramError::InvalidAccountData);
     }
     
-    if value >= u128::MAX as u64 {
-        return Err(ProgramError::InvalidArgument);
-    }
+    check_deposit_value(value)?;
     
     let nonce = pool_state.increment_nonce();
     let label = crate::crypto::poseidon::compute_label(&pool_state.scope, nonce);
     let commitment = crate::crypto::poseidon::compute_commitment(value, &label, &precommitment_hash);
     
-    // Insert commitment into state tree
-    pool_state.insert_state_commitment(commitment)?;
-    
-    // Insert label into ASP tree
-    pool_state.insert_asp_label(label)?;
+    if pool_state.has_deposit_queue() {
+        let queue_account = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if queue_account.key() != &pool_state.deposit_queue {
+            msg!("Not the pool's deposit queue");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        let queue = DepositQueueZC::from_account_mut(queue_account)?;
+        queue.validate(pool_account.key())?;
+        queue.push(commitment, label)?;
+    } else if accounts.len() > 3 {
+        msg!("Pool has no deposit queue");
+        return Err(ProgramError::InvalidArgument);
+    } else {
+        let mut roots = PoolStateLeanIMT::root_history_mut(pool_account)?;
+        let slot = super::clock::current_slot()?;
+        
+        // Insert commitment into state tree
+        pool_state.insert_state_commitment(commitment, &mut roots, slot)?;
+        
+        // Insert label into ASP tree
+        pool_state.insert_asp_label(label)?;
+    }
     
     // Update depositor state using zero-copy
     let depositor_state = DepositorStateZC::from_account_mut(depositor_account)?;
     depositor_state.set(depositor, label);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Value checks shared by pool and shard deposits
+pub(crate) fn check_deposit_value(value: u64) -> ProgramResult {
+    if value == 0 || value >= u128::MAX as u64 {
+        msg!("Invalid deposit value");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{clock, PrivacyPoolInstruction};
+    use crate::state::RootHistory;
+    use crate::utils::{process_test_instruction, TestAccount};
+
+    const PROGRAM_ID: Pubkey = [1u8; 32];
+
+    fn initialized_pool(authority: &mut TestAccount) -> TestAccount {
+        let mut pool = TestAccount::new([4u8; 32], PROGRAM_ID, PoolStateLeanIMT::space(8));
+        let initialize = PrivacyPoolInstruction::InitializePool {
+            entrypoint_authority: [2u8; 32],
+            max_tree_depth: 20,
+            asset_mint: [3u8; 32],
+            root_history_capacity: 8,
+            root_expiry_slots: 0,
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, authority], &initialize).unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_deposit_through_entrypoint() {
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        let mut depositor_account = TestAccount::new([5u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let mut depositor = TestAccount::signer([6u8; 32]);
+        clock::set_host_slot(42);
+
+        let deposit = PrivacyPoolInstruction::Deposit {
+            depositor: depositor.key,
+            value: 1_000,
+            precommitment_hash: [7u8; 32],
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut depositor_account, &mut depositor], &deposit)
+            .unwrap();
+
+        let state = unsafe { std::ptr::read_unaligned(pool.data.as_ptr() as *const PoolStateLeanIMT) };
+        let label = crate::crypto::poseidon::compute_label(&state.scope, 1);
+        let commitment = crate::crypto::poseidon::compute_commitment(1_000, &label, &[7u8; 32]);
+        let (state_size, nonce) = (state.state_tree.size, state.nonce);
+        assert_eq!((state_size, nonce), (1, 1));
+        assert_eq!(state.get_state_root(), commitment);
+        assert_eq!(state.get_asp_root(), label);
+
+        let history = RootHistory::from_bytes_mut(&mut pool.data[PoolStateLeanIMT::LEN..]).unwrap();
+        let latest = history.latest().unwrap();
+        let slot = latest.slot;
+        assert_eq!((latest.root, slot), (commitment, 42));
+        assert_eq!(&depositor_account.data[32..], &label);
+
+        // The signer has to be the depositor
+        depositor.is_signer = false;
+        let result =
+            process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut depositor_account, &mut depositor], &deposit);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn test_deposit_requires_attached_queue() {
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        let mut depositor_account = TestAccount::new([5u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let mut depositor = TestAccount::signer([6u8; 32]);
+        let mut queue = TestAccount::new([8u8; 32], PROGRAM_ID, DepositQueueZC::LEN);
+        let mut other_queue = TestAccount::new([9u8; 32], PROGRAM_ID, DepositQueueZC::LEN);
+        clock::set_host_slot(42);
+
+        let deposit = PrivacyPoolInstruction::Deposit {
+            depositor: depositor.key,
+            value: 1_000,
+            precommitment_hash: [7u8; 32],
+        };
+
+        // No queue attached yet, so one can't be passed
+        let result = process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut depositor_account, &mut depositor, &mut queue],
+            &deposit,
+        );
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        let initialize_queue = PrivacyPoolInstruction::InitializeQueue;
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut queue, &mut authority], &initialize_queue).unwrap();
+        let state = unsafe { std::ptr::read_unaligned(pool.data.as_ptr() as *const PoolStateLeanIMT) };
+        assert_eq!(state.deposit_queue, queue.key);
+
+        // Once attached, deposits can neither skip the queue nor use another one
+        let result =
+            process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut depositor_account, &mut depositor], &deposit);
+        assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+        let result = process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut depositor_account, &mut depositor, &mut other_queue],
+            &deposit,
+        );
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut depositor_account, &mut depositor, &mut queue],
+            &deposit,
+        )
+        .unwrap();
+        let pending = unsafe { std::ptr::read_unaligned(queue.data.as_ptr() as *const DepositQueueZC) }.pending();
+        assert_eq!(pending, 1);
+
+        // More than one insert batch worth of deposits, all drained by a single crank
+        let mut expected = crate::crypto::merkle_tree::LeanIMT::new(32);
+        for i in 0..20u8 {
+            if i > 0 {
+                let deposit = PrivacyPoolInstruction::Deposit {
+                    depositor: depositor.key,
+                    value: 1_000,
+                    precommitment_hash: [i; 32],
+                };
+                process_test_instruction(
+                    &PROGRAM_ID,
+                    &mut [&mut pool, &mut depositor_account, &mut depositor, &mut queue],
+                    &deposit,
+                )
+                .unwrap();
+            }
+            let queued = unsafe { std::ptr::read_unaligned(queue.data.as_ptr() as *const DepositQueueZC) };
+            expected.insert(queued.peek(i as u64).unwrap().commitment).unwrap();
+        }
+
+        // Winding down stops new deposits but the queue can still be drained
+        pool.data[std::mem::offset_of!(PoolStateLeanIMT, is_dead)] = 1;
+        let result = process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut depositor_account, &mut depositor, &mut queue],
+            &deposit,
+        );
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut queue],
+            &PrivacyPoolInstruction::ProcessQueue { max_batch: 20 },
+        )
+        .unwrap();
+        let pending = unsafe { std::ptr::read_unaligned(queue.data.as_ptr() as *const DepositQueueZC) }.pending();
+        assert_eq!(pending, 0);
+
+        let state = unsafe { std::ptr::read_unaligned(pool.data.as_ptr() as *const PoolStateLeanIMT) };
+        let state_size = state.state_tree.size;
+        assert_eq!(state_size, 20);
+        assert_eq!(state.get_state_root(), expected.root());
+        let history = RootHistory::from_bytes_mut(&mut pool.data[PoolStateLeanIMT::LEN..]).unwrap();
+        assert_eq!(history.latest().unwrap().root, expected.root());
+    }
+}
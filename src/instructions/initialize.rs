@@ -6,7 +6,7 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::state::PoolStateLeanIMT;
+use crate::state::{PoolStateLeanIMT, RootHistory};
 use solana_program::keccak;
 
 /// Initialize a new privacy pool using Lean IMT
@@ -16,6 +16,8 @@ pub fn initialize_pool(
     entrypoint_authority: Pubkey,
     max_tree_depth: u8,
     asset_mint: Pubkey,
+    root_history_capacity: u32,
+    root_expiry_slots: u64,
 ) -> ProgramResult {
     let pool_account = &accounts[0];
     let authority = &accounts[1];
@@ -31,6 +33,17 @@ pub fn initialize_pool(
         return Err(ProgramError::InvalidArgument);
     }
     
+    if root_history_capacity == 0 || root_history_capacity > crate::constants::MAX_ROOT_HISTORY_SIZE {
+        msg!("Invalid root history capacity");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // The account must be sized for the requested root history
+    if pool_account.data_len() != PoolStateLeanIMT::space(root_history_capacity) {
+        msg!("Pool account size does not match root history capacity");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
     // Get mutable reference to pool state using zero-copy
     let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
     
@@ -56,6 +69,12 @@ pub fn initialize_pool(
         withdrawal_verifier,
         scope,
     );
+    RootHistory::initialize_account_tail(
+        pool_account,
+        PoolStateLeanIMT::LEN,
+        root_history_capacity,
+        root_expiry_slots,
+    )?;
     
     msg!("Pool initialized with Lean IMT");
     Ok(())
@@ -5,11 +5,14 @@ use pinocchio::{
 };
 
 pub mod types;
+pub mod clock;
 pub mod initialize;
 pub mod deposit;
 pub mod withdraw;
 pub mod ragequit;
 pub mod wind_down;
+pub mod shard;
+pub mod queue;
 
 pub use types::*;
 
@@ -24,8 +27,18 @@ pub fn process_instruction(
             entrypoint_authority,
             max_tree_depth,
             asset_mint,
+            root_history_capacity,
+            root_expiry_slots,
         } => {
-            initialize::initialize_pool(program_id, accounts, entrypoint_authority, max_tree_depth, asset_mint)
+            initialize::initialize_pool(
+                program_id,
+                accounts,
+                entrypoint_authority,
+                max_tree_depth,
+                asset_mint,
+                root_history_capacity,
+                root_expiry_slots,
+            )
         }
         
         PrivacyPoolInstruction::Deposit {
@@ -52,5 +65,38 @@ pub fn process_instruction(
         PrivacyPoolInstruction::WindDown => {
             wind_down::wind_down(program_id, accounts)
         }
+        
+        PrivacyPoolInstruction::InitializeShard {
+            shard_index,
+        } => {
+            shard::initialize_shard(program_id, accounts, shard_index)
+        }
+        
+        PrivacyPoolInstruction::ShardDeposit {
+            shard_index,
+            depositor,
+            value,
+            precommitment_hash,
+        } => {
+            shard::shard_deposit(program_id, accounts, shard_index, depositor, value, precommitment_hash)
+        }
+        
+        PrivacyPoolInstruction::ShardWithdraw {
+            shard_index,
+            withdrawal_data,
+            proof_data,
+        } => {
+            shard::shard_withdraw(program_id, accounts, shard_index, withdrawal_data, proof_data)
+        }
+        
+        PrivacyPoolInstruction::InitializeQueue => {
+            queue::initialize_queue(program_id, accounts)
+        }
+        
+        PrivacyPoolInstruction::ProcessQueue {
+            max_batch,
+        } => {
+            queue::process_queue(program_id, accounts, max_batch)
+        }
     }
 }
\ No newline at end of file
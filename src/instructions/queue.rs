@@ -0,0 +1,129 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    log::sol_log_data,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+
+use crate::state::{PoolStateLeanIMT, DepositQueueZC};
+use crate::state::lean_imt::MAX_INSERT_BATCH;
+
+/// Event tag emitted for every queued deposit once it lands in the state tree
+pub const QUEUE_INSERT_EVENT: &[u8] = b"queue_insert";
+
+/// Attach a deposit queue to a pool
+pub fn initialize_queue(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    let pool_account = &accounts[0];
+    let queue_account = &accounts[1];
+    let authority = &accounts[2];
+    
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    
+    if pool_state.authority != authority.key().as_ref() {
+        msg!("Only pool authority can attach a deposit queue");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    if pool_state.has_deposit_queue() {
+        msg!("Pool already has a deposit queue");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    
+    let queue = DepositQueueZC::from_account_mut(queue_account)?;
+    
+    if queue.is_initialized != 0 {
+        msg!("Deposit queue already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    
+    queue.initialize(*pool_account.key());
+    pool_state.deposit_queue = *queue_account.key();
+    
+    msg!("Deposit queue initialized");
+    Ok(())
+}
+
+/// Insert up to `max_batch` queued deposits into the pool trees
+/// Permissionless: anyone can crank the queue, the outcome is fully determined by its contents.
+/// Still allowed once the pool is wound down, so deposits queued before that can be drained
+pub fn process_queue(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_batch: u16,
+) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    let pool_account = &accounts[0];
+    let queue_account = &accounts[1];
+    
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    
+    if queue_account.key() != &pool_state.deposit_queue {
+        msg!("Not the pool's deposit queue");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    let queue = DepositQueueZC::from_account_mut(queue_account)?;
+    queue.validate(pool_account.key())?;
+    
+    let batch_size = queue.pending().min(max_batch as u64);
+    if batch_size == 0 {
+        msg!("Deposit queue is empty");
+        return Ok(());
+    }
+    
+    // Fed to the trees in stack-sized chunks; the batch still gets a single root
+    let mut commitments = [[0u8; 32]; MAX_INSERT_BATCH];
+    let mut labels = [[0u8; 32]; MAX_INSERT_BATCH];
+    let mut new_root = pool_state.state_tree.root();
+    let mut processed = 0;
+    while processed < batch_size {
+        let chunk_size = (batch_size - processed).min(MAX_INSERT_BATCH as u64) as usize;
+        for (i, (commitment, label)) in commitments.iter_mut().zip(labels.iter_mut()).take(chunk_size).enumerate() {
+            let entry = queue.peek(processed + i as u64).ok_or(ProgramError::InvalidAccountData)?;
+            *commitment = entry.commitment;
+            *label = entry.label;
+        }
+        
+        let first_leaf_index = pool_state.state_tree.size;
+        new_root = pool_state.state_tree.insert_many(&commitments[..chunk_size])?;
+        pool_state.asp_tree.insert_many(&labels[..chunk_size])?;
+        
+        for (offset, commitment) in commitments[..chunk_size].iter().enumerate() {
+            let leaf_index = first_leaf_index + offset as u64;
+            sol_log_data(&[QUEUE_INSERT_EVENT, commitment, &leaf_index.to_le_bytes()]);
+        }
+        processed += chunk_size as u64;
+    }
+    
+    // A single root for the whole batch
+    let mut roots = PoolStateLeanIMT::root_history_mut(pool_account)?;
+    roots.push(new_root, super::clock::current_slot()?);
+    queue.advance(batch_size);
+    
+    msg!("Processed {} queued deposits", batch_size);
+    Ok(())
+}
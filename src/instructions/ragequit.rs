@@ -6,7 +6,7 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::state::zero_copy::{PrivacyPoolStateZC, DepositorStateZC, NullifierStateZC};
+use crate::state::{PoolStateLeanIMT, DepositorStateZC, NullifierStateZC};
 use super::types::RagequitProofData;
 
 /// Process a ragequit withdrawal using zero-copy accounts
@@ -15,6 +15,12 @@ pub fn ragequit(
     accounts: &[AccountInfo],
     proof_data: RagequitProofData,
 ) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    proof_data.check_public_signals()?;
+    
     let pool_account = &accounts[0];
     let depositor_account = &accounts[1];
     let ragequitter_account = &accounts[2];
@@ -24,12 +30,15 @@ pub fn ragequit(
         return Err(ProgramError::MissingRequiredSignature);
     }
     
-    // Get pool state using zero-copy (currently unused but will be needed for validation)
-    let _pool_state = PrivacyPoolStateZC::from_account(pool_account)?;
+    let pool_state = PoolStateLeanIMT::from_account(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
     
     // Verify depositor
     let depositor_state = DepositorStateZC::from_account_mut(depositor_account)?;
-    if &depositor_state.depositor != ragequitter_account.key().as_ref() {
+    if depositor_state.depositor != ragequitter_account.key().as_ref() {
         msg!("Not original depositor");
         return Err(ProgramError::InvalidArgument);
     }
@@ -47,6 +56,10 @@ pub fn ragequit(
     
     // Update nullifier state using zero-copy
     let nullifier_state = NullifierStateZC::from_account_mut(nullifier_account)?;
+    if nullifier_state.is_spent != 0 {
+        msg!("Nullifier already spent");
+        return Err(ProgramError::InvalidArgument);
+    }
     nullifier_state.set_spent(proof_data.nullifier_hash());
     
     msg!("Ragequit processed: {} tokens to {:?}", 
@@ -0,0 +1,295 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    log::sol_log_data,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+
+use crate::state::{PoolStateLeanIMT, StateShardZC, DepositorStateZC, NullifierStateZC, RootHistory};
+use super::types::{WithdrawalData, WithdrawProofData};
+
+/// Event tag emitted for every shard deposit, linking its label and commitment to the shard leaf index
+pub const SHARD_DEPOSIT_EVENT: &[u8] = b"shard_deposit";
+
+/// Create the next state tree shard for a pool
+pub fn initialize_shard(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    shard_index: u16,
+) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    let pool_account = &accounts[0];
+    let shard_account = &accounts[1];
+    let authority = &accounts[2];
+    
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    
+    if pool_state.authority != authority.key().as_ref() {
+        msg!("Only pool authority can add shards");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // Shards are created sequentially so the pool only needs to track a count
+    let shard_count = pool_state.shard_count;
+    if shard_index != shard_count || shard_count >= crate::constants::MAX_STATE_SHARDS {
+        msg!("Invalid shard index");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // Shards inherit the pool's root history settings
+    let pool_roots = PoolStateLeanIMT::root_history_mut(pool_account)?;
+    let root_history_capacity = pool_roots.capacity();
+    let root_expiry_slots = pool_roots.expiry_slots();
+    
+    if shard_account.data_len() != StateShardZC::space(root_history_capacity) {
+        msg!("Shard account size does not match root history capacity");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    let shard_state = StateShardZC::from_account_mut(shard_account)?;
+    
+    if shard_state.is_initialized != 0 {
+        msg!("Shard already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    
+    shard_state.initialize(*pool_account.key(), shard_index);
+    RootHistory::initialize_account_tail(
+        shard_account,
+        StateShardZC::LEN,
+        root_history_capacity,
+        root_expiry_slots,
+    )?;
+    pool_state.shard_count = shard_count + 1;
+    
+    msg!("Shard {} initialized", shard_index);
+    Ok(())
+}
+
+/// Deposit into a single state tree shard
+/// The commitment only goes into the shard's state tree; the label still goes into the pool's
+/// ASP tree, so shard notes prove against the same ASP root as pool notes
+pub fn shard_deposit(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    shard_index: u16,
+    depositor: Pubkey,
+    value: u64,
+    precommitment_hash: [u8; 32],
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    let pool_account = &accounts[0];
+    let shard_account = &accounts[1];
+    let depositor_account = &accounts[2];
+    let depositor_signer = &accounts[3];
+    
+    if !depositor_signer.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    if depositor_signer.key() != &depositor {
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_dead != 0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    super::deposit::check_deposit_value(value)?;
+    
+    let shard_state = StateShardZC::from_account_mut(shard_account)?;
+    shard_state.validate(pool_account.key(), shard_index)?;
+    
+    let nonce = shard_state.increment_nonce();
+    let label = crate::crypto::poseidon::compute_shard_label(&pool_state.scope, shard_index, nonce);
+    let commitment = crate::crypto::poseidon::compute_commitment(value, &label, &precommitment_hash);
+    
+    let leaf_index = shard_state.state_tree.size;
+    let mut roots = StateShardZC::root_history_mut(shard_account)?;
+    shard_state.insert_state_commitment(commitment, &mut roots, super::clock::current_slot()?)?;
+    pool_state.insert_asp_label(label)?;
+    
+    let depositor_state = DepositorStateZC::from_account_mut(depositor_account)?;
+    depositor_state.set(depositor, label);
+    
+    sol_log_data(&[
+        SHARD_DEPOSIT_EVENT,
+        &shard_index.to_le_bytes(),
+        &label,
+        &commitment,
+        &leaf_index.to_le_bytes(),
+    ]);
+    msg!("Shard deposit: shard {} value {}", shard_index, value);
+    Ok(())
+}
+
+/// Process a private withdrawal proving against a shard's root history
+/// Like `withdraw`, this stays open after the pool is wound down so notes can still leave
+pub fn shard_withdraw(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    shard_index: u16,
+    withdrawal_data: WithdrawalData,
+    proof_data: WithdrawProofData,
+) -> ProgramResult {
+    if accounts.len() < 4 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    proof_data.check_public_signals()?;
+    
+    let pool_account = &accounts[0];
+    let shard_account = &accounts[1];
+    let processooor_account = &accounts[2];
+    let nullifier_account = &accounts[3];
+    
+    if processooor_account.key() != &withdrawal_data.processooor {
+        msg!("Invalid processooor");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    if !processooor_account.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    let pool_state = PoolStateLeanIMT::from_account(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    
+    let shard_state = StateShardZC::from_account_mut(shard_account)?;
+    shard_state.validate(pool_account.key(), shard_index)?;
+    
+    let expected_context = crate::crypto::poseidon::compute_context(&withdrawal_data, &pool_state.scope);
+    if expected_context != proof_data.context() {
+        msg!("Context mismatch");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    if proof_data.state_tree_depth() as usize > crate::state::lean_imt::MAX_TREE_DEPTH ||
+       proof_data.asp_tree_depth() as usize > crate::state::lean_imt::MAX_TREE_DEPTH {
+        msg!("Invalid tree depth");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    let mut roots = StateShardZC::root_history_mut(shard_account)?;
+    let slot = super::clock::current_slot()?;
+    
+    if !roots.is_known_root(&proof_data.state_root(), slot) {
+        msg!("Unknown state root for shard");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // Shard labels live in the pool's ASP tree
+    if proof_data.asp_root() != pool_state.get_asp_root() {
+        msg!("Unknown ASP root");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    if !crate::crypto::verifying_key::verify_withdraw_proof(&proof_data) {
+        msg!("Invalid withdrawal proof");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    let nullifier_state = NullifierStateZC::from_account_mut(nullifier_account)?;
+    if nullifier_state.is_spent != 0 {
+        msg!("Nullifier already spent");
+        return Err(ProgramError::InvalidArgument);
+    }
+    nullifier_state.set_spent(proof_data.existing_nullifier_hash());
+    
+    // The change commitment stays in the shard the note was spent from
+    shard_state.insert_state_commitment(proof_data.new_commitment_hash(), &mut roots, slot)?;
+    
+    msg!("Shard withdrawal processed: {} tokens from shard {}",
+         proof_data.withdrawn_value(),
+         shard_index);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{clock, PrivacyPoolInstruction};
+    use crate::utils::{process_test_instruction, TestAccount};
+
+    const PROGRAM_ID: Pubkey = [1u8; 32];
+
+    #[test]
+    fn test_shard_deposit_checks_value_and_fills_pool_asp_tree() {
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = TestAccount::new([4u8; 32], PROGRAM_ID, PoolStateLeanIMT::space(8));
+        let mut shard = TestAccount::new([5u8; 32], PROGRAM_ID, StateShardZC::space(8));
+        let initialize = PrivacyPoolInstruction::InitializePool {
+            entrypoint_authority: [3u8; 32],
+            max_tree_depth: 20,
+            asset_mint: [3u8; 32],
+            root_history_capacity: 8,
+            root_expiry_slots: 0,
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut authority], &initialize).unwrap();
+        let initialize_shard = PrivacyPoolInstruction::InitializeShard { shard_index: 0 };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut shard, &mut authority], &initialize_shard).unwrap();
+        clock::set_host_slot(42);
+
+        // Same value checks as pool deposits
+        let mut depositor_account = TestAccount::new([6u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let mut depositor = TestAccount::signer([7u8; 32]);
+        for value in [0, u64::MAX] {
+            let deposit = PrivacyPoolInstruction::ShardDeposit {
+                shard_index: 0,
+                depositor: depositor.key,
+                value,
+                precommitment_hash: [8u8; 32],
+            };
+            let result = process_test_instruction(
+                &PROGRAM_ID,
+                &mut [&mut pool, &mut shard, &mut depositor_account, &mut depositor],
+                &deposit,
+            );
+            assert_eq!(result, Err(ProgramError::InvalidArgument));
+        }
+
+        let deposit = PrivacyPoolInstruction::ShardDeposit {
+            shard_index: 0,
+            depositor: depositor.key,
+            value: 1_000,
+            precommitment_hash: [8u8; 32],
+        };
+        process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut shard, &mut depositor_account, &mut depositor],
+            &deposit,
+        )
+        .unwrap();
+
+        // The commitment stays in the shard, the label goes into the pool's ASP tree
+        let pool_state = unsafe { std::ptr::read_unaligned(pool.data.as_ptr() as *const PoolStateLeanIMT) };
+        let shard_state = unsafe { std::ptr::read_unaligned(shard.data.as_ptr() as *const StateShardZC) };
+        let label = crate::crypto::poseidon::compute_shard_label(&pool_state.scope, 0, 1);
+        let commitment = crate::crypto::poseidon::compute_commitment(1_000, &label, &[8u8; 32]);
+        let pool_size = pool_state.state_tree.size;
+        assert_eq!(pool_size, 0);
+        assert_eq!(shard_state.state_tree.root(), commitment);
+        assert_eq!(pool_state.get_asp_root(), label);
+    }
+}
@@ -3,7 +3,7 @@ use pinocchio::{
     pubkey::Pubkey,
 };
 
-use crate::BorshDeserialize;
+use crate::{BorshDeserialize, BorshSerialize};
 
 #[derive(Debug)]
 pub enum PrivacyPoolInstruction {
@@ -11,6 +11,8 @@ pub enum PrivacyPoolInstruction {
         entrypoint_authority: Pubkey,
         max_tree_depth: u8,
         asset_mint: Pubkey,
+        root_history_capacity: u32,
+        root_expiry_slots: u64,
     },
     Deposit {
         depositor: Pubkey,
@@ -25,6 +27,24 @@ pub enum PrivacyPoolInstruction {
         proof_data: RagequitProofData,
     },
     WindDown,
+    InitializeShard {
+        shard_index: u16,
+    },
+    ShardDeposit {
+        shard_index: u16,
+        depositor: Pubkey,
+        value: u64,
+        precommitment_hash: [u8; 32],
+    },
+    ShardWithdraw {
+        shard_index: u16,
+        withdrawal_data: WithdrawalData,
+        proof_data: WithdrawProofData,
+    },
+    InitializeQueue,
+    ProcessQueue {
+        max_batch: u16,
+    },
 }
 
 #[derive(Debug)]
@@ -49,41 +69,64 @@ pub struct RagequitProofData {
     pub public_signals: Vec<[u8; 32]>,
 }
 
+/// Public signals follow the withdraw circuit's snarkjs output order:
+/// newCommitmentHash, existingNullifierHash, withdrawnValue, stateRoot, stateTreeDepth,
+/// ASPRoot, ASPTreeDepth, context; each one a little-endian field element
 impl WithdrawProofData {
-    pub fn withdrawn_value(&self) -> u64 {
-        u64::from_le_bytes(self.public_signals[0][..8].try_into().unwrap_or([0u8; 8]))
+    pub const PUBLIC_SIGNALS: usize = 8;
+
+    /// Reject proofs without the circuit's public signals before the accessors index into them
+    pub fn check_public_signals(&self) -> Result<(), ProgramError> {
+        if self.public_signals.len() != Self::PUBLIC_SIGNALS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(())
+    }
+
+    pub fn new_commitment_hash(&self) -> [u8; 32] {
+        self.public_signals[0]
     }
     
-    pub fn state_root(&self) -> [u8; 32] {
+    pub fn existing_nullifier_hash(&self) -> [u8; 32] {
         self.public_signals[1]
     }
     
-    pub fn state_tree_depth(&self) -> u8 {
-        self.public_signals[2][0]
+    pub fn withdrawn_value(&self) -> u64 {
+        u64::from_le_bytes(self.public_signals[2][..8].try_into().unwrap_or([0u8; 8]))
     }
     
-    pub fn asp_root(&self) -> [u8; 32] {
+    pub fn state_root(&self) -> [u8; 32] {
         self.public_signals[3]
     }
     
-    pub fn asp_tree_depth(&self) -> u8 {
+    pub fn state_tree_depth(&self) -> u8 {
         self.public_signals[4][0]
     }
     
-    pub fn context(&self) -> [u8; 32] {
+    pub fn asp_root(&self) -> [u8; 32] {
         self.public_signals[5]
     }
     
-    pub fn new_commitment_hash(&self) -> [u8; 32] {
-        self.public_signals[6]
+    pub fn asp_tree_depth(&self) -> u8 {
+        self.public_signals[6][0]
     }
     
-    pub fn existing_nullifier_hash(&self) -> [u8; 32] {
+    pub fn context(&self) -> [u8; 32] {
         self.public_signals[7]
     }
 }
 
 impl RagequitProofData {
+    pub const PUBLIC_SIGNALS: usize = 4;
+
+    /// Reject proofs without the circuit's public signals before the accessors index into them
+    pub fn check_public_signals(&self) -> Result<(), ProgramError> {
+        if self.public_signals.len() != Self::PUBLIC_SIGNALS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(())
+    }
+
     pub fn value(&self) -> u64 {
         u64::from_le_bytes(self.public_signals[0][..8].try_into().unwrap_or([0u8; 8]))
     }
@@ -124,11 +167,29 @@ impl BorshDeserialize for PrivacyPoolInstruction {
                     <[u8; 32]>::try_from(&data[offset..offset + 32])
                         .map_err(|_| ProgramError::InvalidInstructionData)?
                 );
+                offset += 32;
+                
+                // Root history settings are optional trailing fields; older clients get the defaults
+                let mut root_history_capacity = crate::constants::ROOT_HISTORY_SIZE as u32;
+                let mut root_expiry_slots = 0u64;
+                if data.len() >= offset + 4 + 8 {
+                    root_history_capacity = u32::from_le_bytes(
+                        <[u8; 4]>::try_from(&data[offset..offset + 4])
+                            .map_err(|_| ProgramError::InvalidInstructionData)?
+                    );
+                    offset += 4;
+                    root_expiry_slots = u64::from_le_bytes(
+                        <[u8; 8]>::try_from(&data[offset..offset + 8])
+                            .map_err(|_| ProgramError::InvalidInstructionData)?
+                    );
+                }
                 
                 Ok(PrivacyPoolInstruction::InitializePool {
                     entrypoint_authority,
                     max_tree_depth,
                     asset_mint,
+                    root_history_capacity,
+                    root_expiry_slots,
                 })
             }
             1 => {
@@ -161,93 +222,7 @@ impl BorshDeserialize for PrivacyPoolInstruction {
                     return Err(ProgramError::InvalidInstructionData);
                 }
                 
-                let mut offset = 1;
-                
-                // Parse processooor pubkey (32 bytes)
-                if data.len() < offset + 32 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let processooor = Pubkey::from(
-                    <[u8; 32]>::try_from(&data[offset..offset + 32])
-                        .map_err(|_| ProgramError::InvalidInstructionData)?
-                );
-                offset += 32;
-                
-                // Parse withdrawal data length (4 bytes)
-                if data.len() < offset + 4 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let data_len = u32::from_le_bytes(
-                    <[u8; 4]>::try_from(&data[offset..offset + 4])
-                        .map_err(|_| ProgramError::InvalidInstructionData)?
-                ) as usize;
-                offset += 4;
-                
-                // Parse withdrawal data
-                if data.len() < offset + data_len {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let withdrawal_data_bytes = data[offset..offset + data_len].to_vec();
-                offset += data_len;
-                
-                let withdrawal_data = WithdrawalData {
-                    processooor,
-                    data: withdrawal_data_bytes,
-                };
-                
-                // Parse proof data
-                // proof_a: 64 bytes
-                if data.len() < offset + 64 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let proof_a = <[u8; 64]>::try_from(&data[offset..offset + 64])
-                    .map_err(|_| ProgramError::InvalidInstructionData)?;
-                offset += 64;
-                
-                // proof_b: 128 bytes
-                if data.len() < offset + 128 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let proof_b = <[u8; 128]>::try_from(&data[offset..offset + 128])
-                    .map_err(|_| ProgramError::InvalidInstructionData)?;
-                offset += 128;
-                
-                // proof_c: 64 bytes
-                if data.len() < offset + 64 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let proof_c = <[u8; 64]>::try_from(&data[offset..offset + 64])
-                    .map_err(|_| ProgramError::InvalidInstructionData)?;
-                offset += 64;
-                
-                // Parse public signals count (4 bytes)
-                if data.len() < offset + 4 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let signals_count = u32::from_le_bytes(
-                    <[u8; 4]>::try_from(&data[offset..offset + 4])
-                        .map_err(|_| ProgramError::InvalidInstructionData)?
-                ) as usize;
-                offset += 4;
-                
-                // Parse public signals (32 bytes each)
-                let mut public_signals = Vec::new();
-                for _ in 0..signals_count {
-                    if data.len() < offset + 32 {
-                        return Err(ProgramError::InvalidInstructionData);
-                    }
-                    let signal = <[u8; 32]>::try_from(&data[offset..offset + 32])
-                        .map_err(|_| ProgramError::InvalidInstructionData)?;
-                    public_signals.push(signal);
-                    offset += 32;
-                }
-                
-                let proof_data = WithdrawProofData {
-                    proof_a,
-                    proof_b,
-                    proof_c,
-                    public_signals,
-                };
+                let (withdrawal_data, proof_data) = parse_withdraw_payload(data, 1)?;
                 
                 Ok(PrivacyPoolInstruction::Withdraw {
                     withdrawal_data,
@@ -324,7 +299,293 @@ impl BorshDeserialize for PrivacyPoolInstruction {
                 // WindDown instruction - no additional data needed
                 Ok(PrivacyPoolInstruction::WindDown)
             }
+            5 => {
+                if data.len() < 1 + 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let shard_index = u16::from_le_bytes([data[1], data[2]]);
+                
+                Ok(PrivacyPoolInstruction::InitializeShard {
+                    shard_index,
+                })
+            }
+            6 => {
+                if data.len() < 1 + 2 + 32 + 8 + 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let mut offset = 1;
+                let shard_index = u16::from_le_bytes([data[offset], data[offset + 1]]);
+                offset += 2;
+                let depositor = Pubkey::from(
+                    <[u8; 32]>::try_from(&data[offset..offset + 32])
+                        .map_err(|_| ProgramError::InvalidInstructionData)?
+                );
+                offset += 32;
+                let value = u64::from_le_bytes(
+                    <[u8; 8]>::try_from(&data[offset..offset + 8])
+                        .map_err(|_| ProgramError::InvalidInstructionData)?
+                );
+                offset += 8;
+                let precommitment_hash = <[u8; 32]>::try_from(&data[offset..offset + 32])
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                
+                Ok(PrivacyPoolInstruction::ShardDeposit {
+                    shard_index,
+                    depositor,
+                    value,
+                    precommitment_hash,
+                })
+            }
+            7 => {
+                // Withdraw against a shard: shard index (2 bytes) followed by the regular withdraw payload
+                if data.len() < 1 + 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let shard_index = u16::from_le_bytes([data[1], data[2]]);
+                let (withdrawal_data, proof_data) = parse_withdraw_payload(data, 3)?;
+                
+                Ok(PrivacyPoolInstruction::ShardWithdraw {
+                    shard_index,
+                    withdrawal_data,
+                    proof_data,
+                })
+            }
+            8 => {
+                // InitializeQueue instruction - no additional data needed
+                Ok(PrivacyPoolInstruction::InitializeQueue)
+            }
+            9 => {
+                if data.len() < 1 + 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let max_batch = u16::from_le_bytes([data[1], data[2]]);
+                
+                Ok(PrivacyPoolInstruction::ProcessQueue {
+                    max_batch,
+                })
+            }
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
-}
\ No newline at end of file
+}
+
+impl BorshSerialize for PrivacyPoolInstruction {
+    /// Encode the instruction in the format `try_from_slice` parses
+    fn try_to_vec(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut data = Vec::new();
+        
+        match self {
+            PrivacyPoolInstruction::InitializePool {
+                entrypoint_authority,
+                max_tree_depth,
+                asset_mint,
+                root_history_capacity,
+                root_expiry_slots,
+            } => {
+                data.push(0);
+                data.extend_from_slice(entrypoint_authority.as_ref());
+                data.push(*max_tree_depth);
+                data.extend_from_slice(asset_mint.as_ref());
+                data.extend_from_slice(&root_history_capacity.to_le_bytes());
+                data.extend_from_slice(&root_expiry_slots.to_le_bytes());
+            }
+            PrivacyPoolInstruction::Deposit {
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                data.push(1);
+                data.extend_from_slice(depositor.as_ref());
+                data.extend_from_slice(&value.to_le_bytes());
+                data.extend_from_slice(precommitment_hash);
+            }
+            PrivacyPoolInstruction::Withdraw {
+                withdrawal_data,
+                proof_data,
+            } => {
+                data.push(2);
+                write_withdraw_payload(&mut data, withdrawal_data, proof_data)?;
+            }
+            PrivacyPoolInstruction::Ragequit { proof_data } => {
+                data.push(3);
+                write_proof(
+                    &mut data,
+                    &proof_data.proof_a,
+                    &proof_data.proof_b,
+                    &proof_data.proof_c,
+                    &proof_data.public_signals,
+                )?;
+            }
+            PrivacyPoolInstruction::WindDown => {
+                data.push(4);
+            }
+            PrivacyPoolInstruction::InitializeShard { shard_index } => {
+                data.push(5);
+                data.extend_from_slice(&shard_index.to_le_bytes());
+            }
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index,
+                depositor,
+                value,
+                precommitment_hash,
+            } => {
+                data.push(6);
+                data.extend_from_slice(&shard_index.to_le_bytes());
+                data.extend_from_slice(depositor.as_ref());
+                data.extend_from_slice(&value.to_le_bytes());
+                data.extend_from_slice(precommitment_hash);
+            }
+            PrivacyPoolInstruction::ShardWithdraw {
+                shard_index,
+                withdrawal_data,
+                proof_data,
+            } => {
+                data.push(7);
+                data.extend_from_slice(&shard_index.to_le_bytes());
+                write_withdraw_payload(&mut data, withdrawal_data, proof_data)?;
+            }
+            PrivacyPoolInstruction::InitializeQueue => {
+                data.push(8);
+            }
+            PrivacyPoolInstruction::ProcessQueue { max_batch } => {
+                data.push(9);
+                data.extend_from_slice(&max_batch.to_le_bytes());
+            }
+        }
+        
+        Ok(data)
+    }
+}
+
+/// Inverse of `parse_withdraw_payload`
+fn write_withdraw_payload(
+    data: &mut Vec<u8>,
+    withdrawal_data: &WithdrawalData,
+    proof_data: &WithdrawProofData,
+) -> Result<(), ProgramError> {
+    let data_len = u32::try_from(withdrawal_data.data.len())
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    data.extend_from_slice(withdrawal_data.processooor.as_ref());
+    data.extend_from_slice(&data_len.to_le_bytes());
+    data.extend_from_slice(&withdrawal_data.data);
+    write_proof(
+        data,
+        &proof_data.proof_a,
+        &proof_data.proof_b,
+        &proof_data.proof_c,
+        &proof_data.public_signals,
+    )
+}
+
+fn write_proof(
+    data: &mut Vec<u8>,
+    proof_a: &[u8; 64],
+    proof_b: &[u8; 128],
+    proof_c: &[u8; 64],
+    public_signals: &[[u8; 32]],
+) -> Result<(), ProgramError> {
+    let signals_count = u32::try_from(public_signals.len())
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    data.extend_from_slice(proof_a);
+    data.extend_from_slice(proof_b);
+    data.extend_from_slice(proof_c);
+    data.extend_from_slice(&signals_count.to_le_bytes());
+    for signal in public_signals {
+        data.extend_from_slice(signal);
+    }
+    Ok(())
+}
+
+/// Parse the withdrawal data and proof that follow the discriminant (and any prefix) of a withdraw-style instruction
+fn parse_withdraw_payload(
+    data: &[u8],
+    mut offset: usize,
+) -> Result<(WithdrawalData, WithdrawProofData), ProgramError> {
+    // Parse processooor pubkey (32 bytes)
+    if data.len() < offset + 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let processooor = Pubkey::from(
+        <[u8; 32]>::try_from(&data[offset..offset + 32])
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+    offset += 32;
+    
+    // Parse withdrawal data length (4 bytes)
+    if data.len() < offset + 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let data_len = u32::from_le_bytes(
+        <[u8; 4]>::try_from(&data[offset..offset + 4])
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    ) as usize;
+    offset += 4;
+    
+    // Parse withdrawal data
+    if data.len() < offset + data_len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let withdrawal_data_bytes = data[offset..offset + data_len].to_vec();
+    offset += data_len;
+    
+    let withdrawal_data = WithdrawalData {
+        processooor,
+        data: withdrawal_data_bytes,
+    };
+    
+    // Parse proof data
+    // proof_a: 64 bytes
+    if data.len() < offset + 64 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let proof_a = <[u8; 64]>::try_from(&data[offset..offset + 64])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    offset += 64;
+    
+    // proof_b: 128 bytes
+    if data.len() < offset + 128 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let proof_b = <[u8; 128]>::try_from(&data[offset..offset + 128])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    offset += 128;
+    
+    // proof_c: 64 bytes
+    if data.len() < offset + 64 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let proof_c = <[u8; 64]>::try_from(&data[offset..offset + 64])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    offset += 64;
+    
+    // Parse public signals count (4 bytes)
+    if data.len() < offset + 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let signals_count = u32::from_le_bytes(
+        <[u8; 4]>::try_from(&data[offset..offset + 4])
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    ) as usize;
+    offset += 4;
+    
+    // Parse public signals (32 bytes each)
+    let mut public_signals = Vec::new();
+    for _ in 0..signals_count {
+        if data.len() < offset + 32 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let signal = <[u8; 32]>::try_from(&data[offset..offset + 32])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        public_signals.push(signal);
+        offset += 32;
+    }
+    
+    let proof_data = WithdrawProofData {
+        proof_a,
+        proof_b,
+        proof_c,
+        public_signals,
+    };
+    
+    Ok((withdrawal_data, proof_data))
+}
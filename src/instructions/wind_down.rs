@@ -6,13 +6,17 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::state::zero_copy::PrivacyPoolStateZC;
+use crate::state::PoolStateLeanIMT;
 
 /// Wind down the pool (disable deposits) using zero-copy accounts
 pub fn wind_down(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
+    if accounts.len() < 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
     let pool_account = &accounts[0];
     let entrypoint_account = &accounts[1];
     
@@ -21,20 +25,70 @@ pub fn wind_down(
     }
     
     // Get mutable reference to pool state using zero-copy
-    let pool_state = PrivacyPoolStateZC::from_account_mut(pool_account)?;
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
     
-    if pool_state.get_entrypoint_authority() != *entrypoint_account.key() {
+    if &pool_state.entrypoint != entrypoint_account.key() {
         msg!("Only entrypoint can wind down pool");
         return Err(ProgramError::InvalidArgument);
     }
     
-    if pool_state.is_dead() {
+    if pool_state.is_dead != 0 {
         msg!("Pool already dead");
         return Err(ProgramError::InvalidAccountData);
     }
     
-    pool_state.set_dead(true);
+    pool_state.is_dead = 1;
     
     msg!("Pool wound down");
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::PrivacyPoolInstruction;
+    use crate::state::DepositorStateZC;
+    use crate::utils::{process_test_instruction, TestAccount};
+
+    const PROGRAM_ID: Pubkey = [1u8; 32];
+
+    #[test]
+    fn test_wind_down_stops_deposits() {
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut entrypoint = TestAccount::signer([3u8; 32]);
+        let mut pool = TestAccount::new([4u8; 32], PROGRAM_ID, PoolStateLeanIMT::space(8));
+        let initialize = PrivacyPoolInstruction::InitializePool {
+            entrypoint_authority: entrypoint.key,
+            max_tree_depth: 20,
+            asset_mint: [5u8; 32],
+            root_history_capacity: 8,
+            root_expiry_slots: 0,
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut authority], &initialize).unwrap();
+
+        // Only the entrypoint may wind the pool down
+        let wind_down = PrivacyPoolInstruction::WindDown;
+        let result = process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut authority], &wind_down);
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut entrypoint], &wind_down).unwrap();
+        assert_eq!(pool.data[std::mem::offset_of!(PoolStateLeanIMT, is_dead)], 1);
+
+        let result = process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut entrypoint], &wind_down);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let mut depositor_account = TestAccount::new([6u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let mut depositor = TestAccount::signer([7u8; 32]);
+        let deposit = PrivacyPoolInstruction::Deposit {
+            depositor: depositor.key,
+            value: 1_000,
+            precommitment_hash: [8u8; 32],
+        };
+        let result =
+            process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut depositor_account, &mut depositor], &deposit);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
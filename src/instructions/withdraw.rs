@@ -6,7 +6,7 @@ use pinocchio::{
     ProgramResult,
 };
 
-use crate::state::zero_copy::{PrivacyPoolStateZC, NullifierStateZC};
+use crate::state::{PoolStateLeanIMT, NullifierStateZC};
 use super::types::{WithdrawalData, WithdrawProofData};
 
 /// Process a private withdrawal using zero-copy accounts
@@ -16,6 +16,12 @@ pub fn withdraw(
     withdrawal_data: WithdrawalData,
     proof_data: WithdrawProofData,
 ) -> ProgramResult {
+    if accounts.len() < 3 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    
+    proof_data.check_public_signals()?;
+    
     let pool_account = &accounts[0];
     let processooor_account = &accounts[1];
     let nullifier_account = &accounts[2];
@@ -30,7 +36,11 @@ pub fn withdraw(
     }
     
     // Get mutable reference to pool state using zero-copy
-    let pool_state = PrivacyPoolStateZC::from_account_mut(pool_account)?;
+    let pool_state = PoolStateLeanIMT::from_account_mut(pool_account)?;
+    
+    if pool_state.is_initialized == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
     
     let expected_context = crate::crypto::poseidon::compute_context(&withdrawal_data, &pool_state.scope);
     if expected_context != proof_data.context() {
@@ -38,17 +48,25 @@ pub fn withdraw(
         return Err(ProgramError::InvalidArgument);
     }
     
-    if proof_data.state_tree_depth() > pool_state.max_tree_depth || 
-       proof_data.asp_tree_depth() > pool_state.max_tree_depth {
+    if proof_data.state_tree_depth() as usize > crate::state::lean_imt::MAX_TREE_DEPTH ||
+       proof_data.asp_tree_depth() as usize > crate::state::lean_imt::MAX_TREE_DEPTH {
         msg!("Invalid tree depth");
         return Err(ProgramError::InvalidArgument);
     }
     
-    if !pool_state.is_known_root(&proof_data.state_root()) {
+    let mut roots = PoolStateLeanIMT::root_history_mut(pool_account)?;
+    let slot = super::clock::current_slot()?;
+    
+    if !roots.is_known_root(&proof_data.state_root(), slot) {
         msg!("Unknown state root");
         return Err(ProgramError::InvalidArgument);
     }
     
+    if proof_data.asp_root() != pool_state.get_asp_root() {
+        msg!("Unknown ASP root");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
     if !crate::crypto::verifying_key::verify_withdraw_proof(&proof_data) {
         msg!("Invalid withdrawal proof");
         return Err(ProgramError::InvalidArgument);
@@ -56,11 +74,14 @@ pub fn withdraw(
     
     // Update nullifier state using zero-copy
     let nullifier_state = NullifierStateZC::from_account_mut(nullifier_account)?;
+    if nullifier_state.is_spent != 0 {
+        msg!("Nullifier already spent");
+        return Err(ProgramError::InvalidArgument);
+    }
     nullifier_state.set_spent(proof_data.existing_nullifier_hash());
     
-    // Update merkle tree in-place
-    pool_state.merkle_tree.insert(proof_data.new_commitment_hash())?;
-    pool_state.add_root(pool_state.merkle_tree.root);
+    // Insert the change commitment and record the new root
+    pool_state.insert_state_commitment(proof_data.new_commitment_hash(), &mut roots, slot)?;
     
     msg!("Withdrawal processed: {} tokens to {:?}", 
          proof_data.withdrawn_value(), 
@@ -10,6 +10,10 @@ pub mod state;
 pub mod instructions;
 pub mod crypto;
 
+// Host-side helpers, never compiled into the on-chain program
+#[cfg(feature = "client")]
+pub mod client;
+
 // Utils module is test-only
 #[cfg(any(test, feature = "test-utils"))]
 pub mod utils;
@@ -50,4 +54,6 @@ pub mod constants {
     ];
     pub const MAX_TREE_DEPTH: u8 = 32;
     pub const ROOT_HISTORY_SIZE: usize = 64;
+    pub const MAX_ROOT_HISTORY_SIZE: u32 = 8192;
+    pub const MAX_STATE_SHARDS: u16 = 16;
 }
\ No newline at end of file
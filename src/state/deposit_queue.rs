@@ -0,0 +1,147 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Maximum number of pending deposits a queue can hold
+pub const DEPOSIT_QUEUE_CAPACITY: usize = 256;
+
+/// A deposit waiting to be inserted into the pool trees
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct QueuedDeposit {
+    pub commitment: [u8; 32],
+    pub label: [u8; 32],
+}
+
+/// Optional deposit queue attached to a pool
+/// Deposits only append here; `ProcessQueue` later inserts a batch into the trees
+/// and pushes a single root, so the Poseidon hashing is paid once per batch instead of per deposit
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct DepositQueueZC {
+    pub is_initialized: u8,
+    pub _padding1: [u8; 7],
+    /// Pool account this queue feeds
+    pub pool: [u8; 32],
+    /// Total number of deposits ever processed (ring buffer read position)
+    pub head: u64,
+    /// Total number of deposits ever enqueued (ring buffer write position)
+    pub tail: u64,
+    pub entries: [QueuedDeposit; DEPOSIT_QUEUE_CAPACITY],
+}
+
+impl DepositQueueZC {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn from_account_mut(account: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        if account.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let data_ptr = account.try_borrow_mut_data()?.as_mut_ptr();
+        unsafe {
+            let state = &mut *(data_ptr as *mut Self);
+            Ok(state)
+        }
+    }
+
+    pub fn initialize(&mut self, pool: Pubkey) {
+        self.is_initialized = 1;
+        self.pool.copy_from_slice(pool.as_ref());
+        self.head = 0;
+        self.tail = 0;
+    }
+
+    /// Check that this queue is initialized and belongs to the given pool
+    pub fn validate(&self, pool: &Pubkey) -> Result<(), ProgramError> {
+        if self.is_initialized == 0 {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        if self.pool != pool.as_ref() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
+    /// Number of deposits waiting to be processed
+    pub fn pending(&self) -> u64 {
+        self.tail - self.head
+    }
+
+    /// Append a deposit, returning its position in the queue
+    pub fn push(&mut self, commitment: [u8; 32], label: [u8; 32]) -> Result<u64, ProgramError> {
+        if self.pending() >= DEPOSIT_QUEUE_CAPACITY as u64 {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        let position = self.tail;
+        self.entries[(position % DEPOSIT_QUEUE_CAPACITY as u64) as usize] = QueuedDeposit {
+            commitment,
+            label,
+        };
+        self.tail = position + 1;
+        Ok(position)
+    }
+
+    /// Look at the deposit `offset` entries after the head without removing it
+    pub fn peek(&self, offset: u64) -> Option<QueuedDeposit> {
+        if offset >= self.pending() {
+            return None;
+        }
+
+        let position = self.head + offset;
+        Some(self.entries[(position % DEPOSIT_QUEUE_CAPACITY as u64) as usize])
+    }
+
+    /// Mark the first `count` pending deposits as processed
+    pub fn advance(&mut self, count: u64) {
+        self.head += count.min(self.pending());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_push_and_advance() {
+        let mut buffer = vec![0u8; DepositQueueZC::LEN];
+        let queue = unsafe { &mut *(buffer.as_mut_ptr() as *mut DepositQueueZC) };
+        queue.initialize(Pubkey::from([1u8; 32]));
+
+        assert_eq!(queue.push([1u8; 32], [11u8; 32]).unwrap(), 0);
+        assert_eq!(queue.push([2u8; 32], [12u8; 32]).unwrap(), 1);
+        assert_eq!(queue.pending(), 2);
+
+        let first = queue.peek(0).unwrap();
+        assert_eq!(first.commitment, [1u8; 32]);
+        assert_eq!(first.label, [11u8; 32]);
+        assert!(queue.peek(2).is_none());
+
+        queue.advance(1);
+        assert_eq!(queue.pending(), 1);
+        assert_eq!(queue.peek(0).unwrap().commitment, [2u8; 32]);
+    }
+
+    #[test]
+    fn test_queue_wraps_and_rejects_when_full() {
+        let mut buffer = vec![0u8; DepositQueueZC::LEN];
+        let queue = unsafe { &mut *(buffer.as_mut_ptr() as *mut DepositQueueZC) };
+        queue.initialize(Pubkey::from([1u8; 32]));
+
+        for i in 0..DEPOSIT_QUEUE_CAPACITY {
+            queue.push([i as u8; 32], [0u8; 32]).unwrap();
+        }
+        assert!(queue.push([0xffu8; 32], [0u8; 32]).is_err());
+
+        // Freeing one slot lets the write position wrap around
+        queue.advance(1);
+        assert_eq!(queue.push([0xffu8; 32], [0u8; 32]).unwrap(), DEPOSIT_QUEUE_CAPACITY as u64);
+        assert_eq!(queue.peek(DEPOSIT_QUEUE_CAPACITY as u64 - 1).unwrap().commitment, [0xffu8; 32]);
+    }
+}
@@ -4,9 +4,13 @@ use pinocchio::{
     pubkey::Pubkey,
 };
 
+use crate::state::root_history::RootHistory;
+
 // Constants matching the Solidity implementation
 pub const MAX_TREE_DEPTH: usize = 32;
-pub const ROOT_HISTORY_SIZE: usize = 64;
+
+/// Most leaves `insert_many` takes at once; batches are hashed in a stack buffer of this size
+pub const MAX_INSERT_BATCH: usize = 16;
 
 /// Lean Incremental Merkle Tree implementation matching the Solidity version
 /// This is a zero-copy structure that fits in a Solana account
@@ -113,6 +117,104 @@ impl LeanIMTStateZC {
         Ok(node)
     }
     
+    /// Insert several leaves at once (Solidity `_insertMany`)
+    /// Each level is hashed once for the whole batch instead of once per leaf,
+    /// and the resulting root is identical to inserting the leaves one by one.
+    /// Batches are capped at `MAX_INSERT_BATCH` so the levels can be hashed in place without allocating
+    pub fn insert_many(&mut self, leaves: &[[u8; 32]]) -> Result<[u8; 32], ProgramError> {
+        if leaves.is_empty() {
+            return Ok(self.root());
+        }
+        
+        if leaves.len() > MAX_INSERT_BATCH {
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        for (i, leaf) in leaves.iter().enumerate() {
+            if self.has_leaf(leaf) || leaves[..i].contains(leaf) {
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+        
+        let tree_size = self.size;
+        let new_size = tree_size + leaves.len() as u64;
+        
+        let mut tree_depth = self.depth as usize;
+        while (1u64 << tree_depth) < new_size {
+            tree_depth += 1;
+        }
+        if tree_depth > MAX_TREE_DEPTH {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.depth = tree_depth as u32;
+        
+        // New nodes of the current level; each level overwrites the front of the buffer,
+        // which is safe because parent `i` only reads children at `2i - 1` or later
+        let mut nodes = [[0u8; 32]; MAX_INSERT_BATCH];
+        nodes[..leaves.len()].copy_from_slice(leaves);
+        let mut current_level_len = leaves.len();
+        let mut current_level_start_index = tree_size;
+        let mut current_level_size = new_size;
+        let mut next_level_start_index = current_level_start_index >> 1;
+        let mut next_level_size = ((current_level_size - 1) >> 1) + 1;
+        
+        for level in 0..tree_depth {
+            let number_of_new_nodes = (next_level_size - next_level_start_index) as usize;
+            
+            // Keep the last left node of this level for future insertions; it is stored
+            // after hashing because the first parent may still need the old side node
+            let side_node = if current_level_size & 1 == 1 {
+                Some(nodes[current_level_len - 1])
+            } else if current_level_len > 1 {
+                Some(nodes[current_level_len - 2])
+            } else {
+                None
+            };
+            
+            for i in 0..number_of_new_nodes {
+                let left_position = (i as u64 + next_level_start_index) * 2;
+                
+                // The left node is either already in the tree (saved as a side node) or new
+                let left_node = if left_position < current_level_start_index {
+                    self.side_nodes[level]
+                } else {
+                    nodes[(left_position - current_level_start_index) as usize]
+                };
+                
+                nodes[i] = if left_position + 1 < current_level_size {
+                    let right_node = nodes[(left_position + 1 - current_level_start_index) as usize];
+                    crate::crypto::poseidon::hash_two(&left_node, &right_node)
+                } else {
+                    left_node
+                };
+            }
+            
+            if let Some(side_node) = side_node {
+                self.side_nodes[level] = side_node;
+            }
+            
+            current_level_len = number_of_new_nodes;
+            current_level_start_index = next_level_start_index;
+            next_level_start_index >>= 1;
+            current_level_size = next_level_size;
+            next_level_size = ((next_level_size - 1) >> 1) + 1;
+        }
+        
+        self.size = new_size;
+        let root = nodes[0];
+        self.side_nodes[tree_depth] = root;
+        
+        // Track the leaves (simplified - in production would need better approach)
+        for leaf in leaves {
+            if self.leaf_count < 1024 {
+                self.leaf_indices[self.leaf_count as usize] = *leaf;
+                self.leaf_count += 1;
+            }
+        }
+        
+        Ok(root)
+    }
+    
     /// Get the current root
     pub fn root(&self) -> [u8; 32] {
         self.side_nodes[self.depth as usize]
@@ -140,6 +242,8 @@ impl LeanIMTStateZC {
 }
 
 /// Pool state using Lean IMT
+/// The account data is this fixed-size state followed by a `RootHistory` region
+/// whose capacity is chosen when the pool is initialized
 #[repr(C, packed)]
 #[derive(Clone, Copy)]
 pub struct PoolStateLeanIMT {
@@ -153,11 +257,11 @@ pub struct PoolStateLeanIMT {
     pub scope: [u8; 32],
     pub nonce: u64,
     pub is_dead: u8,
-    pub _padding2: [u8; 7],
-    
-    /// Root history (circular buffer)
-    pub roots: [[u8; 32]; ROOT_HISTORY_SIZE],
-    pub current_root_index: u64,
+    /// Number of state tree shards created for this pool
+    pub shard_count: u16,
+    pub _padding2: [u8; 5],
+    /// Deposit queue attached by `InitializeQueue` (all zeros if none); deposits must go through it
+    pub deposit_queue: [u8; 32],
     
     /// Lean IMT for state tree
     pub state_tree: LeanIMTStateZC,
@@ -170,7 +274,7 @@ impl PoolStateLeanIMT {
     pub const LEN: usize = std::mem::size_of::<Self>();
     
     pub fn from_account_mut<'a>(account: &'a AccountInfo) -> Result<&'a mut Self, ProgramError> {
-        if account.data_len() != Self::LEN {
+        if account.data_len() < Self::LEN + RootHistory::space(1) {
             return Err(ProgramError::InvalidAccountData);
         }
         
@@ -181,6 +285,28 @@ impl PoolStateLeanIMT {
         }
     }
     
+    pub fn from_account<'a>(account: &'a AccountInfo) -> Result<&'a Self, ProgramError> {
+        if account.data_len() < Self::LEN + RootHistory::space(1) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        
+        let data_ptr = account.try_borrow_data()?.as_ptr();
+        unsafe {
+            let state = &*(data_ptr as *const Self);
+            Ok(state)
+        }
+    }
+    
+    /// Root history stored after the pool state
+    pub fn root_history_mut<'a>(account: &'a AccountInfo) -> Result<RootHistory<'a>, ProgramError> {
+        RootHistory::from_account_tail(account, Self::LEN)
+    }
+    
+    /// Account size needed for a pool keeping `root_history_capacity` roots
+    pub const fn space(root_history_capacity: u32) -> usize {
+        Self::LEN + RootHistory::space(root_history_capacity)
+    }
+    
     pub fn initialize(
         &mut self,
         authority: Pubkey,
@@ -197,24 +323,25 @@ impl PoolStateLeanIMT {
         self.scope = scope;
         self.nonce = 0;
         self.is_dead = 0;
-        
-        // Initialize root history
-        for i in 0..ROOT_HISTORY_SIZE {
-            self.roots[i] = [0u8; 32];
-        }
-        self.current_root_index = 0;
+        self.shard_count = 0;
+        self.deposit_queue = [0u8; 32];
         
         // Initialize trees
         self.state_tree.initialize();
         self.asp_tree.initialize();
     }
     
-    pub fn insert_state_commitment(&mut self, commitment: [u8; 32]) -> Result<(), ProgramError> {
+    pub fn insert_state_commitment(
+        &mut self,
+        commitment: [u8; 32],
+        roots: &mut RootHistory,
+        slot: u64,
+    ) -> Result<(), ProgramError> {
         // Insert into state tree
         let new_root = self.state_tree.insert(commitment)?;
         
         // Add to root history
-        self.add_root(new_root);
+        roots.push(new_root, slot);
         
         Ok(())
     }
@@ -225,16 +352,6 @@ impl PoolStateLeanIMT {
         Ok(())
     }
     
-    pub fn add_root(&mut self, root: [u8; 32]) {
-        let index = (self.current_root_index as usize) % ROOT_HISTORY_SIZE;
-        self.roots[index] = root;
-        self.current_root_index = ((self.current_root_index + 1) as usize % ROOT_HISTORY_SIZE) as u64;
-    }
-    
-    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
-        self.roots.iter().any(|r| r == root)
-    }
-    
     pub fn get_state_root(&self) -> [u8; 32] {
         self.state_tree.root()
     }
@@ -251,6 +368,11 @@ impl PoolStateLeanIMT {
         self.asp_tree.depth
     }
     
+    /// Whether deposits have to be queued instead of inserted directly
+    pub fn has_deposit_queue(&self) -> bool {
+        self.deposit_queue != [0u8; 32]
+    }
+    
     pub fn increment_nonce(&mut self) -> u64 {
         self.nonce += 1;
         self.nonce
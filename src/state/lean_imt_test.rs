@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use crate::state::lean_imt::LeanIMTStateZC;
+    use crate::state::lean_imt::{LeanIMTStateZC, MAX_INSERT_BATCH};
     use crate::crypto::poseidon;
+    use crate::crypto::merkle_tree::LeanIMT;
     
     #[test]
     fn test_lean_imt_insertion() {
@@ -150,4 +151,66 @@ mod tests {
         let final_root = state.root();
         println!("Final root after 5 insertions: {:?}", final_root);
     }
-}
\ No newline at end of file
+    
+    fn test_leaf(i: u64) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[..8].copy_from_slice(&(i + 1).to_le_bytes());
+        leaf
+    }
+    
+    #[test]
+    fn test_lean_imt_insert_many_matches_insert() {
+        // Batches of different shapes, including ones that cross power-of-two boundaries
+        let batches: [&[u64]; 5] = [&[0], &[1, 2], &[3, 4, 5, 6, 7], &[8], &[9, 10, 11, 12, 13, 14, 15, 16]];
+        
+        let mut single_buffer = vec![0u8; LeanIMTStateZC::LEN];
+        let single = unsafe { &mut *(single_buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+        single.initialize();
+        
+        let mut batch_buffer = vec![0u8; LeanIMTStateZC::LEN];
+        let batched = unsafe { &mut *(batch_buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+        batched.initialize();
+        
+        let mut off_chain = LeanIMT::new(0);
+        
+        for batch in batches.iter() {
+            let leaves: Vec<[u8; 32]> = batch.iter().map(|i| test_leaf(*i)).collect();
+            for leaf in &leaves {
+                single.insert(*leaf).unwrap();
+            }
+            let batch_root = batched.insert_many(&leaves).unwrap();
+            off_chain.insert_many(&leaves).unwrap();
+            
+            assert_eq!(batch_root, single.root());
+            assert_eq!(batched.root(), off_chain.root());
+            let (batched_size, single_size) = (batched.size, single.size);
+            let (batched_depth, single_depth) = (batched.depth, single.depth);
+            assert_eq!(batched_size, single_size);
+            assert_eq!(batched_depth, single_depth);
+            assert_eq!(batched_depth as u8, off_chain.depth());
+        }
+        
+        // Side nodes must match too, otherwise later single inserts would diverge
+        single.insert(test_leaf(100)).unwrap();
+        batched.insert(test_leaf(100)).unwrap();
+        assert_eq!(batched.root(), single.root());
+    }
+    
+    #[test]
+    fn test_lean_imt_insert_many_rejects_duplicates() {
+        let mut buffer = vec![0u8; LeanIMTStateZC::LEN];
+        let state = unsafe { &mut *(buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+        state.initialize();
+        
+        state.insert(test_leaf(0)).unwrap();
+        assert!(state.insert_many(&[test_leaf(1), test_leaf(0)]).is_err());
+        assert!(state.insert_many(&[test_leaf(1), test_leaf(1)]).is_err());
+        
+        // Larger batches have to be split by the caller
+        let oversized: Vec<[u8; 32]> = (1..=MAX_INSERT_BATCH as u64 + 1).map(test_leaf).collect();
+        assert!(state.insert_many(&oversized).is_err());
+        
+        let size = state.size;
+        assert_eq!(size, 1);
+    }
+}
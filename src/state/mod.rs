@@ -1,8 +1,14 @@
 pub mod zero_copy;
 pub mod lean_imt;
+pub mod root_history;
+pub mod shard;
+pub mod deposit_queue;
 
 // Export the Lean IMT implementation as the primary one
 pub use lean_imt::*;
+pub use root_history::{RootHistory, RootHistoryEntry, RootHistoryHeader};
+pub use shard::StateShardZC;
+pub use deposit_queue::{DepositQueueZC, QueuedDeposit, DEPOSIT_QUEUE_CAPACITY};
 
 // Keep zero_copy for backwards compatibility during migration
 pub use zero_copy::{NullifierStateZC, DepositorStateZC};
@@ -0,0 +1,229 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+};
+
+/// Bookkeeping for a root history buffer stored at the tail of an account
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct RootHistoryHeader {
+    /// Number of entries in the circular buffer, chosen at initialization
+    pub capacity: u32,
+    pub _padding: u32,
+    /// Roots older than this many slots are rejected (0 = only the capacity limits validity)
+    pub expiry_slots: u64,
+    /// Index of the next entry to overwrite
+    pub current_index: u64,
+    /// Total number of roots ever pushed
+    pub count: u64,
+}
+
+/// A root together with the slot it was created in
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct RootHistoryEntry {
+    pub root: [u8; 32],
+    pub slot: u64,
+}
+
+/// Zero-copy view over a variable-length root history region:
+/// a `RootHistoryHeader` followed by `capacity` `RootHistoryEntry`s
+pub struct RootHistory<'a> {
+    pub header: &'a mut RootHistoryHeader,
+    pub entries: &'a mut [RootHistoryEntry],
+}
+
+impl RootHistoryHeader {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+impl RootHistoryEntry {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+}
+
+impl<'a> RootHistory<'a> {
+    /// Bytes needed to store a history of `capacity` roots
+    pub const fn space(capacity: u32) -> usize {
+        RootHistoryHeader::LEN + capacity as usize * RootHistoryEntry::LEN
+    }
+
+    /// Map the root history stored after the first `offset` bytes of an account
+    pub fn from_account_tail(account: &'a AccountInfo, offset: usize) -> Result<Self, ProgramError> {
+        Self::from_bytes_mut(Self::account_tail(account, offset)?)
+    }
+
+    /// Set up an empty root history after the first `offset` bytes of an account
+    pub fn initialize_account_tail(
+        account: &'a AccountInfo,
+        offset: usize,
+        capacity: u32,
+        expiry_slots: u64,
+    ) -> Result<Self, ProgramError> {
+        Self::initialize(Self::account_tail(account, offset)?, capacity, expiry_slots)
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn account_tail(account: &'a AccountInfo, offset: usize) -> Result<&'a mut [u8], ProgramError> {
+        let data_len = account.data_len();
+        if data_len < offset {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let data_ptr = account.try_borrow_mut_data()?.as_mut_ptr();
+        unsafe {
+            Ok(std::slice::from_raw_parts_mut(data_ptr.add(offset), data_len - offset))
+        }
+    }
+
+    /// Map an already initialized root history region
+    pub fn from_bytes_mut(data: &'a mut [u8]) -> Result<Self, ProgramError> {
+        if data.len() < RootHistoryHeader::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let header = unsafe { &*(data.as_ptr() as *const RootHistoryHeader) };
+        let capacity = header.capacity;
+        Self::map(data, capacity)
+    }
+
+    /// Set up an empty root history in `data`, which must be exactly `space(capacity)` bytes
+    pub fn initialize(data: &'a mut [u8], capacity: u32, expiry_slots: u64) -> Result<Self, ProgramError> {
+        if capacity == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let history = Self::map(data, capacity)?;
+        history.header.capacity = capacity;
+        history.header._padding = 0;
+        history.header.expiry_slots = expiry_slots;
+        history.header.current_index = 0;
+        history.header.count = 0;
+        for entry in history.entries.iter_mut() {
+            entry.root = [0u8; 32];
+            entry.slot = 0;
+        }
+        Ok(history)
+    }
+
+    fn map(data: &'a mut [u8], capacity: u32) -> Result<Self, ProgramError> {
+        if capacity == 0 || data.len() != Self::space(capacity) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (header_bytes, entry_bytes) = data.split_at_mut(RootHistoryHeader::LEN);
+        unsafe {
+            let header = &mut *(header_bytes.as_mut_ptr() as *mut RootHistoryHeader);
+            let entries = std::slice::from_raw_parts_mut(
+                entry_bytes.as_mut_ptr() as *mut RootHistoryEntry,
+                capacity as usize,
+            );
+            Ok(Self { header, entries })
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.header.capacity
+    }
+
+    pub fn expiry_slots(&self) -> u64 {
+        self.header.expiry_slots
+    }
+
+    /// Record a new root created in `slot`
+    pub fn push(&mut self, root: [u8; 32], slot: u64) {
+        let capacity = self.header.capacity as u64;
+        let index = self.header.current_index % capacity;
+        self.entries[index as usize] = RootHistoryEntry { root, slot };
+        self.header.current_index = (index + 1) % capacity;
+        self.header.count += 1;
+    }
+
+    /// The most recently pushed root, if any
+    pub fn latest(&self) -> Option<RootHistoryEntry> {
+        if self.header.count == 0 {
+            return None;
+        }
+
+        let capacity = self.header.capacity as u64;
+        let index = (self.header.current_index + capacity - 1) % capacity;
+        Some(self.entries[index as usize])
+    }
+
+    /// Check whether `root` is still acceptable at `current_slot`
+    /// The latest root is always accepted; older roots must be in the buffer and,
+    /// if an expiry window is configured, younger than `expiry_slots`
+    pub fn is_known_root(&self, root: &[u8; 32], current_slot: u64) -> bool {
+        if *root == [0u8; 32] {
+            return false;
+        }
+
+        if let Some(latest) = self.latest() {
+            if latest.root == *root {
+                return true;
+            }
+        }
+
+        let expiry_slots = self.header.expiry_slots;
+        self.entries.iter().any(|entry| {
+            let entry_slot = entry.slot;
+            entry.root == *root
+                && (expiry_slots == 0 || current_slot.saturating_sub(entry_slot) <= expiry_slots)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_history_capacity() {
+        let mut data = vec![0u8; RootHistory::space(4)];
+        let mut history = RootHistory::initialize(&mut data, 4, 0).unwrap();
+
+        for i in 1..=5u8 {
+            history.push([i; 32], i as u64);
+        }
+
+        // The first root was overwritten, the rest are known regardless of age
+        assert!(!history.is_known_root(&[1u8; 32], 1_000_000));
+        for i in 2..=5u8 {
+            assert!(history.is_known_root(&[i; 32], 1_000_000));
+        }
+        assert_eq!(history.latest().unwrap().root, [5u8; 32]);
+        assert!(!history.is_known_root(&[0u8; 32], 0));
+    }
+
+    #[test]
+    fn test_root_history_slot_expiry() {
+        let mut data = vec![0u8; RootHistory::space(16)];
+        let mut history = RootHistory::initialize(&mut data, 16, 100).unwrap();
+
+        history.push([1u8; 32], 1_000);
+        history.push([2u8; 32], 1_050);
+
+        assert!(history.is_known_root(&[1u8; 32], 1_100));
+        assert!(!history.is_known_root(&[1u8; 32], 1_101));
+
+        // The latest root never expires, even when no deposits land for a long time
+        assert!(history.is_known_root(&[2u8; 32], 1_000_000));
+    }
+
+    #[test]
+    fn test_root_history_remap() {
+        let mut data = vec![0u8; RootHistory::space(8)];
+        {
+            let mut history = RootHistory::initialize(&mut data, 8, 10).unwrap();
+            history.push([9u8; 32], 5);
+        }
+
+        let history = RootHistory::from_bytes_mut(&mut data).unwrap();
+        assert_eq!(history.capacity(), 8);
+        assert_eq!(history.expiry_slots(), 10);
+        assert!(history.is_known_root(&[9u8; 32], 6));
+
+        let mut short = vec![0u8; RootHistory::space(8) - 1];
+        short[0] = 8;
+        assert!(RootHistory::from_bytes_mut(&mut short).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::state::lean_imt::LeanIMTStateZC;
+use crate::state::root_history::RootHistory;
+
+/// A state tree shard owned by a pool
+/// Each shard lives in its own account so deposits routed to different shards
+/// don't write-lock the same account and can be executed in parallel.
+/// Like the pool, the account data is followed by a `RootHistory` region.
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+pub struct StateShardZC {
+    pub is_initialized: u8,
+    pub _padding1: [u8; 5],
+    /// Position of this shard within the pool (0..pool.shard_count)
+    pub shard_index: u16,
+    /// Pool account this shard belongs to
+    pub pool: [u8; 32],
+    /// Per-shard deposit nonce, used to derive labels without touching the pool
+    pub nonce: u64,
+
+    /// Lean IMT holding this shard's commitments
+    pub state_tree: LeanIMTStateZC,
+}
+
+impl StateShardZC {
+    pub const LEN: usize = std::mem::size_of::<Self>();
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn from_account_mut(account: &AccountInfo) -> Result<&mut Self, ProgramError> {
+        if account.data_len() < Self::LEN + RootHistory::space(1) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let data_ptr = account.try_borrow_mut_data()?.as_mut_ptr();
+        unsafe {
+            let state = &mut *(data_ptr as *mut Self);
+            Ok(state)
+        }
+    }
+
+    /// Root history stored after the shard state
+    pub fn root_history_mut<'a>(account: &'a AccountInfo) -> Result<RootHistory<'a>, ProgramError> {
+        RootHistory::from_account_tail(account, Self::LEN)
+    }
+
+    /// Account size needed for a shard keeping `root_history_capacity` roots
+    pub const fn space(root_history_capacity: u32) -> usize {
+        Self::LEN + RootHistory::space(root_history_capacity)
+    }
+
+    pub fn initialize(&mut self, pool: Pubkey, shard_index: u16) {
+        self.is_initialized = 1;
+        self.shard_index = shard_index;
+        self.pool.copy_from_slice(pool.as_ref());
+        self.nonce = 0;
+        self.state_tree.initialize();
+    }
+
+    /// Check that this shard is initialized and belongs to the given pool at the given index
+    pub fn validate(&self, pool: &Pubkey, shard_index: u16) -> Result<(), ProgramError> {
+        if self.is_initialized == 0 {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        let stored_index = self.shard_index;
+        if self.pool != pool.as_ref() || stored_index != shard_index {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
+    pub fn insert_state_commitment(
+        &mut self,
+        commitment: [u8; 32],
+        roots: &mut RootHistory,
+        slot: u64,
+    ) -> Result<(), ProgramError> {
+        let new_root = self.state_tree.insert(commitment)?;
+        roots.push(new_root, slot);
+        Ok(())
+    }
+
+    pub fn get_state_root(&self) -> [u8; 32] {
+        self.state_tree.root()
+    }
+
+    pub fn increment_nonce(&mut self) -> u64 {
+        self.nonce += 1;
+        self.nonce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_shard(buffer: &mut [u8], pool: Pubkey, shard_index: u16) -> (&mut StateShardZC, RootHistory<'_>) {
+        let (state_bytes, history_bytes) = buffer.split_at_mut(StateShardZC::LEN);
+        let shard = unsafe { &mut *(state_bytes.as_mut_ptr() as *mut StateShardZC) };
+        shard.initialize(pool, shard_index);
+        let roots = RootHistory::initialize(history_bytes, 8, 0).unwrap();
+        (shard, roots)
+    }
+
+    #[test]
+    fn test_shards_keep_independent_root_history() {
+        let pool = Pubkey::from([7u8; 32]);
+        let mut buffer0 = vec![0u8; StateShardZC::space(8)];
+        let mut buffer1 = vec![0u8; StateShardZC::space(8)];
+        let (shard0, mut roots0) = new_shard(&mut buffer0, pool, 0);
+        let (shard1, mut roots1) = new_shard(&mut buffer1, pool, 1);
+
+        shard0.insert_state_commitment([1u8; 32], &mut roots0, 10).unwrap();
+        shard1.insert_state_commitment([2u8; 32], &mut roots1, 10).unwrap();
+
+        let root0 = shard0.get_state_root();
+        let root1 = shard1.get_state_root();
+        assert!(roots0.is_known_root(&root0, 10));
+        assert!(!roots0.is_known_root(&root1, 10));
+        assert!(roots1.is_known_root(&root1, 10));
+        assert!(!roots1.is_known_root(&root0, 10));
+    }
+
+    #[test]
+    fn test_shard_validation() {
+        let pool = Pubkey::from([7u8; 32]);
+        let other_pool = Pubkey::from([8u8; 32]);
+        let mut buffer = vec![0u8; StateShardZC::space(8)];
+        let (shard, _) = new_shard(&mut buffer, pool, 3);
+
+        assert!(shard.validate(&pool, 3).is_ok());
+        assert_eq!(shard.validate(&pool, 2), Err(ProgramError::InvalidArgument));
+        assert_eq!(shard.validate(&other_pool, 3), Err(ProgramError::InvalidArgument));
+
+        let mut empty = vec![0u8; StateShardZC::LEN];
+        let uninitialized = unsafe { &*(empty.as_mut_ptr() as *const StateShardZC) };
+        assert_eq!(uninitialized.validate(&pool, 0), Err(ProgramError::UninitializedAccount));
+    }
+}
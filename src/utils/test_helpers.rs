@@ -25,8 +25,16 @@ pub struct TestAccount {
     pub owner: Pubkey,
     pub executable: bool,
     pub rent_epoch: u64,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    /// The account as the runtime lays it out, while an `AccountInfo` points at it
+    raw: Vec<u64>,
 }
 
+/// Size of the header pinocchio maps before the account data
+/// (borrow state, flags, original length, key, owner, lamports, data length)
+const ACCOUNT_HEADER_LEN: usize = 88;
+
 impl TestAccount {
     pub fn new(key: Pubkey, owner: Pubkey, data_len: usize) -> Self {
         Self {
@@ -36,16 +44,72 @@ impl TestAccount {
             owner,
             executable: false,
             rent_epoch: 0,
+            is_signer: false,
+            is_writable: true,
+            raw: Vec::new(),
         }
     }
     
+    pub fn signer(key: Pubkey) -> Self {
+        let mut account = Self::new(key, Pubkey::default(), 0);
+        account.is_signer = true;
+        account
+    }
+    
+    /// Lay the account out the way the entrypoint hands it to the program and return an
+    /// `AccountInfo` over that copy; call `sync` afterwards to read back what the program wrote
     pub fn to_account_info(&mut self) -> AccountInfo {
-        // For now, we'll just panic - this needs proper AccountInfo construction
-        // TODO: Fix this once we resolve the pinocchio API compatibility
-        panic!("AccountInfo construction needs to be implemented for current pinocchio version")
+        let mut raw = vec![0u64; (ACCOUNT_HEADER_LEN + self.data.len()).div_ceil(8)];
+        {
+            let bytes = raw_bytes_mut(&mut raw);
+            // borrow_state (0 = not borrowed), is_signer, is_writable, executable
+            bytes[1] = self.is_signer as u8;
+            bytes[2] = self.is_writable as u8;
+            bytes[3] = self.executable as u8;
+            bytes[4..8].copy_from_slice(&(self.data.len() as u32).to_le_bytes());
+            bytes[8..40].copy_from_slice(self.key.as_ref());
+            bytes[40..72].copy_from_slice(self.owner.as_ref());
+            bytes[72..80].copy_from_slice(&self.lamports.to_le_bytes());
+            bytes[80..88].copy_from_slice(&(self.data.len() as u64).to_le_bytes());
+            bytes[ACCOUNT_HEADER_LEN..ACCOUNT_HEADER_LEN + self.data.len()].copy_from_slice(&self.data);
+        }
+        self.raw = raw;
+        
+        // `AccountInfo` is a single pointer to that layout
+        unsafe { std::mem::transmute::<*mut u64, AccountInfo>(self.raw.as_mut_ptr()) }
+    }
+    
+    /// Copy lamports and data back from the layout the last `to_account_info` handed out
+    pub fn sync(&mut self) {
+        if self.raw.is_empty() {
+            return;
+        }
+        let bytes = raw_bytes_mut(&mut self.raw);
+        self.lamports = u64::from_le_bytes(bytes[72..80].try_into().unwrap());
+        let data_len = u64::from_le_bytes(bytes[80..88].try_into().unwrap()) as usize;
+        self.data = bytes[ACCOUNT_HEADER_LEN..ACCOUNT_HEADER_LEN + data_len].to_vec();
     }
 }
 
+fn raw_bytes_mut(raw: &mut [u64]) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(raw.as_mut_ptr() as *mut u8, raw.len() * 8) }
+}
+
+/// Run an instruction through the program entrypoint and write the changes back to `accounts`
+pub fn process_test_instruction(
+    program_id: &Pubkey,
+    accounts: &mut [&mut TestAccount],
+    instruction: &PrivacyPoolInstruction,
+) -> ProgramResult {
+    let data = instruction.try_to_vec()?;
+    let infos: Vec<AccountInfo> = accounts.iter_mut().map(|account| account.to_account_info()).collect();
+    let result = crate::process_instruction(program_id, &infos, &data);
+    for account in accounts.iter_mut() {
+        account.sync();
+    }
+    result
+}
+
 impl TestContext {
     pub fn new() -> Self {
         let program_id = Pubkey::from([1u8; 32]);
@@ -139,14 +203,14 @@ pub fn create_test_withdraw_proof_data() -> WithdrawProofData {
         proof_b: [2u8; 128],
         proof_c: [3u8; 64],
         public_signals: vec![
+            [203u8; 32],      // new_commitment_hash
+            [204u8; 32],      // existing_nullifier_hash
             value_bytes,      // withdrawn_value = 100
             [200u8; 32],      // state_root
             depth_bytes,      // state_tree_depth = 1
             [201u8; 32],      // asp_root
             asp_depth_bytes,  // asp_tree_depth = 2
             [202u8; 32],      // context
-            [203u8; 32],      // new_commitment_hash
-            [204u8; 32],      // existing_nullifier_hash
         ],
     }
 }
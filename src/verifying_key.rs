@@ -1,46 +0,0 @@
-use groth16_solana::groth16::Groth16Verifyingkey;
-
-pub const VERIFYINGKEY: Groth16Verifyingkey =  Groth16Verifyingkey {
-	nr_pubinputs: 3,
-
-	vk_alpha_g1: [
-		45,77,154,167,227,2,217,223,65,116,157,85,7,148,157,5,219,234,51,251,177,108,100,59,34,245,153,162,190,109,242,226,
-		20,190,221,80,60,55,206,176,97,216,236,96,32,159,227,69,206,137,131,10,25,35,3,1,240,118,202,255,0,77,25,38,
-	],
-
-	vk_beta_g2: [
-		9,103,3,47,203,247,118,209,175,201,133,248,136,119,241,130,211,132,128,166,83,242,222,202,169,121,76,188,59,243,6,12,
-		14,24,120,71,173,76,121,131,116,208,214,115,43,245,1,132,125,214,139,192,224,113,36,30,2,19,188,127,193,61,183,171,
-		48,76,251,209,224,138,112,74,153,245,232,71,217,63,140,60,170,253,222,196,107,122,13,55,157,166,154,77,17,35,70,167,
-		23,57,193,177,164,87,168,199,49,49,35,210,77,47,145,146,248,150,183,198,62,234,5,169,213,127,6,84,122,208,206,200,
-	],
-
-	vk_gamme_g2: [
-		25,142,147,147,146,13,72,58,114,96,191,183,49,251,93,37,241,170,73,51,53,169,231,18,151,228,133,183,174,243,18,194,
-		24,0,222,239,18,31,30,118,66,106,0,102,94,92,68,121,103,67,34,212,247,94,218,221,70,222,189,92,217,146,246,237,
-		9,6,137,208,88,95,240,117,236,158,153,173,105,12,51,149,188,75,49,51,112,179,142,243,85,172,218,220,209,34,151,91,
-		18,200,94,165,219,140,109,235,74,171,113,128,141,203,64,143,227,209,231,105,12,67,211,123,76,230,204,1,102,250,125,170,
-	],
-
-	vk_delta_g2: [
-		2,22,91,223,71,248,240,172,6,62,172,28,133,216,179,129,181,25,111,11,57,82,81,109,56,72,186,30,182,36,252,172,
-		16,188,186,19,232,49,180,91,224,153,186,120,140,160,121,128,156,46,13,124,12,17,228,46,139,147,114,126,97,18,233,205,
-		5,116,130,154,213,34,173,13,195,168,13,208,99,115,128,227,234,227,28,250,155,141,137,158,36,167,22,12,87,11,127,16,
-		24,199,249,177,115,220,133,106,242,130,193,33,58,87,111,126,173,101,11,117,195,134,89,118,136,33,252,107,14,120,127,182,
-	],
-
-	vk_ic: &[
-		[
-			34,147,50,44,150,72,54,224,102,236,227,150,31,81,64,173,190,62,4,129,207,67,62,34,125,100,87,35,20,132,228,226,
-			45,33,167,251,135,207,14,202,86,212,63,87,188,126,133,142,215,167,90,23,219,220,180,159,253,125,5,207,81,103,112,238,
-		],
-		[
-			12,10,168,37,217,181,198,78,161,229,11,1,197,217,155,215,42,241,224,3,195,198,49,42,219,69,39,178,173,203,51,41,
-			13,101,185,87,137,163,128,198,114,75,88,199,70,211,193,195,47,203,96,26,3,1,150,17,83,73,200,29,148,30,181,181,
-		],
-		[
-			1,253,63,89,100,129,5,93,12,255,43,108,54,6,83,150,205,79,54,14,195,106,81,44,30,213,145,47,206,72,250,164,
-			47,169,72,189,65,238,99,196,168,80,58,121,146,223,44,194,19,123,179,30,202,230,81,78,227,142,237,91,120,80,223,187,
-		],
-	]
-};
\ No newline at end of file
@@ -0,0 +1,225 @@
+//! Replays `fixtures/indexer/pool-history.jsonl`, a pool history in `getTransaction` format:
+//! two direct deposits, two queued deposits and their `ProcessQueue` (with its `queue_insert`
+//! logs), a failed deposit, a withdrawal, a ragequit, a shard deposit and a deposit into
+//! another pool.
+//!
+//! The fixture is synthetic, not recorded from a validator: keys are keccak hashes of names,
+//! signatures, blockhashes and compute units are made up, and proofs are zeroed since the
+//! indexer never verifies them. Hashes, instruction data and `Program data:` logs follow the
+//! program, with withdraw signals in snarkjs order.
+#![cfg(feature = "client")]
+
+use serde_json::Value;
+use solana_program::keccak;
+
+use solana_privacy_pools::{
+    client::indexer::{
+        http,
+        source::{FileSource, RecordSource, SourceError, TransactionRecord},
+        store::MemoryStore,
+        IndexError, Indexer,
+    },
+    client::scanner::{PoolTransaction, StateTree},
+    client::snarkjs,
+    crypto::{field, merkle_tree::LeanIMT, poseidon},
+    instructions::{PrivacyPoolInstruction, WithdrawalData},
+    BorshSerialize,
+};
+
+const FIXTURE: &str = include_str!("fixtures/indexer/pool-history.jsonl");
+const REALISTIC_PROOF: &str = include_str!("../scripts/realistic-withdraw-proof.json");
+
+fn key(name: &str) -> [u8; 32] {
+    keccak::hash(name.as_bytes()).to_bytes()
+}
+
+fn precommitment(i: u8) -> [u8; 32] {
+    poseidon::compute_precommitment(&[i; 32], &[i + 100; 32])
+}
+
+fn scope() -> [u8; 32] {
+    keccak::hashv(&[b"PrivacyPool", &key("mint")]).to_bytes()
+}
+
+/// Pool commitment of the deposit with this nonce
+fn commitment(nonce: u64, value: u64, precommitment_index: u8) -> [u8; 32] {
+    let label = poseidon::compute_label(&scope(), nonce);
+    poseidon::compute_commitment(value, &label, &precommitment(precommitment_index))
+}
+
+/// Returns the same records on every call, like a dump that never grows
+struct Replay(Vec<TransactionRecord>);
+
+impl RecordSource for Replay {
+    fn records(&mut self, _pool: &[u8; 32], _until: Option<&str>) -> Result<Vec<TransactionRecord>, SourceError> {
+        Ok(self.0.clone())
+    }
+}
+
+fn indexed() -> (Indexer, MemoryStore) {
+    let mut indexer = Indexer::new(key("program"), key("pool"), None);
+    let mut store = MemoryStore::new();
+    let records = FileSource::parse(FIXTURE).unwrap();
+    assert_eq!(indexer.sync(&mut Replay(records), &mut store).unwrap(), 11);
+    (indexer, store)
+}
+
+#[test]
+fn test_fixture_rebuilds_trees() {
+    let (indexer, _) = indexed();
+    assert_eq!(indexer.scope(), Some(scope()));
+
+    let withdrawal_change = poseidon::compute_commitment(1_600_000, &poseidon::compute_label(&scope(), 2), &precommitment(20));
+    let mut expected = LeanIMT::new(32);
+    for leaf in [
+        commitment(1, 1_000_000, 1),
+        commitment(2, 2_000_000, 2),
+        commitment(3, 500_000, 3),
+        commitment(4, 750_000, 4),
+        withdrawal_change,
+    ] {
+        expected.insert(leaf).unwrap();
+    }
+    let state_tree = indexer.state_tree(StateTree::Pool).unwrap();
+    assert_eq!(state_tree.size(), 5);
+    assert_eq!(state_tree.root(), expected.root());
+
+    // Labels of the four pool deposits, then the shard deposit's; withdrawals don't add any
+    let shard_label = poseidon::compute_shard_label(&scope(), 0, 1);
+    let mut asp = LeanIMT::new(32);
+    for nonce in 1..=4 {
+        asp.insert(poseidon::compute_label(&scope(), nonce)).unwrap();
+    }
+    asp.insert(shard_label).unwrap();
+    assert_eq!(indexer.asp_tree().root(), asp.root());
+
+    let shard_tree = indexer.state_tree(StateTree::Shard(0)).unwrap();
+    assert_eq!(shard_tree.root(), poseidon::compute_commitment(300_000, &shard_label, &precommitment(5)));
+    assert_eq!(indexer.shards(), vec![0]);
+
+    // One root per direct deposit, one for the queue batch, one for the withdrawal
+    let roots = indexer.roots(StateTree::Pool);
+    assert_eq!(roots.len(), 4);
+    assert_eq!(roots[2].root, expected.root_at(4).unwrap());
+    assert_eq!(roots.last().unwrap().root, expected.root());
+
+    assert!(indexer.nullifier(&poseidon::hash_two(&[2u8; 32], &[2u8; 32])).is_some());
+    assert!(indexer.nullifier(&poseidon::hash_two(&[1u8; 32], &[1u8; 32])).is_some());
+    assert_eq!(indexer.spent_nullifiers(), 2);
+
+    let stats = indexer.stats();
+    assert_eq!((stats.deposits, stats.pending_deposits, stats.withdrawals, stats.ragequits), (5, 0, 1, 1));
+    assert_eq!(stats.total_deposited, 4_550_000);
+    assert_eq!(stats.total_withdrawn, 400_000);
+    assert_eq!(stats.total_ragequit, 1_000_000);
+    assert_eq!(stats.instructions, 11);
+}
+
+#[test]
+fn test_withdrawal_replayed_from_snarkjs_output() {
+    let (mut indexer, _) = indexed();
+    let fixture: Value = serde_json::from_str(REALISTIC_PROOF).unwrap();
+    let public_json = fixture["rawPublicSignals"].to_string();
+    let proof_data = snarkjs::withdraw_proof_from_snarkjs(&fixture["rawProof"].to_string(), &public_json).unwrap();
+    let signal = |i: usize| field::decimal_to_bytes(fixture["rawPublicSignals"][i].as_str().unwrap()).unwrap();
+
+    let instruction = PrivacyPoolInstruction::Withdraw {
+        withdrawal_data: WithdrawalData {
+            processooor: key("processooor"),
+            data: vec![],
+        },
+        proof_data,
+    };
+    indexer
+        .apply(&PoolTransaction {
+            signature: "withdrawal".into(),
+            slot: 200,
+            instruction_data: instruction.try_to_vec().unwrap(),
+            account_count: 3,
+        })
+        .unwrap();
+
+    // newCommitment, nullifier and withdrawnValue lead the snarkjs signals
+    let (tree, proof) = indexer.state_proof(&signal(0)).unwrap();
+    assert_eq!((tree, proof.leaf_index), (StateTree::Pool, 5));
+    assert_eq!(indexer.nullifier(&signal(1)), Some("withdrawal"));
+    assert_eq!(indexer.stats().total_withdrawn, 400_000 + 1_500_000_000);
+}
+
+#[test]
+fn test_reopen_from_store() {
+    let (indexer, mut store) = indexed();
+    let mut reopened = Indexer::open(key("program"), key("pool"), None, &store).unwrap();
+
+    assert_eq!(reopened.stats(), indexer.stats());
+    assert_eq!(reopened.roots(StateTree::Pool), indexer.roots(StateTree::Pool));
+    assert_eq!(reopened.asp_tree().root(), indexer.asp_tree().root());
+    assert_eq!(reopened.last_signature(), indexer.last_signature());
+
+    // Everything in the dump is already indexed
+    let records = FileSource::parse(FIXTURE).unwrap();
+    assert_eq!(reopened.sync(&mut Replay(records), &mut store).unwrap(), 0);
+}
+
+#[test]
+fn test_proofs_served_over_http_api() {
+    let (indexer, _) = indexed();
+    let hex = |bytes: &[u8; 32]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+
+    let queued = commitment(4, 750_000, 4);
+    let (tree, proof) = indexer.state_proof(&queued).unwrap();
+    assert_eq!(tree, StateTree::Pool);
+    assert_eq!(proof.leaf_index, 3);
+    assert_eq!(proof.compute_root(), indexer.state_tree(StateTree::Pool).unwrap().root());
+
+    let response = http::route(&indexer, "GET", &format!("/proofs/state/{}", hex(&queued)));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body["leaf_index"], 3);
+    assert_eq!(response.body["root"], hex(&proof.root));
+    assert_eq!(response.body["circuit_inputs"]["stateIndex"], "3");
+
+    let label = poseidon::compute_label(&scope(), 2);
+    let response = http::route(&indexer, "GET", &format!("/proofs/asp/{}", hex(&label)));
+    assert_eq!(response.body["tree"], "asp");
+    assert_eq!(response.body["root"], hex(&indexer.asp_tree().root()));
+
+    let response = http::route(&indexer, "GET", "/stats");
+    assert_eq!(response.body["state_tree"]["size"], 5);
+    assert_eq!(response.body["shards"][0]["size"], 1);
+    assert_eq!(response.body["counters"]["deposits"], 5);
+
+    let response = http::route(&indexer, "GET", "/shards/0/roots");
+    assert_eq!(response.body["roots"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_queue_events_must_match_replay() {
+    // Claim the second queued deposit landed at leaf 7
+    let mut tampered = Vec::new();
+    for line in FIXTURE.lines() {
+        let mut record: Value = serde_json::from_str(line).unwrap();
+        for log in record["meta"]["logMessages"].as_array_mut().unwrap() {
+            let text = log.as_str().unwrap();
+            if text.starts_with("Program data: ") && text.ends_with("AwAAAAAAAAA=") {
+                *log = Value::String(text.replace("AwAAAAAAAAA=", "BwAAAAAAAAA="));
+            }
+        }
+        tampered.push(serde_json::from_value(record).unwrap());
+    }
+
+    let mut indexer = Indexer::new(key("program"), key("pool"), None);
+    let error = indexer.sync(&mut Replay(tampered), &mut MemoryStore::new()).unwrap_err();
+    assert!(matches!(error, IndexError::QueueEventMismatch { .. }));
+}
+
+#[test]
+fn test_deposit_before_initialize_needs_scope() {
+    let records: Vec<TransactionRecord> = FileSource::parse(FIXTURE).unwrap().into_iter().skip(1).collect();
+
+    let mut indexer = Indexer::new(key("program"), key("pool"), None);
+    let error = indexer.sync(&mut Replay(records.clone()), &mut MemoryStore::new()).unwrap_err();
+    assert!(matches!(error, IndexError::UnknownScope { .. }));
+
+    let mut indexer = Indexer::new(key("program"), key("pool"), Some(scope()));
+    assert_eq!(indexer.sync(&mut Replay(records), &mut MemoryStore::new()).unwrap(), 10);
+}
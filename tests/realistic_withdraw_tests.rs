@@ -0,0 +1,442 @@
+//! Replays the scenario in `scripts/realistic-withdraw-proof.json`
+//! (five deposits, partial withdrawal from the third) through the program's code paths, and
+//! `fixtures/withdraw/program-withdraw.json` through deposits and a withdrawal on a test pool.
+#![cfg(feature = "client")]
+
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+use serde_json::Value;
+
+use solana_privacy_pools::{
+    client::snarkjs,
+    crypto::{field, merkle_tree::LeanIMT},
+    instructions::{PrivacyPoolInstruction, RagequitProofData, WithdrawProofData, WithdrawalData},
+    state::LeanIMTStateZC,
+    BorshDeserialize, BorshSerialize,
+};
+
+const REALISTIC_PROOF: &str = include_str!("../scripts/realistic-withdraw-proof.json");
+
+fn fixture() -> Value {
+    serde_json::from_str(REALISTIC_PROOF).unwrap()
+}
+
+fn deposit_commitments(fixture: &Value) -> Vec<[u8; 32]> {
+    fixture["deposits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|deposit| field::decimal_to_bytes(deposit["commitment"].as_str().unwrap()).unwrap())
+        .collect()
+}
+
+fn realistic_proof(fixture: &Value) -> WithdrawProofData {
+    snarkjs::withdraw_proof_from_snarkjs(
+        &fixture["rawProof"].to_string(),
+        &fixture["rawPublicSignals"].to_string(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_replay_realistic_deposits() {
+    let fixture = fixture();
+    let commitments = deposit_commitments(&fixture);
+    assert_eq!(commitments.len(), 5);
+
+    let mut buffer = vec![0u8; LeanIMTStateZC::LEN];
+    let state_tree = unsafe { &mut *(buffer.as_mut_ptr() as *mut LeanIMTStateZC) };
+    state_tree.initialize();
+    let mut off_chain = LeanIMT::new(32);
+
+    for commitment in &commitments {
+        state_tree.insert(*commitment).unwrap();
+        off_chain.insert(*commitment).unwrap();
+    }
+
+    let depth = state_tree.depth;
+    assert_eq!(depth as u64, fixture["trees"]["stateDepth"].as_u64().unwrap());
+    assert_eq!(state_tree.root(), off_chain.root());
+
+    // The withdrawn note is the third deposit
+    let from_deposit = fixture["withdrawal"]["fromDeposit"].as_u64().unwrap();
+    let proof = off_chain.generate_circuit_proof(from_deposit, 20).unwrap();
+    assert_eq!(proof.leaf, commitments[from_deposit as usize]);
+    assert_eq!(proof.compute_root(), off_chain.root());
+}
+
+#[test]
+fn test_realistic_proof_through_withdraw_parser() {
+    let fixture = fixture();
+    let proof = realistic_proof(&fixture);
+    let processooor = Pubkey::from([9u8; 32]);
+    let data = PrivacyPoolInstruction::Withdraw {
+        withdrawal_data: WithdrawalData {
+            processooor,
+            data: b"relay".to_vec(),
+        },
+        proof_data: realistic_proof(&fixture),
+    }
+    .try_to_vec()
+    .unwrap();
+
+    match PrivacyPoolInstruction::try_from_slice(&data).unwrap() {
+        PrivacyPoolInstruction::Withdraw { withdrawal_data, proof_data } => {
+            assert_eq!(withdrawal_data.processooor, processooor);
+            assert_eq!(withdrawal_data.data, b"relay".to_vec());
+            assert_eq!(proof_data.proof_a, proof.proof_a);
+            assert_eq!(proof_data.proof_b, proof.proof_b);
+            assert_eq!(proof_data.proof_c, proof.proof_c);
+            assert_eq!(proof_data.public_signals, proof.public_signals);
+        }
+        _ => panic!("Expected Withdraw instruction"),
+    }
+
+    // snarkjs order: newCommitmentHash, existingNullifierHash, withdrawnValue, ...
+    let signals: Vec<[u8; 32]> = fixture["rawPublicSignals"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| field::decimal_to_bytes(s.as_str().unwrap()).unwrap())
+        .collect();
+    assert_eq!(proof.public_signals, signals);
+    let withdrawn: u64 = fixture["withdrawal"]["withdrawnValue"].as_str().unwrap().parse().unwrap();
+    assert_eq!(u64::from_le_bytes(signals[2][..8].try_into().unwrap()), withdrawn);
+}
+
+/// Pool and nullifier accounts driven through the program entrypoint
+#[cfg(feature = "test-utils")]
+mod program {
+    use super::*;
+    use solana_privacy_pools::{
+        client::{
+            accounts::{NullifierAccount, PoolAccount},
+            note::Note,
+            preflight::{preflight_withdraw, PoolSnapshot, PreflightError},
+        },
+        crypto::poseidon,
+        instructions::clock,
+        state::{DepositorStateZC, NullifierStateZC, PoolStateLeanIMT},
+        utils::{process_test_instruction, TestAccount},
+    };
+
+    pub const PROGRAM_ID: Pubkey = [1u8; 32];
+    pub const SLOT: u64 = 500;
+
+    pub fn initialized_pool(authority: &mut TestAccount) -> TestAccount {
+        let mut pool = TestAccount::new([4u8; 32], PROGRAM_ID, PoolStateLeanIMT::space(8));
+        let initialize = PrivacyPoolInstruction::InitializePool {
+            entrypoint_authority: authority.key,
+            max_tree_depth: 20,
+            asset_mint: [3u8; 32],
+            root_history_capacity: 8,
+            root_expiry_slots: 0,
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, authority], &initialize).unwrap();
+        clock::set_host_slot(SLOT);
+        pool
+    }
+
+    pub fn withdraw(
+        pool: &mut TestAccount,
+        processooor: &mut TestAccount,
+        nullifier: &mut TestAccount,
+        withdrawal_data: WithdrawalData,
+        proof_data: WithdrawProofData,
+    ) -> pinocchio::ProgramResult {
+        let instruction = PrivacyPoolInstruction::Withdraw { withdrawal_data, proof_data };
+        process_test_instruction(&PROGRAM_ID, &mut [pool, processooor, nullifier], &instruction)
+    }
+
+    pub fn nullifier_account() -> TestAccount {
+        TestAccount::new([8u8; 32], PROGRAM_ID, NullifierStateZC::LEN)
+    }
+
+    pub fn nullifier_state(account: &TestAccount) -> NullifierAccount {
+        NullifierAccount::decode(&account.data).unwrap()
+    }
+
+    pub fn pool_state(account: &TestAccount) -> PoolAccount {
+        PoolAccount::decode(&account.data).unwrap()
+    }
+
+    /// `fixtures/withdraw/program-withdraw.json`: deposits into `initialized_pool` and a proof
+    /// for a withdrawal from one of them, made with the context of the fixture's withdrawal data
+    /// under that pool's scope
+    const PROGRAM_WITHDRAW: &str = include_str!("fixtures/withdraw/program-withdraw.json");
+
+    fn program_withdraw() -> Value {
+        serde_json::from_str(PROGRAM_WITHDRAW).unwrap()
+    }
+
+    fn decimal(value: &Value) -> [u8; 32] {
+        field::decimal_to_bytes(value.as_str().unwrap()).unwrap()
+    }
+
+    fn pubkey(value: &Value) -> Pubkey {
+        bs58::decode(value.as_str().unwrap()).into_vec().unwrap().try_into().unwrap()
+    }
+
+    fn byte_array<const N: usize>(value: &Value) -> [u8; N] {
+        let bytes: Vec<u8> = value.as_array().unwrap().iter().map(|b| b.as_u64().unwrap() as u8).collect();
+        bytes.try_into().unwrap()
+    }
+
+    fn fixture_withdrawal(fixture: &Value) -> WithdrawalData {
+        WithdrawalData {
+            processooor: pubkey(&fixture["withdrawal"]["processooor"]),
+            data: fixture["withdrawal"]["data"].as_str().unwrap().as_bytes().to_vec(),
+        }
+    }
+
+    fn fixture_proof(fixture: &Value) -> WithdrawProofData {
+        WithdrawProofData {
+            proof_a: byte_array(&fixture["proof"]["proofA"]),
+            proof_b: byte_array(&fixture["proof"]["proofB"]),
+            proof_c: byte_array(&fixture["proof"]["proofC"]),
+            public_signals: fixture["publicSignals"].as_array().unwrap().iter().map(byte_array).collect(),
+        }
+    }
+
+    /// Deposit the fixture's notes through the program, in order; returns them with the
+    /// matching off-chain state and ASP trees
+    fn deposit_fixture_notes(pool: &mut TestAccount, fixture: &Value) -> (Vec<Note>, LeanIMT, LeanIMT) {
+        let scope = pool_state(pool).scope;
+        let mut state_tree = LeanIMT::new(32);
+        let mut asp_tree = LeanIMT::new(32);
+        let mut notes = Vec::new();
+        for (i, deposit) in fixture["deposits"].as_array().unwrap().iter().enumerate() {
+            let label = poseidon::compute_label(&scope, i as u64 + 1);
+            let value = deposit["value"].as_str().unwrap().parse().unwrap();
+            let note = Note::new(value, label, decimal(&deposit["nullifier"]), decimal(&deposit["secret"]));
+
+            let mut depositor = TestAccount::signer(pubkey(&deposit["depositor"]));
+            let mut depositor_account = TestAccount::new([30 + i as u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+            let instruction = PrivacyPoolInstruction::Deposit {
+                depositor: depositor.key,
+                value: note.value,
+                precommitment_hash: note.precommitment(),
+            };
+            process_test_instruction(
+                &PROGRAM_ID,
+                &mut [pool, &mut depositor_account, &mut depositor],
+                &instruction,
+            )
+            .unwrap();
+            assert_eq!(pool_state(pool).state_tree.leaves[i], note.commitment());
+
+            state_tree.insert(note.commitment()).unwrap();
+            asp_tree.insert(label).unwrap();
+            notes.push(note);
+        }
+        (notes, state_tree, asp_tree)
+    }
+
+    #[test]
+    fn test_fixture_withdraw_through_program() {
+        let fixture = program_withdraw();
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        let scope = pool_state(&pool).scope;
+
+        let (notes, mut state_tree, asp_tree) = deposit_fixture_notes(&mut pool, &fixture);
+        for (deposit, note) in fixture["deposits"].as_array().unwrap().iter().zip(&notes) {
+            assert_eq!(note.label, decimal(&deposit["label"]));
+            assert_eq!(note.commitment(), decimal(&deposit["commitment"]));
+        }
+
+        let proof = fixture_proof(&fixture);
+        let before = pool_state(&pool);
+        assert_eq!(before.state_tree.root, state_tree.root());
+        assert_eq!(before.asp_tree.root, asp_tree.root());
+        assert_eq!(proof.state_root(), state_tree.root());
+        assert_eq!(proof.asp_root(), asp_tree.root());
+        assert_eq!(proof.context(), poseidon::compute_context(&fixture_withdrawal(&fixture), &scope));
+
+        // Relayed with other data, the context no longer matches and nothing changes
+        let mut processooor = TestAccount::signer(fixture_withdrawal(&fixture).processooor);
+        let mut nullifier = nullifier_account();
+        let tampered = WithdrawalData {
+            data: b"other".to_vec(),
+            ..fixture_withdrawal(&fixture)
+        };
+        let result = withdraw(&mut pool, &mut processooor, &mut nullifier, tampered, fixture_proof(&fixture));
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+        assert_eq!(nullifier.data, vec![0u8; NullifierStateZC::LEN]);
+        assert_eq!(pool_state(&pool), before);
+
+        withdraw(&mut pool, &mut processooor, &mut nullifier, fixture_withdrawal(&fixture), proof).unwrap();
+
+        let withdrawal = &fixture["withdrawal"];
+        let note = notes[withdrawal["fromDeposit"].as_u64().unwrap() as usize];
+        let spent = nullifier_state(&nullifier);
+        assert!(spent.is_spent);
+        assert_eq!(spent.nullifier_hash, note.nullifier_hash());
+
+        // The new leaf is the change note, under the withdrawn note's label
+        let withdrawn: u64 = withdrawal["withdrawnValue"].as_str().unwrap().parse().unwrap();
+        let change = Note::new(
+            note.value - withdrawn,
+            note.label,
+            decimal(&withdrawal["newNullifier"]),
+            decimal(&withdrawal["newSecret"]),
+        );
+        assert_eq!(change.commitment(), decimal(&withdrawal["newCommitment"]));
+
+        state_tree.insert(change.commitment()).unwrap();
+        let after = pool_state(&pool);
+        assert_eq!(after.state_tree.size, notes.len() as u64 + 1);
+        assert_eq!(after.state_tree.leaves[notes.len()], change.commitment());
+        assert_eq!(after.state_tree.root, state_tree.root());
+        assert_eq!(after.root_history.roots[0].root, state_tree.root());
+        assert_eq!(after.root_history.roots[0].slot, SLOT);
+        assert_eq!(after.asp_tree, before.asp_tree);
+    }
+
+    #[test]
+    fn test_fixture_withdraw_preflight() {
+        let fixture = program_withdraw();
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        deposit_fixture_notes(&mut pool, &fixture);
+
+        let snapshot = PoolSnapshot::from_account_data(&pool.data).unwrap();
+        let withdrawal_data = fixture_withdrawal(&fixture);
+        let proof = fixture_proof(&fixture);
+        assert_eq!(preflight_withdraw(&snapshot, &withdrawal_data, &proof, None, SLOT), Ok(()));
+
+        // A deposit after the proof was made moves the ASP root on
+        let mut depositor = TestAccount::signer([23u8; 32]);
+        let mut depositor_account = TestAccount::new([40u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let deposit = PrivacyPoolInstruction::Deposit {
+            depositor: depositor.key,
+            value: 1_000,
+            precommitment_hash: [5u8; 32],
+        };
+        process_test_instruction(&PROGRAM_ID, &mut [&mut pool, &mut depositor_account, &mut depositor], &deposit)
+            .unwrap();
+        let snapshot = PoolSnapshot::from_account_data(&pool.data).unwrap();
+        assert_eq!(
+            preflight_withdraw(&snapshot, &withdrawal_data, &proof, None, SLOT),
+            Err(PreflightError::UnknownAspRoot { root: proof.asp_root() })
+        );
+
+        // The state root is still in the history, but the program turns the proof away too
+        let before = pool_state(&pool);
+        let mut processooor = TestAccount::signer(withdrawal_data.processooor);
+        let mut nullifier = nullifier_account();
+        let result = withdraw(&mut pool, &mut processooor, &mut nullifier, withdrawal_data, proof);
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+        assert_eq!(nullifier.data, vec![0u8; NullifierStateZC::LEN]);
+        assert_eq!(pool_state(&pool), before);
+    }
+
+    #[test]
+    fn test_short_public_signals_are_rejected() {
+        let fixture = fixture();
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        let before = pool_state(&pool);
+
+        // Fewer signals than the circuit has used to panic in the accessors
+        let mut processooor = TestAccount::signer([9u8; 32]);
+        let mut nullifier = nullifier_account();
+        let mut proof = realistic_proof(&fixture);
+        proof.public_signals.truncate(3);
+        let withdrawal_data = WithdrawalData {
+            processooor: processooor.key,
+            data: b"relay".to_vec(),
+        };
+        let result = withdraw(&mut pool, &mut processooor, &mut nullifier, withdrawal_data, proof);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        let ragequit = PrivacyPoolInstruction::Ragequit {
+            proof_data: RagequitProofData {
+                proof_a: [0u8; 64],
+                proof_b: [0u8; 128],
+                proof_c: [0u8; 64],
+                public_signals: vec![[0u8; 32]],
+            },
+        };
+        let mut depositor = TestAccount::new([6u8; 32], PROGRAM_ID, DepositorStateZC::LEN);
+        let result = process_test_instruction(
+            &PROGRAM_ID,
+            &mut [&mut pool, &mut depositor, &mut processooor, &mut nullifier],
+            &ragequit,
+        );
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        assert_eq!(nullifier.data, vec![0u8; NullifierStateZC::LEN]);
+        assert_eq!(pool_state(&pool), before);
+    }
+
+    /// Rebuilds the fixture from its keys, secrets and amounts with the checked-in withdraw
+    /// artifacts and compares it with the checked-in copy; `UPDATE_FIXTURES=1` writes it instead
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_program_withdraw_fixture_is_current() {
+        use serde_json::json;
+        use solana_privacy_pools::client::{prover::WithdrawProver, witness::WithdrawWitnessInputs};
+        use std::path::Path;
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut fixture = program_withdraw();
+        let mut authority = TestAccount::signer([2u8; 32]);
+        let mut pool = initialized_pool(&mut authority);
+        let scope = pool_state(&pool).scope;
+
+        let (notes, state_tree, asp_tree) = deposit_fixture_notes(&mut pool, &fixture);
+        for (deposit, note) in fixture["deposits"].as_array_mut().unwrap().iter_mut().zip(&notes) {
+            deposit["label"] = json!(field::bytes_to_decimal(&note.label));
+            deposit["commitment"] = json!(field::bytes_to_decimal(&note.commitment()));
+        }
+
+        let withdrawal = &fixture["withdrawal"];
+        let context = poseidon::compute_context(&fixture_withdrawal(&fixture), &scope);
+        let inputs = WithdrawWitnessInputs::builder(notes[withdrawal["fromDeposit"].as_u64().unwrap() as usize])
+            .withdrawn_value(withdrawal["withdrawnValue"].as_str().unwrap().parse().unwrap())
+            .context(context)
+            .new_note_secrets(decimal(&withdrawal["newNullifier"]), decimal(&withdrawal["newSecret"]))
+            .state_tree(&state_tree)
+            .unwrap()
+            .asp_tree(&asp_tree)
+            .unwrap()
+            .build()
+            .unwrap();
+        let proof = WithdrawProver::new(
+            root.join("trusted-setup/final-keys/withdraw_final.zkey"),
+            root.join("build/withdraw/withdraw.r1cs"),
+            root.join("build/withdraw/withdraw_js/withdraw.wasm"),
+        )
+        .unwrap()
+        .prove(&inputs)
+        .unwrap();
+
+        fixture["withdrawal"]["context"] = json!(field::bytes_to_decimal(&context));
+        fixture["withdrawal"]["newCommitment"] = json!(field::bytes_to_decimal(&inputs.new_commitment()));
+        fixture["trees"] = json!({
+            "stateRoot": field::bytes_to_decimal(&state_tree.root()),
+            "stateDepth": state_tree.depth(),
+            "aspRoot": field::bytes_to_decimal(&asp_tree.root()),
+            "aspDepth": asp_tree.depth(),
+        });
+
+        if std::env::var_os("UPDATE_FIXTURES").is_some() {
+            fixture["proof"] = json!({
+                "proofA": proof.proof_a.to_vec(),
+                "proofB": proof.proof_b.to_vec(),
+                "proofC": proof.proof_c.to_vec(),
+            });
+            fixture["publicSignals"] = json!(proof.public_signals);
+            let path = root.join("tests/fixtures/withdraw/program-withdraw.json");
+            std::fs::write(path, serde_json::to_string_pretty(&fixture).unwrap() + "\n").unwrap();
+            return;
+        }
+
+        // Proofs are randomized, their public signals aren't
+        let checked_in = program_withdraw();
+        assert_eq!(proof.public_signals, fixture_proof(&checked_in).public_signals);
+        fixture["proof"] = checked_in["proof"].clone();
+        fixture["publicSignals"] = checked_in["publicSignals"].clone();
+        assert_eq!(fixture, checked_in, "stale fixture, rerun with UPDATE_FIXTURES=1");
+    }
+}
@@ -287,7 +287,7 @@ mod withdrawal_tests {
         
         // Set valid context
         let valid_context = poseidon::compute_context(&withdrawal_data, &pool_state.scope);
-        proof_data.public_signals[5] = valid_context;
+        proof_data.public_signals[7] = valid_context;
         
         // Add state root to known roots
         let mut updated_state = pool_state;
@@ -532,15 +532,71 @@ mod instruction_parsing_tests {
                 entrypoint_authority: parsed_authority,
                 max_tree_depth: parsed_depth,
                 asset_mint: parsed_mint,
+                root_history_capacity,
+                root_expiry_slots,
             } => {
                 assert_eq!(parsed_authority, entrypoint_authority);
                 assert_eq!(parsed_depth, max_tree_depth);
                 assert_eq!(parsed_mint, asset_mint);
+                // Root history settings default when omitted
+                assert_eq!(root_history_capacity, constants::ROOT_HISTORY_SIZE as u32);
+                assert_eq!(root_expiry_slots, 0);
             }
             _ => panic!("Wrong instruction type parsed"),
         }
     }
 
+    #[test]
+    fn test_initialize_pool_instruction_parsing_with_root_history() {
+        let mut instruction_data = vec![0u8]; // InitializePool discriminant
+        instruction_data.extend_from_slice(&[1u8; 32]);
+        instruction_data.push(20u8);
+        instruction_data.extend_from_slice(&[2u8; 32]);
+        instruction_data.extend_from_slice(&1024u32.to_le_bytes());
+        instruction_data.extend_from_slice(&150u64.to_le_bytes());
+        
+        match PrivacyPoolInstruction::try_from_slice(&instruction_data).unwrap() {
+            PrivacyPoolInstruction::InitializePool { root_history_capacity, root_expiry_slots, .. } => {
+                assert_eq!(root_history_capacity, 1024);
+                assert_eq!(root_expiry_slots, 150);
+            }
+            _ => panic!("Wrong instruction type parsed"),
+        }
+    }
+
+    #[test]
+    fn test_shard_deposit_instruction_parsing() {
+        let shard_index = 3u16;
+        let depositor = Pubkey::from([7u8; 32]);
+        let value = 5000u64;
+        let precommitment_hash = [9u8; 32];
+        
+        let mut instruction_data = vec![6u8]; // ShardDeposit discriminant
+        instruction_data.extend_from_slice(&shard_index.to_le_bytes());
+        instruction_data.extend_from_slice(depositor.as_ref());
+        instruction_data.extend_from_slice(&value.to_le_bytes());
+        instruction_data.extend_from_slice(&precommitment_hash);
+        
+        match PrivacyPoolInstruction::try_from_slice(&instruction_data).unwrap() {
+            PrivacyPoolInstruction::ShardDeposit {
+                shard_index: parsed_index,
+                depositor: parsed_depositor,
+                value: parsed_value,
+                precommitment_hash: parsed_precommitment,
+            } => {
+                assert_eq!(parsed_index, shard_index);
+                assert_eq!(parsed_depositor, depositor);
+                assert_eq!(parsed_value, value);
+                assert_eq!(parsed_precommitment, precommitment_hash);
+            }
+            _ => panic!("Wrong instruction type parsed"),
+        }
+        
+        // Truncated shard deposit must be rejected
+        let result = PrivacyPoolInstruction::try_from_slice(&instruction_data[..40]);
+        assert!(result.is_err(), "Should fail with insufficient data");
+    }
+
     #[test]
     fn test_instruction_parsing_with_invalid_data() {
         let invalid_instruction_data = vec![99u8]; // Invalid discriminant